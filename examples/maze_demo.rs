@@ -5,17 +5,18 @@
 //! Run with: cargo run --example maze_demo
 
 use std::fs;
-use train_trm::data::maze::Maze;
+use train_trm::data::maze::{maze_montage, Maze, RenderOptions};
 
 fn main() {
     println!("=== TRM Maze Solving Demonstration ===\n");
 
     // Generate different sized mazes
     let sizes = [(11, 11), (15, 15), (21, 21)];
+    let mut solved = Vec::new();
 
     for (idx, (width, height)) in sizes.iter().enumerate() {
         println!("Generating maze {}x{}...", width, height);
-        let mut maze = Maze::generate_random(*width, *height);
+        let mut maze = Maze::generate_random(*width, *height).expect("valid maze size");
 
         println!("Solving maze...");
         if maze.solve() {
@@ -41,11 +42,19 @@ fn main() {
             print_numerical_matrix(&maze);
 
             println!("\n{}\n", "=".repeat(60));
+            solved.push(maze);
         } else {
             println!("No solution found!\n");
         }
     }
 
+    // All mazes side by side in one raster image
+    let montage = maze_montage(&solved, solved.len(), &RenderOptions::default());
+    match montage.save("maze_montage.png") {
+        Ok(_) => println!("✓ Saved montage to maze_montage.png\n"),
+        Err(e) => eprintln!("✗ Error saving montage: {}", e),
+    }
+
     println!("=== Demonstration Complete ===");
     println!("\nOpen the generated .svg files in a web browser to see the");
     println!("visual representation of the mazes with solution paths!");
@@ -66,7 +66,9 @@ fn main() {
 
     // Train
     println!("Starting training...\n");
-    let metrics = trainer.train(&train_examples);
+    let metrics = trainer
+        .train(&train_examples)
+        .expect("copy task examples train without augmentation");
 
     // Evaluate after training
     let final_val_loss = trainer.evaluate(&val_examples);
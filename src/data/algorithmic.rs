@@ -0,0 +1,556 @@
+//! Algorithmic sequence tasks that need several reasoning steps
+//!
+//! Each task maps a sequence of tokens to another: sorting, reversal,
+//! multi-digit addition with carry, parity, and balanced-parenthesis checking.
+//! Tokens are encoded either as one value per position or one-hot, and
+//! validation is an exact match on the decoded target tokens.
+
+use super::problem::check_len;
+use super::{Task, TrainingExample, Validation};
+use crate::utils::{Result, TRMError};
+use ndarray::Array2;
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+
+/// Token for an opening parenthesis
+pub const OPEN: usize = 0;
+/// Token for a closing parenthesis
+pub const CLOSE: usize = 1;
+
+/// Which algorithm the task asks the model to carry out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlgorithmicKind {
+    /// Sort `length` digits in ascending order
+    Sort,
+    /// Reverse `length` digits
+    Reverse,
+    /// Add two `length`-digit numbers, most significant digit first
+    Addition,
+    /// Whether `length` bits contain an odd number of ones
+    Parity,
+    /// Whether `length` parentheses are balanced
+    Parentheses,
+}
+
+impl AlgorithmicKind {
+    /// Every kind, in declaration order
+    pub const ALL: [AlgorithmicKind; 5] = [
+        AlgorithmicKind::Sort,
+        AlgorithmicKind::Reverse,
+        AlgorithmicKind::Addition,
+        AlgorithmicKind::Parity,
+        AlgorithmicKind::Parentheses,
+    ];
+
+    /// Lowercase name, as accepted by `from_str`
+    pub fn name(self) -> &'static str {
+        match self {
+            AlgorithmicKind::Sort => "sort",
+            AlgorithmicKind::Reverse => "reverse",
+            AlgorithmicKind::Addition => "addition",
+            AlgorithmicKind::Parity => "parity",
+            AlgorithmicKind::Parentheses => "parentheses",
+        }
+    }
+
+    /// Number of distinct tokens in inputs and targets
+    pub fn vocab(self) -> usize {
+        match self {
+            AlgorithmicKind::Sort | AlgorithmicKind::Reverse | AlgorithmicKind::Addition => 10,
+            AlgorithmicKind::Parity | AlgorithmicKind::Parentheses => 2,
+        }
+    }
+
+    /// Input tokens for a task of the given length
+    pub fn input_len(self, length: usize) -> usize {
+        match self {
+            AlgorithmicKind::Addition => 2 * length,
+            _ => length,
+        }
+    }
+
+    /// Target tokens for a task of the given length
+    pub fn target_len(self, length: usize) -> usize {
+        match self {
+            AlgorithmicKind::Sort | AlgorithmicKind::Reverse => length,
+            AlgorithmicKind::Addition => length + 1,
+            AlgorithmicKind::Parity | AlgorithmicKind::Parentheses => 1,
+        }
+    }
+
+    /// Compute the target tokens for an input sequence
+    pub fn solve(self, input: &[usize]) -> Vec<usize> {
+        match self {
+            AlgorithmicKind::Sort => {
+                let mut sorted = input.to_vec();
+                sorted.sort_unstable();
+                sorted
+            }
+            AlgorithmicKind::Reverse => input.iter().rev().copied().collect(),
+            AlgorithmicKind::Addition => {
+                let (a, b) = input.split_at(input.len() / 2);
+                let mut sum = Vec::with_capacity(a.len() + 1);
+                let mut carry = 0;
+                for (x, y) in a.iter().rev().zip(b.iter().rev()) {
+                    let digit = x + y + carry;
+                    sum.push(digit % 10);
+                    carry = digit / 10;
+                }
+                sum.push(carry);
+                sum.reverse();
+                sum
+            }
+            AlgorithmicKind::Parity => vec![input.iter().sum::<usize>() % 2],
+            AlgorithmicKind::Parentheses => {
+                let mut depth = 0i64;
+                let mut balanced = true;
+                for &token in input {
+                    depth += if token == OPEN { 1 } else { -1 };
+                    balanced &= depth >= 0;
+                }
+                let balanced = balanced && depth == 0;
+                vec![usize::from(balanced)]
+            }
+        }
+    }
+
+    /// Draw a random input sequence
+    ///
+    /// Parentheses are balanced half of the time; other kinds draw tokens
+    /// uniformly.
+    pub fn sample<R: Rng>(self, length: usize, rng: &mut R) -> Vec<usize> {
+        match self {
+            AlgorithmicKind::Parentheses => {
+                let mut tokens = balanced_parentheses(length, rng);
+                if rng.gen_bool(0.5) {
+                    // Flipping one token changes the count, so it never balances
+                    let i = rng.gen_range(0..length);
+                    tokens[i] = 1 - tokens[i];
+                }
+                tokens
+            }
+            _ => (0..self.input_len(length))
+                .map(|_| rng.gen_range(0..self.vocab()))
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for AlgorithmicKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for AlgorithmicKind {
+    type Err = TRMError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        AlgorithmicKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == s.to_ascii_lowercase())
+            .ok_or_else(|| TRMError::InvalidData(format!("unknown algorithmic task: {:?}", s)))
+    }
+}
+
+/// A random balanced sequence of `length` parentheses (`length` must be even)
+fn balanced_parentheses<R: Rng>(length: usize, rng: &mut R) -> Vec<usize> {
+    let mut tokens = Vec::with_capacity(length);
+    let mut depth = 0;
+    for remaining in (1..=length).rev() {
+        let open = if depth == 0 {
+            true
+        } else if depth == remaining {
+            false
+        } else {
+            rng.gen_bool(0.5)
+        };
+        if open {
+            depth += 1;
+            tokens.push(OPEN);
+        } else {
+            depth -= 1;
+            tokens.push(CLOSE);
+        }
+    }
+    tokens
+}
+
+/// How tokens are turned into network values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TokenEncoding {
+    /// One value per token: the token id itself
+    #[default]
+    Token,
+    /// `vocab` channels per token
+    OneHot,
+}
+
+impl TokenEncoding {
+    /// Every encoding, in declaration order
+    pub const ALL: [TokenEncoding; 2] = [TokenEncoding::Token, TokenEncoding::OneHot];
+
+    /// Lowercase name, as accepted by `from_str`
+    pub fn name(self) -> &'static str {
+        match self {
+            TokenEncoding::Token => "token",
+            TokenEncoding::OneHot => "onehot",
+        }
+    }
+
+    /// Values per token
+    pub fn channels(self, vocab: usize) -> usize {
+        match self {
+            TokenEncoding::Token => 1,
+            TokenEncoding::OneHot => vocab,
+        }
+    }
+
+    /// Encode tokens as a `(1, len * channels)` row
+    pub fn encode(self, tokens: &[usize], vocab: usize) -> Array2<f32> {
+        let values: Vec<f32> = match self {
+            TokenEncoding::Token => tokens.iter().map(|&t| t as f32).collect(),
+            TokenEncoding::OneHot => tokens
+                .iter()
+                .flat_map(|&t| (0..vocab).map(move |v| if v == t { 1.0 } else { 0.0 }))
+                .collect(),
+        };
+        Array2::from_shape_vec((1, values.len()), values).unwrap()
+    }
+
+    /// Decode values back into tokens: nearest id for `Token`, argmax for `OneHot`
+    ///
+    /// Returns `None` if the length is not a whole number of tokens.
+    pub fn decode(self, values: &[f32], vocab: usize) -> Option<Vec<usize>> {
+        let channels = self.channels(vocab);
+        if values.len() % channels != 0 {
+            return None;
+        }
+        let tokens = match self {
+            TokenEncoding::Token => values
+                .iter()
+                .map(|v| v.round().clamp(0.0, (vocab - 1) as f32) as usize)
+                .collect(),
+            TokenEncoding::OneHot => values
+                .chunks(channels)
+                .map(|scores| {
+                    scores
+                        .iter()
+                        .enumerate()
+                        .max_by(|a, b| a.1.total_cmp(b.1))
+                        .map(|(token, _)| token)
+                        .unwrap_or(0)
+                })
+                .collect(),
+        };
+        Some(tokens)
+    }
+}
+
+impl fmt::Display for TokenEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for TokenEncoding {
+    type Err = TRMError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        TokenEncoding::ALL
+            .into_iter()
+            .find(|encoding| encoding.name() == s.to_ascii_lowercase())
+            .ok_or_else(|| TRMError::InvalidData(format!("unknown token encoding: {:?}", s)))
+    }
+}
+
+/// Generated examples of one algorithmic task
+pub struct AlgorithmicTask {
+    kind: AlgorithmicKind,
+    length: usize,
+    encoding: TokenEncoding,
+    sequences: Vec<(Vec<usize>, Vec<usize>)>,
+    examples: Vec<TrainingExample>,
+}
+
+impl AlgorithmicTask {
+    /// Create a task with `num_examples` random sequences of `length`
+    pub fn new(kind: AlgorithmicKind, num_examples: usize, length: usize) -> Result<Self> {
+        Self::with_rng(kind, num_examples, length, &mut rand::thread_rng())
+    }
+
+    /// Create a task drawing sequences from `rng`
+    ///
+    /// Fails if `length` is zero, or odd for parenthesis checking.
+    pub fn with_rng<R: Rng>(
+        kind: AlgorithmicKind,
+        num_examples: usize,
+        length: usize,
+        rng: &mut R,
+    ) -> Result<Self> {
+        if length == 0 {
+            return Err(TRMError::InvalidData(format!(
+                "{} task needs a positive length",
+                kind
+            )));
+        }
+        if kind == AlgorithmicKind::Parentheses && length % 2 != 0 {
+            return Err(TRMError::InvalidData(format!(
+                "parentheses task needs an even length, got {}",
+                length
+            )));
+        }
+
+        let sequences = (0..num_examples)
+            .map(|_| {
+                let input = kind.sample(length, rng);
+                let target = kind.solve(&input);
+                (input, target)
+            })
+            .collect();
+        let mut task = Self {
+            kind,
+            length,
+            encoding: TokenEncoding::default(),
+            sequences,
+            examples: Vec::new(),
+        };
+        task.encode_examples();
+        Ok(task)
+    }
+
+    /// Re-encode every example with `encoding`
+    pub fn with_encoding(mut self, encoding: TokenEncoding) -> Self {
+        self.encoding = encoding;
+        self.encode_examples();
+        self
+    }
+
+    fn encode_examples(&mut self) {
+        let vocab = self.kind.vocab();
+        self.examples = self
+            .sequences
+            .iter()
+            .map(|(input, target)| {
+                TrainingExample::new(
+                    self.encoding.encode(input, vocab),
+                    self.encoding.encode(target, vocab),
+                )
+            })
+            .collect();
+    }
+
+    /// The algorithm this task asks for
+    pub fn kind(&self) -> AlgorithmicKind {
+        self.kind
+    }
+
+    /// Configured sequence length
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Token encoding used for inputs and targets
+    pub fn encoding(&self) -> TokenEncoding {
+        self.encoding
+    }
+
+    /// Input and target tokens of every example
+    pub fn sequences(&self) -> &[(Vec<usize>, Vec<usize>)] {
+        &self.sequences
+    }
+
+    /// Get all training examples
+    pub fn examples(&self) -> &[TrainingExample] {
+        &self.examples
+    }
+
+    /// Split into training and validation sets
+    pub fn split(&self, train_ratio: f32) -> (Vec<TrainingExample>, Vec<TrainingExample>) {
+        let train_size = (self.examples.len() as f32 * train_ratio) as usize;
+        let train = self.examples[..train_size].to_vec();
+        let val = self.examples[train_size..].to_vec();
+        (train, val)
+    }
+
+    /// Decode a model output into target tokens
+    pub fn decode(&self, output: &Array2<f32>) -> Option<Vec<usize>> {
+        if output.len() != self.output_dim() {
+            return None;
+        }
+        let values: Vec<f32> = output.iter().cloned().collect();
+        self.encoding.decode(&values, self.kind.vocab())
+    }
+}
+
+impl Task for AlgorithmicTask {
+    fn examples(&self) -> &[TrainingExample] {
+        &self.examples
+    }
+
+    /// Exact match on the decoded tokens; the score is the fraction that match
+    fn validate_example(&self, index: usize, output: &Array2<f32>) -> Validation {
+        if let Some(invalid) = check_len(output, self.output_dim()) {
+            return invalid;
+        }
+        let expected = &self.sequences[index].1;
+        let predicted = self.decode(output).expect("length was checked");
+        let wrong = predicted
+            .iter()
+            .zip(expected)
+            .filter(|(p, e)| p != e)
+            .count();
+        let reason =
+            (wrong > 0).then(|| format!("{} of {} tokens are wrong", wrong, expected.len()));
+        Validation::check(1.0 - wrong as f32 / expected.len() as f32, reason)
+    }
+
+    fn input_dim(&self) -> usize {
+        self.kind.input_len(self.length) * self.encoding.channels(self.kind.vocab())
+    }
+
+    fn output_dim(&self) -> usize {
+        self.kind.target_len(self.length) * self.encoding.channels(self.kind.vocab())
+    }
+
+    /// Carries for addition; other kinds have no difficulty key
+    fn difficulty(&self, index: usize) -> Option<usize> {
+        (self.kind == AlgorithmicKind::Addition).then(|| {
+            let input = &self.sequences[index].0;
+            let (a, b) = input.split_at(input.len() / 2);
+            let mut carry = 0;
+            a.iter()
+                .rev()
+                .zip(b.iter().rev())
+                .filter(|(x, y)| {
+                    carry = (*x + *y + carry) / 10;
+                    carry == 1
+                })
+                .count()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_solvers() {
+        assert_eq!(AlgorithmicKind::Sort.solve(&[3, 1, 2, 1]), vec![1, 1, 2, 3]);
+        assert_eq!(AlgorithmicKind::Reverse.solve(&[3, 1, 2]), vec![2, 1, 3]);
+        // 958 + 067 = 1025: carries ripple through every digit
+        assert_eq!(
+            AlgorithmicKind::Addition.solve(&[9, 5, 8, 0, 6, 7]),
+            vec![1, 0, 2, 5]
+        );
+        assert_eq!(AlgorithmicKind::Parity.solve(&[1, 0, 1, 1]), vec![1]);
+        assert_eq!(AlgorithmicKind::Parity.solve(&[1, 0, 0, 1]), vec![0]);
+
+        let parens = |s: &str| -> Vec<usize> {
+            s.chars()
+                .map(|c| if c == '(' { OPEN } else { CLOSE })
+                .collect()
+        };
+        let balanced = |s: &str| AlgorithmicKind::Parentheses.solve(&parens(s));
+        assert_eq!(balanced("(()())"), vec![1]);
+        assert_eq!(balanced("())(()"), vec![0]);
+        assert_eq!(balanced("(((("), vec![0]);
+
+        let task = AlgorithmicTask {
+            kind: AlgorithmicKind::Addition,
+            length: 3,
+            encoding: TokenEncoding::Token,
+            sequences: vec![
+                (vec![9, 5, 8, 0, 6, 7], vec![1, 0, 2, 5]),
+                (vec![1, 2, 3, 4, 5, 6], vec![0, 5, 7, 9]),
+            ],
+            examples: Vec::new(),
+        };
+        assert_eq!(task.difficulty(0), Some(3));
+        assert_eq!(task.difficulty(1), Some(0));
+    }
+
+    #[test]
+    fn test_dimensions() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for kind in AlgorithmicKind::ALL {
+            let task = AlgorithmicTask::with_rng(kind, 5, 6, &mut rng).unwrap();
+            for encoding in TokenEncoding::ALL {
+                let task = AlgorithmicTask {
+                    sequences: task.sequences.clone(),
+                    examples: Vec::new(),
+                    ..task
+                }
+                .with_encoding(encoding);
+                let channels = encoding.channels(kind.vocab());
+                assert_eq!(task.input_dim(), kind.input_len(6) * channels);
+                assert_eq!(task.output_dim(), kind.target_len(6) * channels);
+                for example in task.examples() {
+                    assert_eq!(example.input.len(), task.input_dim());
+                    assert_eq!(example.target.len(), task.output_dim());
+                }
+            }
+            assert_eq!(kind.name().parse::<AlgorithmicKind>().unwrap(), kind);
+        }
+    }
+
+    #[test]
+    fn test_exact_match_validation() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for encoding in TokenEncoding::ALL {
+            let task = AlgorithmicTask::with_rng(AlgorithmicKind::Addition, 10, 4, &mut rng)
+                .unwrap()
+                .with_encoding(encoding);
+            for (index, example) in task.examples().iter().enumerate() {
+                // Small noise still decodes to the target
+                let noisy = example.target.mapv(|v| v + 0.2);
+                assert!(task.validate_example(index, &noisy).correct, "{}", encoding);
+
+                // One wrong digit fails
+                let mut tokens = task.sequences()[index].1.clone();
+                tokens[0] = (tokens[0] + 1) % 10;
+                let wrong = encoding.encode(&tokens, 10);
+                let result = task.validate_example(index, &wrong);
+                assert!(!result.correct, "{}", encoding);
+                assert_eq!(result.score, 4.0 / 5.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parentheses_sampling() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let task =
+            AlgorithmicTask::with_rng(AlgorithmicKind::Parentheses, 200, 8, &mut rng).unwrap();
+        let balanced = task
+            .sequences()
+            .iter()
+            .filter(|(_, target)| target[0] == 1)
+            .count();
+        assert!((60..140).contains(&balanced), "{} balanced", balanced);
+
+        assert!(AlgorithmicTask::new(AlgorithmicKind::Parentheses, 1, 7).is_err());
+        assert!(AlgorithmicTask::new(AlgorithmicKind::Sort, 1, 0).is_err());
+    }
+
+    #[test]
+    fn test_token_decoding() {
+        assert_eq!(
+            TokenEncoding::Token.decode(&[-0.7, 2.4, 12.0], 10),
+            Some(vec![0, 2, 9])
+        );
+        assert_eq!(
+            TokenEncoding::OneHot.decode(&[0.1, 0.9, 0.8, 0.2], 2),
+            Some(vec![1, 0])
+        );
+        assert_eq!(TokenEncoding::OneHot.decode(&[0.0; 3], 2), None);
+        assert_eq!(
+            "onehot".parse::<TokenEncoding>().unwrap(),
+            TokenEncoding::OneHot
+        );
+    }
+}
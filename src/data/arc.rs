@@ -0,0 +1,337 @@
+//! ARC-AGI style grid puzzles loaded from JSON task files
+//!
+//! Each task file holds `train` and `test` lists of `{"input": grid, "output": grid}`
+//! pairs, where a grid is a list of rows of colour indices 0-9.
+
+use super::problem::check_len;
+use super::{Task, TrainingExample, Validation};
+use crate::utils::{Result, TRMError};
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Number of distinct ARC colours
+pub const NUM_COLORS: u8 = 10;
+
+/// A variable-size grid of colour indices
+pub type ArcGrid = Vec<Vec<u8>>;
+
+/// One input/output demonstration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArcPair {
+    pub input: ArcGrid,
+    pub output: ArcGrid,
+}
+
+/// A single ARC task file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArcTask {
+    /// Task name (file stem)
+    #[serde(skip)]
+    pub name: String,
+    pub train: Vec<ArcPair>,
+    pub test: Vec<ArcPair>,
+}
+
+impl ArcTask {
+    /// Load a task from a JSON file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut task: ArcTask = serde_json::from_str(&fs::read_to_string(path)?)?;
+        task.name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(task)
+    }
+
+    /// Load every `.json` task in a directory, sorted by file name
+    pub fn load_dir<P: AsRef<Path>>(dir: P) -> Result<Vec<Self>> {
+        let mut paths: Vec<_> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        paths.iter().map(Self::load).collect()
+    }
+}
+
+/// Encodes variable-size grids into fixed-size tensors
+///
+/// Every cell of the `height x width` canvas gets two channels: the colour
+/// scaled to `[0, 1]` and a mask that is 1.0 inside the grid. Grids are
+/// anchored at the top-left corner and padded with zeros.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArcEncoder {
+    pub height: usize,
+    pub width: usize,
+}
+
+impl Default for ArcEncoder {
+    fn default() -> Self {
+        Self {
+            height: 30,
+            width: 30,
+        }
+    }
+}
+
+impl ArcEncoder {
+    /// Channels per canvas cell (colour, mask)
+    pub const CHANNELS: usize = 2;
+
+    /// Create an encoder with a square canvas
+    pub fn square(size: usize) -> Self {
+        Self {
+            height: size,
+            width: size,
+        }
+    }
+
+    /// Length of an encoded grid
+    pub fn dim(&self) -> usize {
+        self.height * self.width * Self::CHANNELS
+    }
+
+    /// Encode a grid; fails if it does not fit the canvas or has invalid colours
+    pub fn encode(&self, grid: &ArcGrid) -> Result<Vec<f32>> {
+        if grid.len() > self.height || grid.iter().any(|row| row.len() > self.width) {
+            return Err(TRMError::InvalidData(format!(
+                "grid does not fit the {}x{} canvas",
+                self.height, self.width
+            )));
+        }
+
+        let mut values = vec![0.0; self.dim()];
+        for (r, row) in grid.iter().enumerate() {
+            for (c, &color) in row.iter().enumerate() {
+                if color >= NUM_COLORS {
+                    return Err(TRMError::InvalidData(format!(
+                        "invalid colour {} at ({}, {})",
+                        color, r, c
+                    )));
+                }
+                let offset = (r * self.width + c) * Self::CHANNELS;
+                values[offset] = color as f32 / (NUM_COLORS - 1) as f32;
+                values[offset + 1] = 1.0;
+            }
+        }
+        Ok(values)
+    }
+
+    /// Decode a model output back into a grid
+    ///
+    /// The grid extent is the bounding box of cells whose mask is at least 0.5;
+    /// colours are rounded to the nearest valid index.
+    pub fn decode(&self, output: &[f32]) -> ArcGrid {
+        let mut height = 0;
+        let mut width = 0;
+        for r in 0..self.height {
+            for c in 0..self.width {
+                let offset = (r * self.width + c) * Self::CHANNELS;
+                if output.get(offset + 1).is_some_and(|&mask| mask >= 0.5) {
+                    height = height.max(r + 1);
+                    width = width.max(c + 1);
+                }
+            }
+        }
+
+        (0..height)
+            .map(|r| {
+                (0..width)
+                    .map(|c| {
+                        let value = output[(r * self.width + c) * Self::CHANNELS];
+                        (value * (NUM_COLORS - 1) as f32)
+                            .round()
+                            .clamp(0.0, (NUM_COLORS - 1) as f32) as u8
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Encode an input/output pair as a training example
+    pub fn encode_pair(&self, pair: &ArcPair) -> Result<TrainingExample> {
+        let input = Array2::from_shape_vec((1, self.dim()), self.encode(&pair.input)?)
+            .expect("encoded length matches dim");
+        let target = Array2::from_shape_vec((1, self.dim()), self.encode(&pair.output)?)
+            .expect("encoded length matches dim");
+        Ok(TrainingExample::new(input, target))
+    }
+}
+
+/// ARC exact-match metric: same shape and same colour in every cell
+pub fn exact_match(predicted: &ArcGrid, expected: &ArcGrid) -> bool {
+    predicted == expected
+}
+
+/// Encoded ARC pairs with their expected output grids
+pub struct ArcProblemSet {
+    encoder: ArcEncoder,
+    expected: Vec<ArcGrid>,
+    examples: Vec<TrainingExample>,
+}
+
+impl ArcProblemSet {
+    /// Build from the `train` pairs of the given tasks
+    pub fn train(tasks: &[ArcTask], encoder: ArcEncoder) -> Result<Self> {
+        Self::from_pairs(tasks.iter().flat_map(|t| &t.train), encoder)
+    }
+
+    /// Build from the `test` pairs of the given tasks
+    pub fn test(tasks: &[ArcTask], encoder: ArcEncoder) -> Result<Self> {
+        Self::from_pairs(tasks.iter().flat_map(|t| &t.test), encoder)
+    }
+
+    fn from_pairs<'a>(
+        pairs: impl Iterator<Item = &'a ArcPair>,
+        encoder: ArcEncoder,
+    ) -> Result<Self> {
+        let mut expected = Vec::new();
+        let mut examples = Vec::new();
+        for pair in pairs {
+            examples.push(encoder.encode_pair(pair)?);
+            expected.push(pair.output.clone());
+        }
+        Ok(Self {
+            encoder,
+            expected,
+            examples,
+        })
+    }
+
+    /// Get all training examples
+    pub fn examples(&self) -> &[TrainingExample] {
+        &self.examples
+    }
+
+    /// Expected output grids, in example order
+    pub fn expected(&self) -> &[ArcGrid] {
+        &self.expected
+    }
+
+    /// The encoder used for this set
+    pub fn encoder(&self) -> ArcEncoder {
+        self.encoder
+    }
+}
+
+impl Task for ArcProblemSet {
+    fn examples(&self) -> &[TrainingExample] {
+        &self.examples
+    }
+
+    /// Exact match on the decoded grid; the score is the fraction of matching cells
+    fn validate_example(&self, index: usize, output: &Array2<f32>) -> Validation {
+        if let Some(invalid) = check_len(output, self.encoder.dim()) {
+            return invalid;
+        }
+        let values: Vec<f32> = output.iter().cloned().collect();
+        let predicted = self.encoder.decode(&values);
+        let expected = &self.expected[index];
+        if exact_match(&predicted, expected) {
+            return Validation::correct();
+        }
+
+        let size = |grid: &ArcGrid| (grid.len(), grid.first().map_or(0, Vec::len));
+        if size(&predicted) != size(expected) {
+            let ((h, w), (eh, ew)) = (size(&predicted), size(expected));
+            return Validation::incorrect(
+                0.0,
+                format!("grid is {}x{}, expected {}x{}", h, w, eh, ew),
+            );
+        }
+        let cells = expected.iter().flatten().count();
+        let wrong = predicted
+            .iter()
+            .flatten()
+            .zip(expected.iter().flatten())
+            .filter(|(p, e)| p != e)
+            .count();
+        Validation::incorrect(
+            1.0 - wrong as f32 / cells as f32,
+            format!("{} of {} cells differ", wrong, cells),
+        )
+    }
+
+    fn input_dim(&self) -> usize {
+        self.encoder.dim()
+    }
+
+    fn output_dim(&self) -> usize {
+        self.encoder.dim()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TASK_JSON: &str = r#"{
+        "train": [
+            {"input": [[0, 1], [1, 0]], "output": [[1, 0], [0, 1]]},
+            {"input": [[2, 0, 0]], "output": [[0, 2, 2]]}
+        ],
+        "test": [
+            {"input": [[3, 0], [0, 0]], "output": [[0, 3], [3, 3]]}
+        ]
+    }"#;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("train-trm-arc-{}", name));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_load_dir() {
+        let dir = temp_dir("load");
+        fs::write(dir.join("b_task.json"), TASK_JSON).unwrap();
+        fs::write(dir.join("a_task.json"), TASK_JSON).unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let tasks = ArcTask::load_dir(&dir).unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].name, "a_task");
+        assert_eq!(tasks[0].train.len(), 2);
+        assert_eq!(tasks[0].test[0].output, vec![vec![0, 3], vec![3, 3]]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let encoder = ArcEncoder::square(4);
+        let grid = vec![vec![0, 9, 4], vec![5, 0, 1]];
+
+        let encoded = encoder.encode(&grid).unwrap();
+        assert_eq!(encoded.len(), 32);
+        assert_eq!(encoder.decode(&encoded), grid);
+    }
+
+    #[test]
+    fn test_encode_rejects_invalid_grids() {
+        let encoder = ArcEncoder::square(2);
+        assert!(encoder.encode(&vec![vec![0, 0, 0]]).is_err());
+        assert!(encoder.encode(&vec![vec![10]]).is_err());
+    }
+
+    #[test]
+    fn test_problem_set_exact_match() {
+        let mut task: ArcTask = serde_json::from_str(TASK_JSON).unwrap();
+        task.name = "sample".to_string();
+        let tasks = vec![task];
+
+        let train = ArcProblemSet::train(&tasks, ArcEncoder::square(3)).unwrap();
+        let test = ArcProblemSet::test(&tasks, ArcEncoder::square(3)).unwrap();
+        assert_eq!(train.examples().len(), 2);
+        assert_eq!(test.examples().len(), 1);
+        assert_eq!(test.input_dim(), 18);
+
+        let example = &test.examples()[0];
+        assert!(test.validate_example(0, &example.target).correct);
+        // Predicting the input grid is not an exact match
+        assert!(!test.validate_example(0, &example.input).correct);
+    }
+}
@@ -0,0 +1,429 @@
+//! Symmetry augmentation for grid-encoded examples
+//!
+//! A [`GridTransform`] permutes the cells of a grid and the channels within
+//! each cell, applying the same permutation to input and target so the
+//! example stays consistent. A [`Symmetry`] describes which transforms are
+//! valid for a task and samples them.
+
+use super::dataset::{example_rng, Dataset};
+use super::sudoku;
+use super::TrainingExample;
+use ndarray::Array2;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::cell::Cell;
+
+/// A permutation of grid cells and per-cell channels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridTransform {
+    /// Source cell for each destination cell
+    cells: Vec<usize>,
+    /// Source input channel for each destination input channel
+    input_channels: Vec<usize>,
+    /// Source target channel for each destination target channel
+    target_channels: Vec<usize>,
+}
+
+impl GridTransform {
+    /// The transform that leaves examples unchanged
+    pub fn identity(cells: usize, input_channels: usize, target_channels: usize) -> Self {
+        Self {
+            cells: (0..cells).collect(),
+            input_channels: (0..input_channels).collect(),
+            target_channels: (0..target_channels).collect(),
+        }
+    }
+
+    /// Whether this transform leaves examples unchanged
+    pub fn is_identity(&self) -> bool {
+        let sorted = |perm: &[usize]| perm.iter().enumerate().all(|(i, &p)| i == p);
+        sorted(&self.cells) && sorted(&self.input_channels) && sorted(&self.target_channels)
+    }
+
+    /// Whether the example has the input and target sizes this transform expects
+    pub fn matches(&self, example: &TrainingExample) -> bool {
+        example.input.len() == self.cells.len() * self.input_channels.len()
+            && example.target.len() == self.cells.len() * self.target_channels.len()
+    }
+
+    /// Apply the transform to an example
+    ///
+    /// # Panics
+    ///
+    /// Panics if the example does not match the transform's grid size
+    /// (see [`GridTransform::matches`]).
+    pub fn apply(&self, example: &TrainingExample) -> TrainingExample {
+        assert!(
+            self.matches(example),
+            "example of size {}/{} does not match a {}-cell grid with {}/{} channels",
+            example.input.len(),
+            example.target.len(),
+            self.cells.len(),
+            self.input_channels.len(),
+            self.target_channels.len()
+        );
+        TrainingExample::new(
+            self.permute(&example.input, &self.input_channels),
+            self.permute(&example.target, &self.target_channels),
+        )
+    }
+
+    fn permute(&self, array: &Array2<f32>, channels: &[usize]) -> Array2<f32> {
+        let source: Vec<f32> = array.iter().cloned().collect();
+        let source = &source;
+        let width = channels.len();
+        let values = self
+            .cells
+            .iter()
+            .flat_map(|&cell| channels.iter().map(move |&ch| source[cell * width + ch]))
+            .collect();
+        Array2::from_shape_vec(array.raw_dim(), values).expect("permutation preserves size")
+    }
+}
+
+/// The symmetries of a grid task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Symmetry {
+    /// Rotations and reflections of a `height x width` grid
+    ///
+    /// Square grids have all eight; rectangular grids only the four that keep
+    /// the shape (identity, both mirrors and the half turn).
+    Dihedral {
+        height: usize,
+        width: usize,
+        input_channels: usize,
+        target_channels: usize,
+    },
+    /// Sudoku: digit relabelling, band and stack permutations, row and
+    /// column permutations within them, and transposition
+    Sudoku,
+}
+
+impl Symmetry {
+    /// Rotations and reflections of a maze encoded one value per cell
+    pub fn maze(height: usize, width: usize) -> Self {
+        Symmetry::Dihedral {
+            height,
+            width,
+            input_channels: 1,
+            target_channels: 1,
+        }
+    }
+
+    /// Number of transforms in the group, if small enough to enumerate
+    pub fn num_transforms(&self) -> Option<usize> {
+        match *self {
+            Symmetry::Dihedral { height, width, .. } => Some(if height == width { 8 } else { 4 }),
+            Symmetry::Sudoku => None,
+        }
+    }
+
+    /// Every dihedral transform, identity first; empty for Sudoku
+    pub fn transforms(&self) -> Vec<GridTransform> {
+        (0..self.num_transforms().unwrap_or(0))
+            .map(|index| self.dihedral(index))
+            .collect()
+    }
+
+    /// Sample a transform uniformly from the group
+    pub fn sample(&self, rng: &mut impl Rng) -> GridTransform {
+        match self.num_transforms() {
+            Some(n) => self.dihedral(rng.gen_range(0..n)),
+            None => sample_sudoku(rng),
+        }
+    }
+
+    /// Whether the example has the size this symmetry expects
+    pub fn matches(&self, example: &TrainingExample) -> bool {
+        let (cells, input_channels, target_channels) = match *self {
+            Symmetry::Dihedral {
+                height,
+                width,
+                input_channels,
+                target_channels,
+            } => (height * width, input_channels, target_channels),
+            Symmetry::Sudoku => (
+                sudoku::CELLS,
+                sudoku::INPUT_CHANNELS,
+                sudoku::OUTPUT_CHANNELS,
+            ),
+        };
+        example.input.len() == cells * input_channels
+            && example.target.len() == cells * target_channels
+    }
+
+    /// The `index`-th dihedral transform: bits select column flip, row flip
+    /// and transpose
+    fn dihedral(&self, index: usize) -> GridTransform {
+        let Symmetry::Dihedral {
+            height,
+            width,
+            input_channels,
+            target_channels,
+        } = *self
+        else {
+            unreachable!("only dihedral symmetries are enumerated");
+        };
+        let (flip_cols, flip_rows, transpose) = (index & 1 != 0, index & 2 != 0, index & 4 != 0);
+
+        let cells = (0..height * width)
+            .map(|cell| {
+                let (row, col) = (cell / width, cell % width);
+                let row = if flip_rows { height - 1 - row } else { row };
+                let col = if flip_cols { width - 1 - col } else { col };
+                if transpose {
+                    col * width + row
+                } else {
+                    row * width + col
+                }
+            })
+            .collect();
+
+        GridTransform {
+            cells,
+            input_channels: (0..input_channels).collect(),
+            target_channels: (0..target_channels).collect(),
+        }
+    }
+}
+
+fn sample_sudoku(rng: &mut impl Rng) -> GridTransform {
+    let rows = shuffled_lines(rng);
+    let cols = shuffled_lines(rng);
+    let transpose = rng.gen_bool(0.5);
+
+    let cells = (0..sudoku::CELLS)
+        .map(|cell| {
+            let (row, col) = (cell / 9, cell % 9);
+            let (row, col) = if transpose { (col, row) } else { (row, col) };
+            rows[row] * 9 + cols[col]
+        })
+        .collect();
+
+    let mut digits: Vec<usize> = (0..9).collect();
+    digits.shuffle(rng);
+
+    // Input channel 0 marks empty cells; channels 1..=9 are the digits
+    let input_channels = std::iter::once(0)
+        .chain(digits.iter().map(|d| d + 1))
+        .collect();
+
+    GridTransform {
+        cells,
+        input_channels,
+        target_channels: digits,
+    }
+}
+
+/// Map 9 rows (or columns) by shuffling the three bands and the lines within each
+fn shuffled_lines(rng: &mut impl Rng) -> Vec<usize> {
+    let mut bands = [0, 1, 2];
+    bands.shuffle(rng);
+    let mut map = Vec::with_capacity(9);
+    for &band in &bands {
+        let mut lines = [0, 1, 2];
+        lines.shuffle(rng);
+        map.extend(lines.iter().map(|line| band * 3 + line));
+    }
+    map
+}
+
+/// How the trainer applies augmentation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AugmentationMode {
+    /// Add this many augmented copies of every example before training
+    Offline { copies: usize },
+    /// Replace every example with a freshly sampled augmentation each epoch
+    OnTheFly,
+}
+
+/// Augmentation settings for `TrainingConfig`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AugmentationConfig {
+    pub symmetry: Symmetry,
+    pub mode: AugmentationMode,
+    /// Seed for sampling transforms
+    pub seed: u64,
+}
+
+/// Expand a dataset with `copies` augmented versions of every example
+///
+/// Each original example is followed by its copies.
+pub fn augment_dataset<D: Dataset + ?Sized>(
+    examples: &D,
+    symmetry: &Symmetry,
+    copies: usize,
+    rng: &mut impl Rng,
+) -> Vec<TrainingExample> {
+    let mut augmented = Vec::with_capacity(examples.len() * (copies + 1));
+    for example in examples.iter() {
+        let original = augmented.len();
+        augmented.push(example.into_owned());
+        for _ in 0..copies {
+            let copy = symmetry.sample(rng).apply(&augmented[original]);
+            augmented.push(copy);
+        }
+    }
+    augmented
+}
+
+/// A dataset that applies a random symmetry to every example it returns
+///
+/// The transform for example `i` is fixed within an epoch and resampled
+/// when the next epoch starts.
+pub struct AugmentedDataset<'a, D: ?Sized> {
+    inner: &'a D,
+    symmetry: Symmetry,
+    seed: u64,
+    epoch: Cell<usize>,
+}
+
+impl<'a, D: Dataset + ?Sized> AugmentedDataset<'a, D> {
+    /// Wrap a dataset
+    pub fn new(inner: &'a D, symmetry: Symmetry, seed: u64) -> Self {
+        Self {
+            inner,
+            symmetry,
+            seed,
+            epoch: Cell::new(0),
+        }
+    }
+}
+
+impl<D: Dataset + ?Sized> Dataset for AugmentedDataset<'_, D> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn get(&self, index: usize) -> Option<Cow<'_, TrainingExample>> {
+        let example = self.inner.get(index)?;
+        let mut rng = example_rng(self.seed, self.epoch.get(), index);
+        Some(Cow::Owned(self.symmetry.sample(&mut rng).apply(&example)))
+    }
+
+    fn start_epoch(&self, epoch: usize) {
+        self.epoch.set(epoch);
+        self.inner.start_epoch(epoch);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::maze::{Cell as MazeCell, Maze};
+    use crate::data::sudoku::{decode_grid, SudokuPuzzle};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn solved_maze(seed: u64) -> Maze {
+        let mut rng = StdRng::seed_from_u64(seed);
+        loop {
+            let mut maze = Maze::generate_with_rng(7, 7, &mut rng).unwrap();
+            if maze.solve() {
+                return maze;
+            }
+        }
+    }
+
+    #[test]
+    fn test_dihedral_group() {
+        let square = Symmetry::maze(3, 3);
+        let transforms = square.transforms();
+        assert_eq!(transforms.len(), 8);
+        assert!(transforms[0].is_identity());
+        // All eight are distinct
+        for (i, a) in transforms.iter().enumerate() {
+            assert!(transforms[i + 1..].iter().all(|b| a != b));
+        }
+
+        // Every transform keeps the centre of a 3x3 grid in place
+        assert!(transforms.iter().all(|t| t.cells[4] == 4));
+
+        assert_eq!(Symmetry::maze(3, 5).transforms().len(), 4);
+    }
+
+    #[test]
+    fn test_maze_augmentation_keeps_path_valid() {
+        let maze = solved_maze(3);
+        let example = maze.to_training_example().unwrap();
+        let start = MazeCell::Start.to_f32();
+        let goal = MazeCell::Goal.to_f32();
+        let wall = MazeCell::Wall.to_f32();
+
+        for transform in Symmetry::maze(7, 7).transforms() {
+            let augmented = transform.apply(&example);
+            let input: Vec<f32> = augmented.input.iter().cloned().collect();
+            let target: Vec<f32> = augmented.target.iter().cloned().collect();
+
+            assert_eq!(input.iter().filter(|&&v| v == start).count(), 1);
+            assert_eq!(input.iter().filter(|&&v| v == goal).count(), 1);
+            // Path cells are never walls and include start and goal
+            for (cell, &on_path) in target.iter().enumerate() {
+                if on_path > 0.5 {
+                    assert_ne!(input[cell], wall);
+                }
+            }
+            let start_cell = input.iter().position(|&v| v == start).unwrap();
+            assert_eq!(target[start_cell], 1.0);
+        }
+    }
+
+    #[test]
+    fn test_sudoku_augmentation_keeps_solution_valid() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let puzzle = SudokuPuzzle::generate(30, &mut rng);
+        let example = puzzle.to_training_example();
+
+        for _ in 0..10 {
+            let transform = Symmetry::Sudoku.sample(&mut rng);
+            let augmented = transform.apply(&example);
+
+            let target: Vec<f32> = augmented.target.iter().cloned().collect();
+            let solution = decode_grid(&target).unwrap();
+            assert!(solution.is_solved());
+
+            // The givens move with the solution and keep their digits
+            let input: Vec<f32> = augmented.input.iter().cloned().collect();
+            let mut givens = 0;
+            for (cell, channels) in input.chunks(sudoku::INPUT_CHANNELS).enumerate() {
+                let digit = channels.iter().position(|&v| v == 1.0).unwrap();
+                if digit != 0 {
+                    givens += 1;
+                    assert_eq!(solution.cells[cell / 9][cell % 9] as usize, digit);
+                }
+            }
+            assert_eq!(givens, 30);
+        }
+    }
+
+    #[test]
+    fn test_offline_and_on_the_fly() {
+        let maze = solved_maze(5);
+        let examples = vec![maze.to_training_example().unwrap()];
+        let symmetry = Symmetry::maze(7, 7);
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let expanded = augment_dataset(&examples, &symmetry, 3, &mut rng);
+        assert_eq!(expanded.len(), 4);
+        assert_eq!(expanded[0].input, examples[0].input);
+
+        let dataset = AugmentedDataset::new(&examples, symmetry, 9);
+        assert_eq!(dataset.len(), 1);
+        let first = dataset.get(0).unwrap().into_owned();
+        assert_eq!(dataset.get(0).unwrap().input, first.input);
+
+        // Across epochs the sampled transforms vary
+        let inputs: Vec<_> = (0..16)
+            .map(|epoch| {
+                dataset.start_epoch(epoch);
+                dataset.get(0).unwrap().into_owned().input
+            })
+            .collect();
+        assert!(inputs.iter().any(|input| *input != inputs[0]));
+    }
+}
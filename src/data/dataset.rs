@@ -0,0 +1,455 @@
+//! Indexed collections of training examples
+//!
+//! A [`Dataset`] can hold its examples in memory, read them lazily from a
+//! file ([`FileDataset`]) or generate them on demand ([`GeneratedDataset`]).
+
+use super::io::{self, CsvLayout, DatasetFormat};
+use super::maze::{MazeGenerator, MAX_ATTEMPTS_PER_MAZE};
+use super::sudoku::SudokuPuzzle;
+use super::{Task, TrainingExample};
+use crate::model::TRMConfig;
+use crate::utils::{Result, TRMError};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// A sized, indexable source of training examples
+pub trait Dataset {
+    /// Number of examples (per epoch, for generated datasets)
+    fn len(&self) -> usize;
+
+    /// Whether the dataset has no examples
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the example at `index`, or `None` if it is out of range
+    fn get(&self, index: usize) -> Option<Cow<'_, TrainingExample>>;
+
+    /// Called by the trainer before every epoch
+    ///
+    /// Generated datasets use this to draw fresh examples; other datasets
+    /// ignore it.
+    fn start_epoch(&self, _epoch: usize) {}
+
+    /// Iterate over all examples in index order
+    fn iter(&self) -> Box<dyn Iterator<Item = Cow<'_, TrainingExample>> + '_> {
+        Box::new((0..self.len()).filter_map(move |index| self.get(index)))
+    }
+}
+
+impl Dataset for [TrainingExample] {
+    fn len(&self) -> usize {
+        <[TrainingExample]>::len(self)
+    }
+
+    fn get(&self, index: usize) -> Option<Cow<'_, TrainingExample>> {
+        <[TrainingExample]>::get(self, index).map(Cow::Borrowed)
+    }
+}
+
+impl Dataset for Vec<TrainingExample> {
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    fn get(&self, index: usize) -> Option<Cow<'_, TrainingExample>> {
+        self.as_slice().get(index).map(Cow::Borrowed)
+    }
+}
+
+/// Every task is a dataset of its examples
+impl<T: Task + ?Sized> Dataset for T {
+    fn len(&self) -> usize {
+        self.examples().len()
+    }
+
+    fn get(&self, index: usize) -> Option<Cow<'_, TrainingExample>> {
+        self.examples().get(index).map(Cow::Borrowed)
+    }
+}
+
+/// A dataset file read one record at a time
+///
+/// Opening the file parses every record once to validate it and remember
+/// where it starts; afterwards only the requested record is read. Supports
+/// JSON Lines, CSV and binary files (`.npz` archives must be loaded whole
+/// with [`io::load_dataset`]).
+pub struct FileDataset {
+    path: PathBuf,
+    reader: RefCell<BufReader<File>>,
+    format: DatasetFormat,
+    csv_layout: Option<CsvLayout>,
+    /// Byte offset and line (or record) number of every example
+    records: Vec<(u64, usize)>,
+}
+
+impl FileDataset {
+    /// Open a dataset file, inferring the format from the extension
+    ///
+    /// When `config` is given, every example is checked against its
+    /// `input_dim`/`output_dim`.
+    pub fn open<P: AsRef<Path>>(path: P, config: Option<&TRMConfig>) -> Result<Self> {
+        let format = DatasetFormat::from_path(&path)?;
+        let path = path.as_ref().to_path_buf();
+        let mut reader = BufReader::new(File::open(&path)?);
+        let mut csv_layout = None;
+        let mut records = Vec::new();
+
+        match format {
+            DatasetFormat::JsonLines | DatasetFormat::Csv => {
+                let mut offset = 0;
+                let mut line = String::new();
+                let mut line_number = 0;
+                loop {
+                    line.clear();
+                    let read = reader.read_line(&mut line)?;
+                    if read == 0 {
+                        break;
+                    }
+                    let start = offset;
+                    offset += read as u64;
+                    line_number += 1;
+
+                    if format == DatasetFormat::Csv && line_number == 1 {
+                        csv_layout = Some(CsvLayout::parse(line.trim_end())?);
+                        continue;
+                    }
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+
+                    let example = parse_line(format, csv_layout, &line, line_number)?;
+                    if let Some(config) = config {
+                        io::check_dims(&example, config, line_number)?;
+                    }
+                    records.push((start, line_number));
+                }
+            }
+            DatasetFormat::Binary => {
+                let count = io::read_binary_header(&mut reader)?;
+                for index in 0..count {
+                    let start = reader.stream_position()?;
+                    let example = io::read_binary_example(&mut reader)?;
+                    if let Some(config) = config {
+                        io::check_dims(&example, config, index + 1)?;
+                    }
+                    records.push((start, index + 1));
+                }
+            }
+            DatasetFormat::Npz => {
+                return Err(TRMError::InvalidData(
+                    ".npz datasets cannot be read lazily; use load_dataset".to_string(),
+                ))
+            }
+        }
+
+        Ok(Self {
+            path,
+            reader: RefCell::new(reader),
+            format,
+            csv_layout,
+            records,
+        })
+    }
+
+    /// Format of the underlying file
+    pub fn format(&self) -> DatasetFormat {
+        self.format
+    }
+
+    /// Read the example at `index`, reporting I/O and parse errors
+    pub fn try_get(&self, index: usize) -> Result<TrainingExample> {
+        let &(offset, line_number) = self.records.get(index).ok_or_else(|| {
+            TRMError::InvalidData(format!(
+                "index {} out of range for {} examples",
+                index,
+                self.records.len()
+            ))
+        })?;
+
+        let mut reader = self.reader.borrow_mut();
+        reader.seek(SeekFrom::Start(offset))?;
+        match self.format {
+            DatasetFormat::Binary => io::read_binary_example(&mut *reader),
+            format => {
+                let mut line = String::new();
+                reader.read_line(&mut line)?;
+                parse_line(format, self.csv_layout, &line, line_number)
+            }
+        }
+    }
+}
+
+impl Dataset for FileDataset {
+    fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Records were validated on open, so reading only fails if the file
+    /// changes or becomes unreadable afterwards
+    ///
+    /// # Panics
+    ///
+    /// If a record in range cannot be read; use [`FileDataset::try_get`] to
+    /// handle the error instead.
+    fn get(&self, index: usize) -> Option<Cow<'_, TrainingExample>> {
+        if index >= self.records.len() {
+            return None;
+        }
+        match self.try_get(index) {
+            Ok(example) => Some(Cow::Owned(example)),
+            Err(e) => panic!(
+                "{}: cannot read example {}: {}",
+                self.path.display(),
+                index,
+                e
+            ),
+        }
+    }
+}
+
+fn parse_line(
+    format: DatasetFormat,
+    csv_layout: Option<CsvLayout>,
+    line: &str,
+    line_number: usize,
+) -> Result<TrainingExample> {
+    match (format, csv_layout) {
+        (DatasetFormat::Csv, Some(layout)) => layout.parse_row(line.trim_end(), line_number),
+        _ => io::parse_json_line(line, line_number),
+    }
+}
+
+/// An endless supply of procedurally generated examples
+///
+/// Each epoch exposes `epoch_size` examples. Example `i` of epoch `e` is
+/// generated from an RNG seeded with `(seed, e, i)`, so it is the same every
+/// time it is read within an epoch and different in the next one.
+pub struct GeneratedDataset<F> {
+    epoch_size: usize,
+    seed: u64,
+    epoch: Cell<usize>,
+    generator: F,
+}
+
+impl<F: Fn(&mut StdRng) -> TrainingExample> GeneratedDataset<F> {
+    /// Create a dataset drawing `epoch_size` examples per epoch from `generator`
+    pub fn new(epoch_size: usize, seed: u64, generator: F) -> Self {
+        Self {
+            epoch_size,
+            seed,
+            epoch: Cell::new(0),
+            generator,
+        }
+    }
+
+    /// The epoch currently being generated
+    pub fn epoch(&self) -> usize {
+        self.epoch.get()
+    }
+}
+
+/// Deterministic RNG for example `index` of `epoch`
+pub(super) fn example_rng(seed: u64, epoch: usize, index: usize) -> StdRng {
+    let seed = seed
+        ^ (epoch as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (index as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    StdRng::seed_from_u64(seed)
+}
+
+impl<F: Fn(&mut StdRng) -> TrainingExample> Dataset for GeneratedDataset<F> {
+    fn len(&self) -> usize {
+        self.epoch_size
+    }
+
+    fn get(&self, index: usize) -> Option<Cow<'_, TrainingExample>> {
+        if index >= self.epoch_size {
+            return None;
+        }
+        Some(Cow::Owned((self.generator)(&mut example_rng(
+            self.seed,
+            self.epoch.get(),
+            index,
+        ))))
+    }
+
+    fn start_epoch(&self, epoch: usize) {
+        self.epoch.set(epoch);
+    }
+}
+
+/// Fresh solvable mazes from `generator` every epoch
+///
+/// Fails if the generator rejects the maze size or its first maze has no
+/// solution.
+///
+/// # Panics
+///
+/// Reading an example panics if `MAX_ATTEMPTS_PER_MAZE` mazes in a row are
+/// unsolvable.
+pub fn generated_mazes(
+    epoch_size: usize,
+    width: usize,
+    height: usize,
+    generator: MazeGenerator,
+    seed: u64,
+) -> Result<GeneratedDataset<impl Fn(&mut StdRng) -> TrainingExample>> {
+    // Surface size and placement errors now rather than inside the closure
+    let mut probe = generator.generate(width, height, &mut StdRng::seed_from_u64(seed))?;
+    if !probe.solve() {
+        return Err(TRMError::InvalidData(format!(
+            "maze generator produced an unsolvable {}x{} maze",
+            width, height
+        )));
+    }
+
+    Ok(GeneratedDataset::new(epoch_size, seed, move |rng| {
+        for _ in 0..MAX_ATTEMPTS_PER_MAZE {
+            let mut maze = generator
+                .generate(width, height, rng)
+                .expect("maze settings were validated");
+            if !maze.solve() {
+                continue;
+            }
+            if let Some(example) = maze.to_training_example() {
+                return example;
+            }
+        }
+        panic!(
+            "no solvable {}x{} maze in {} attempts",
+            width, height, MAX_ATTEMPTS_PER_MAZE
+        )
+    }))
+}
+
+/// Fresh Sudoku puzzles every epoch
+pub fn generated_sudoku(
+    epoch_size: usize,
+    givens: usize,
+    seed: u64,
+) -> GeneratedDataset<impl Fn(&mut StdRng) -> TrainingExample> {
+    GeneratedDataset::new(epoch_size, seed, move |rng| {
+        SudokuPuzzle::generate(givens, rng).to_training_example()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::io::save_dataset;
+    use crate::data::maze::Placement;
+    use crate::data::tasks::CopyTask;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("train-trm-dataset-{}", name))
+    }
+
+    #[test]
+    fn test_in_memory_and_task_datasets() {
+        let task = CopyTask::new(5, 3);
+        let examples = task.examples().to_vec();
+
+        assert_eq!(Dataset::len(&task), 5);
+        assert_eq!(Dataset::len(&examples), 5);
+        assert!(Dataset::get(&examples, 5).is_none());
+        assert!(matches!(Dataset::get(&examples, 0), Some(Cow::Borrowed(_))));
+
+        let collected: Vec<_> = Dataset::iter(&task).collect();
+        assert_eq!(collected.len(), 5);
+        assert_eq!(collected[2].input, examples[2].input);
+    }
+
+    #[test]
+    fn test_file_dataset_matches_load_dataset() {
+        let examples = CopyTask::new(4, 3).examples().to_vec();
+
+        for name in ["lazy.jsonl", "lazy.csv", "lazy.bin"] {
+            let path = temp_path(name);
+            save_dataset(&path, &examples).unwrap();
+
+            let dataset = FileDataset::open(&path, None).unwrap();
+            assert_eq!(dataset.len(), 4);
+            // Out-of-order access seeks to the right record
+            for index in [3, 0, 2, 1] {
+                let example = dataset.get(index).unwrap();
+                assert_eq!(example.input, examples[index].input, "{}", name);
+                assert_eq!(example.target, examples[index].target, "{}", name);
+            }
+            assert!(dataset.try_get(4).is_err());
+
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot read example 1")]
+    fn test_file_dataset_reports_read_errors() {
+        let path = temp_path("changed.jsonl");
+        save_dataset(&path, CopyTask::new(2, 3).examples()).unwrap();
+        let dataset = FileDataset::open(&path, None).unwrap();
+
+        std::fs::write(&path, "not json\n").unwrap();
+        let _ = dataset.get(1);
+    }
+
+    #[test]
+    fn test_file_dataset_validates_on_open() {
+        let path = temp_path("bad.jsonl");
+        std::fs::write(
+            &path,
+            "{\"input\": [1, 2], \"target\": [3, 4]}\n\n{\"input\": [1], \"target\": [3, 4]}\n",
+        )
+        .unwrap();
+
+        let config = TRMConfig {
+            input_dim: 2,
+            output_dim: 2,
+            ..TRMConfig::default()
+        };
+        match FileDataset::open(&path, Some(&config)) {
+            Err(TRMError::DimensionMismatch { line, .. }) => assert_eq!(line, Some(3)),
+            other => panic!("expected dimension mismatch, got {:?}", other.err()),
+        }
+        assert_eq!(FileDataset::open(&path, None).unwrap().len(), 2);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_generated_dataset_fresh_each_epoch() {
+        let dataset = generated_mazes(3, 9, 9, MazeGenerator::default(), 42).unwrap();
+        assert_eq!(dataset.len(), 3);
+        assert!(dataset.get(3).is_none());
+
+        // Stable within an epoch
+        let first = dataset.get(0).unwrap().into_owned();
+        assert_eq!(dataset.get(0).unwrap().input, first.input);
+        assert_eq!(first.input.ncols(), 81);
+
+        // Different in the next epoch, and reproducible when revisited
+        dataset.start_epoch(1);
+        assert_eq!(dataset.epoch(), 1);
+        let next = dataset.get(0).unwrap().into_owned();
+        assert_ne!(next.input, first.input);
+        dataset.start_epoch(0);
+        assert_eq!(dataset.get(0).unwrap().input, first.input);
+    }
+
+    #[test]
+    fn test_generated_mazes_rejects_bad_generators() {
+        let on_wall = MazeGenerator {
+            placement: Placement::Fixed {
+                start: (0, 0),
+                goal: (7, 7),
+            },
+            ..MazeGenerator::default()
+        };
+        assert!(generated_mazes(3, 9, 9, on_wall, 1).is_err());
+        assert!(generated_mazes(3, 2, 9, MazeGenerator::default(), 1).is_err());
+    }
+}
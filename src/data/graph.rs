@@ -0,0 +1,606 @@
+//! Reasoning tasks on random graphs
+//!
+//! Graphs are drawn from the Erdős–Rényi or random geometric models and fed
+//! to the network as a flattened adjacency matrix. A task asks either for a
+//! shortest path between two marked nodes, as a node mask, or for the
+//! connected-component label of every node, one-hot per node.
+
+use super::problem::check_len;
+use super::{Task, TrainingExample, Validation};
+use crate::utils::{Result, TRMError};
+use ndarray::Array2;
+use rand::Rng;
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+/// Random graph model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphModel {
+    /// Every pair of nodes is joined independently with probability `edge_prob`
+    ErdosRenyi { edge_prob: f32 },
+    /// Nodes are points in the unit square, joined when within `radius`
+    Geometric { radius: f32 },
+}
+
+impl Default for GraphModel {
+    fn default() -> Self {
+        GraphModel::ErdosRenyi { edge_prob: 0.2 }
+    }
+}
+
+impl GraphModel {
+    fn validate(self) -> Result<()> {
+        match self {
+            GraphModel::ErdosRenyi { edge_prob } if !(0.0..=1.0).contains(&edge_prob) => Err(
+                TRMError::InvalidData(format!("edge probability {} is not in [0, 1]", edge_prob)),
+            ),
+            GraphModel::Geometric { radius } if radius < 0.0 || radius.is_nan() => Err(
+                TRMError::InvalidData(format!("geometric radius {} must be non-negative", radius)),
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// An undirected graph stored as an adjacency matrix
+#[derive(Debug, Clone, PartialEq)]
+pub struct Graph {
+    pub adjacency: Vec<Vec<bool>>,
+}
+
+impl Graph {
+    /// A graph with `nodes` nodes and no edges
+    pub fn empty(nodes: usize) -> Self {
+        Self {
+            adjacency: vec![vec![false; nodes]; nodes],
+        }
+    }
+
+    /// Draw a random graph from `model`
+    pub fn random<R: Rng>(nodes: usize, model: GraphModel, rng: &mut R) -> Self {
+        let mut graph = Self::empty(nodes);
+        match model {
+            GraphModel::ErdosRenyi { edge_prob } => {
+                for a in 0..nodes {
+                    for b in a + 1..nodes {
+                        if rng.gen::<f32>() < edge_prob {
+                            graph.add_edge(a, b);
+                        }
+                    }
+                }
+            }
+            GraphModel::Geometric { radius } => {
+                let points: Vec<(f32, f32)> = (0..nodes).map(|_| (rng.gen(), rng.gen())).collect();
+                for a in 0..nodes {
+                    for b in a + 1..nodes {
+                        let (dx, dy) = (points[a].0 - points[b].0, points[a].1 - points[b].1);
+                        if dx * dx + dy * dy <= radius * radius {
+                            graph.add_edge(a, b);
+                        }
+                    }
+                }
+            }
+        }
+        graph
+    }
+
+    /// Number of nodes
+    pub fn nodes(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    /// Join `a` and `b`
+    pub fn add_edge(&mut self, a: usize, b: usize) {
+        self.adjacency[a][b] = true;
+        self.adjacency[b][a] = true;
+    }
+
+    /// Neighbours of `node` in ascending order
+    pub fn neighbors(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        self.adjacency[node]
+            .iter()
+            .enumerate()
+            .filter(|(_, &edge)| edge)
+            .map(|(other, _)| other)
+    }
+
+    /// BFS hop count from `from` to every node; `None` if unreachable
+    pub fn distances(&self, from: usize) -> Vec<Option<usize>> {
+        let mut distance = vec![None; self.nodes()];
+        let mut queue = VecDeque::from([from]);
+        distance[from] = Some(0);
+        while let Some(node) = queue.pop_front() {
+            let next_distance = distance[node].map(|d| d + 1);
+            for next in self.neighbors(node) {
+                if distance[next].is_none() {
+                    distance[next] = next_distance;
+                    queue.push_back(next);
+                }
+            }
+        }
+        distance
+    }
+
+    /// A shortest path from `source` to `target`, if they are connected
+    pub fn shortest_path(&self, source: usize, target: usize) -> Option<Vec<usize>> {
+        let distance = self.distances(target);
+        let mut node = source;
+        let mut path = vec![node];
+        let mut remaining = distance[source]?;
+        // Walk downhill in distance-to-target
+        while remaining > 0 {
+            node = self
+                .neighbors(node)
+                .find(|&next| distance[next] == Some(remaining - 1))
+                .expect("a node at distance d has a neighbour at d - 1");
+            path.push(node);
+            remaining -= 1;
+        }
+        Some(path)
+    }
+
+    /// Whether `nodes`, in any order, form a shortest path from `source` to `target`
+    ///
+    /// Each node must lie on some shortest path, with exactly one node per
+    /// hop and consecutive hops adjacent.
+    pub fn is_shortest_path_set(&self, nodes: &[usize], source: usize, target: usize) -> bool {
+        let from_source = self.distances(source);
+        let from_target = self.distances(target);
+        let Some(length) = from_source[target] else {
+            return false;
+        };
+        if nodes.len() != length + 1 || nodes.iter().any(|&node| node >= self.nodes()) {
+            return false;
+        }
+
+        let mut by_hop = vec![None; length + 1];
+        for &node in nodes {
+            match (from_source[node], from_target[node]) {
+                (Some(d), Some(rest)) if d + rest == length && by_hop[d].is_none() => {
+                    by_hop[d] = Some(node);
+                }
+                _ => return false,
+            }
+        }
+        by_hop
+            .windows(2)
+            .all(|pair| matches!(pair, [Some(a), Some(b)] if self.adjacency[*a][*b]))
+    }
+
+    /// Connected-component label of every node
+    ///
+    /// Labels are numbered from 0 in order of each component's lowest node.
+    pub fn components(&self) -> Vec<usize> {
+        let mut labels = vec![usize::MAX; self.nodes()];
+        let mut next_label = 0;
+        for node in 0..self.nodes() {
+            if labels[node] != usize::MAX {
+                continue;
+            }
+            for (other, distance) in self.distances(node).into_iter().enumerate() {
+                if distance.is_some() {
+                    labels[other] = next_label;
+                }
+            }
+            next_label += 1;
+        }
+        labels
+    }
+
+    /// Flattened adjacency matrix, 1.0 for an edge
+    pub fn encode(&self) -> Vec<f32> {
+        self.adjacency
+            .iter()
+            .flatten()
+            .map(|&edge| if edge { 1.0 } else { 0.0 })
+            .collect()
+    }
+}
+
+/// Renumber labels in order of first appearance, so equal partitions compare equal
+pub fn canonical_labels(labels: &[usize]) -> Vec<usize> {
+    let mut seen: Vec<usize> = Vec::new();
+    labels
+        .iter()
+        .map(|label| {
+            seen.iter().position(|s| s == label).unwrap_or_else(|| {
+                seen.push(*label);
+                seen.len() - 1
+            })
+        })
+        .collect()
+}
+
+/// What the model has to predict about a graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GraphObjective {
+    /// Mask of the nodes on a shortest path between two marked nodes
+    #[default]
+    ShortestPath,
+    /// Connected-component label of every node
+    Components,
+}
+
+impl GraphObjective {
+    /// Every objective, in declaration order
+    pub const ALL: [GraphObjective; 2] = [GraphObjective::ShortestPath, GraphObjective::Components];
+
+    /// Lowercase name, as accepted by `from_str`
+    pub fn name(self) -> &'static str {
+        match self {
+            GraphObjective::ShortestPath => "path",
+            GraphObjective::Components => "components",
+        }
+    }
+}
+
+impl fmt::Display for GraphObjective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for GraphObjective {
+    type Err = TRMError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        GraphObjective::ALL
+            .into_iter()
+            .find(|objective| objective.name() == s.to_ascii_lowercase())
+            .ok_or_else(|| TRMError::InvalidData(format!("unknown graph objective: {:?}", s)))
+    }
+}
+
+/// One generated graph with its question and answer
+#[derive(Debug, Clone)]
+pub struct GraphInstance {
+    pub graph: Graph,
+    /// Marked source and target for shortest-path questions
+    pub endpoints: Option<(usize, usize)>,
+    /// Path nodes from source to target, or a label per node
+    pub answer: Vec<usize>,
+}
+
+/// Shortest-path or connectivity task on random graphs
+pub struct GraphTask {
+    instances: Vec<GraphInstance>,
+    examples: Vec<TrainingExample>,
+    nodes: usize,
+    objective: GraphObjective,
+}
+
+impl GraphTask {
+    /// Create a task with `num_graphs` random graphs of `nodes` nodes
+    pub fn new(
+        num_graphs: usize,
+        nodes: usize,
+        model: GraphModel,
+        objective: GraphObjective,
+    ) -> Result<Self> {
+        Self::with_rng(num_graphs, nodes, model, objective, &mut rand::thread_rng())
+    }
+
+    /// Create a task drawing graphs from `rng`
+    ///
+    /// For shortest paths, graphs without any edge are redrawn and the
+    /// endpoints are two distinct nodes of the same component.
+    pub fn with_rng<R: Rng>(
+        num_graphs: usize,
+        nodes: usize,
+        model: GraphModel,
+        objective: GraphObjective,
+        rng: &mut R,
+    ) -> Result<Self> {
+        if nodes < 2 {
+            return Err(TRMError::InvalidData(format!(
+                "graph task needs at least 2 nodes, got {}",
+                nodes
+            )));
+        }
+        model.validate()?;
+        if objective == GraphObjective::ShortestPath
+            && matches!(
+                model,
+                GraphModel::ErdosRenyi { edge_prob: 0.0 } | GraphModel::Geometric { radius: 0.0 }
+            )
+        {
+            return Err(TRMError::InvalidData(
+                "shortest paths need graphs with edges".to_string(),
+            ));
+        }
+
+        let mut instances = Vec::with_capacity(num_graphs);
+        while instances.len() < num_graphs {
+            let graph = Graph::random(nodes, model, rng);
+            let instance = match objective {
+                GraphObjective::Components => GraphInstance {
+                    answer: graph.components(),
+                    endpoints: None,
+                    graph,
+                },
+                GraphObjective::ShortestPath => {
+                    let connected: Vec<usize> = (0..nodes)
+                        .filter(|&node| graph.neighbors(node).next().is_some())
+                        .collect();
+                    if connected.is_empty() {
+                        continue;
+                    }
+                    let source = connected[rng.gen_range(0..connected.len())];
+                    let reachable: Vec<usize> = graph
+                        .distances(source)
+                        .iter()
+                        .enumerate()
+                        .filter(|&(node, d)| node != source && d.is_some())
+                        .map(|(node, _)| node)
+                        .collect();
+                    let target = reachable[rng.gen_range(0..reachable.len())];
+                    GraphInstance {
+                        answer: graph
+                            .shortest_path(source, target)
+                            .expect("target is reachable"),
+                        endpoints: Some((source, target)),
+                        graph,
+                    }
+                }
+            };
+            instances.push(instance);
+        }
+
+        let mut task = Self {
+            instances,
+            examples: Vec::new(),
+            nodes,
+            objective,
+        };
+        task.examples = task
+            .instances
+            .iter()
+            .map(|instance| task.encode(instance))
+            .collect();
+        Ok(task)
+    }
+
+    fn encode(&self, instance: &GraphInstance) -> TrainingExample {
+        let n = self.nodes;
+        let mut input = instance.graph.encode();
+        let mut target;
+        match self.objective {
+            GraphObjective::ShortestPath => {
+                let (source, goal) = instance.endpoints.expect("path instances are marked");
+                let mut marks = vec![0.0; 2 * n];
+                marks[source] = 1.0;
+                marks[n + goal] = 1.0;
+                input.extend(marks);
+
+                target = vec![0.0; n];
+                for &node in &instance.answer {
+                    target[node] = 1.0;
+                }
+            }
+            GraphObjective::Components => {
+                target = vec![0.0; n * n];
+                for (node, &label) in instance.answer.iter().enumerate() {
+                    target[node * n + label] = 1.0;
+                }
+            }
+        }
+        TrainingExample::new(
+            Array2::from_shape_vec((1, input.len()), input).unwrap(),
+            Array2::from_shape_vec((1, target.len()), target).unwrap(),
+        )
+    }
+
+    /// Generated graphs with their questions and answers
+    pub fn instances(&self) -> &[GraphInstance] {
+        &self.instances
+    }
+
+    /// What the task asks for
+    pub fn objective(&self) -> GraphObjective {
+        self.objective
+    }
+
+    /// Get all training examples
+    pub fn examples(&self) -> &[TrainingExample] {
+        &self.examples
+    }
+
+    /// Split into training and validation sets
+    pub fn split(&self, train_ratio: f32) -> (Vec<TrainingExample>, Vec<TrainingExample>) {
+        let train_size = (self.examples.len() as f32 * train_ratio) as usize;
+        let train = self.examples[..train_size].to_vec();
+        let val = self.examples[train_size..].to_vec();
+        (train, val)
+    }
+}
+
+impl Task for GraphTask {
+    fn examples(&self) -> &[TrainingExample] {
+        &self.examples
+    }
+
+    /// Accepts any shortest path, and any relabelling of the components
+    fn validate_example(&self, index: usize, output: &Array2<f32>) -> Validation {
+        if let Some(invalid) = check_len(output, self.output_dim()) {
+            return invalid;
+        }
+        let instance = &self.instances[index];
+        match self.objective {
+            GraphObjective::ShortestPath => {
+                let (source, target) = instance.endpoints.expect("path instances are marked");
+                let nodes: Vec<usize> = output
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &v)| v > 0.5)
+                    .map(|(node, _)| node)
+                    .collect();
+                if instance.graph.is_shortest_path_set(&nodes, source, target) {
+                    return Validation::correct();
+                }
+                // Partial credit: overlap with the generator's path
+                let shared = nodes
+                    .iter()
+                    .filter(|node| instance.answer.contains(node))
+                    .count();
+                let union = nodes.len() + instance.answer.len() - shared;
+                let reason = if nodes.len() != instance.answer.len() {
+                    format!(
+                        "{} nodes marked, a shortest path has {}",
+                        nodes.len(),
+                        instance.answer.len()
+                    )
+                } else {
+                    "marked nodes are not a shortest path".to_string()
+                };
+                Validation::incorrect(shared as f32 / union as f32, reason)
+            }
+            GraphObjective::Components => {
+                let values: Vec<f32> = output.iter().cloned().collect();
+                let labels: Vec<usize> = values
+                    .chunks(self.nodes)
+                    .map(|scores| {
+                        scores
+                            .iter()
+                            .enumerate()
+                            .max_by(|a, b| a.1.total_cmp(b.1))
+                            .map(|(label, _)| label)
+                            .unwrap_or(0)
+                    })
+                    .collect();
+                let wrong = canonical_labels(&labels)
+                    .iter()
+                    .zip(&instance.answer)
+                    .filter(|(p, e)| p != e)
+                    .count();
+                let reason = (wrong > 0)
+                    .then(|| format!("{} of {} nodes are mislabelled", wrong, self.nodes));
+                Validation::check(1.0 - wrong as f32 / self.nodes as f32, reason)
+            }
+        }
+    }
+
+    fn input_dim(&self) -> usize {
+        match self.objective {
+            GraphObjective::ShortestPath => self.nodes * self.nodes + 2 * self.nodes,
+            GraphObjective::Components => self.nodes * self.nodes,
+        }
+    }
+
+    fn output_dim(&self) -> usize {
+        match self.objective {
+            GraphObjective::ShortestPath => self.nodes,
+            GraphObjective::Components => self.nodes * self.nodes,
+        }
+    }
+
+    /// Hops on the shortest path, or the number of components
+    fn difficulty(&self, index: usize) -> Option<usize> {
+        let answer = &self.instances[index].answer;
+        match self.objective {
+            GraphObjective::ShortestPath => Some(answer.len() - 1),
+            GraphObjective::Components => answer.iter().max().map(|label| label + 1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// 4-cycle 0-1-2-3-0 plus a separate edge 4-5
+    fn square_and_edge() -> Graph {
+        let mut graph = Graph::empty(6);
+        for (a, b) in [(0, 1), (1, 2), (2, 3), (3, 0), (4, 5)] {
+            graph.add_edge(a, b);
+        }
+        graph
+    }
+
+    #[test]
+    fn test_shortest_paths_and_components() {
+        let graph = square_and_edge();
+        assert_eq!(graph.distances(0)[2], Some(2));
+        assert_eq!(graph.distances(0)[4], None);
+        assert_eq!(graph.shortest_path(0, 2).unwrap().len(), 3);
+        assert!(graph.shortest_path(0, 5).is_none());
+        assert_eq!(graph.components(), vec![0, 0, 0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn test_any_optimal_path_is_accepted() {
+        let graph = square_and_edge();
+        // Both ways round the square are optimal, in any order
+        assert!(graph.is_shortest_path_set(&[0, 1, 2], 0, 2));
+        assert!(graph.is_shortest_path_set(&[2, 3, 0], 0, 2));
+        // Not a path, too long, or missing an endpoint
+        assert!(!graph.is_shortest_path_set(&[0, 1, 3], 0, 2));
+        assert!(!graph.is_shortest_path_set(&[0, 1, 2, 3], 0, 2));
+        assert!(!graph.is_shortest_path_set(&[1, 2], 0, 2));
+        assert!(!graph.is_shortest_path_set(&[0, 4], 0, 4));
+    }
+
+    #[test]
+    fn test_random_models() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let complete = Graph::random(6, GraphModel::ErdosRenyi { edge_prob: 1.0 }, &mut rng);
+        assert_eq!(complete.neighbors(0).count(), 5);
+        assert!(!complete.adjacency[3][3]);
+
+        let isolated = Graph::random(6, GraphModel::Geometric { radius: 0.0 }, &mut rng);
+        assert_eq!(isolated.components(), vec![0, 1, 2, 3, 4, 5]);
+
+        let dense = Graph::random(20, GraphModel::Geometric { radius: 1.5 }, &mut rng);
+        assert!(dense.components().iter().all(|&label| label == 0));
+    }
+
+    #[test]
+    fn test_path_task_validation() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let model = GraphModel::ErdosRenyi { edge_prob: 0.3 };
+        let task =
+            GraphTask::with_rng(20, 8, model, GraphObjective::ShortestPath, &mut rng).unwrap();
+        assert_eq!(task.input_dim(), 64 + 16);
+        assert_eq!(task.output_dim(), 8);
+
+        for (index, example) in task.examples().iter().enumerate() {
+            assert_eq!(example.input.len(), task.input_dim());
+            assert!(task.validate_example(index, &example.target).correct);
+
+            // Dropping a node breaks the path
+            let mut broken = example.target.clone();
+            let on_path = task.instances()[index].answer[0];
+            broken[[0, on_path]] = 0.0;
+            let result = task.validate_example(index, &broken);
+            assert!(!result.correct);
+            assert!(result.score > 0.0 && result.score < 1.0);
+            assert!(result.reason.unwrap().contains("nodes marked"));
+        }
+    }
+
+    #[test]
+    fn test_components_task_validation() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let model = GraphModel::Geometric { radius: 0.3 };
+        let task = GraphTask::with_rng(10, 6, model, GraphObjective::Components, &mut rng).unwrap();
+        assert_eq!(task.output_dim(), 36);
+        for (index, example) in task.examples().iter().enumerate() {
+            assert!(task.validate_example(index, &example.target).correct);
+        }
+
+        // Relabelled components are still correct
+        let labels = canonical_labels(&[3, 3, 7, 3, 7, 1]);
+        assert_eq!(labels, vec![0, 0, 1, 0, 1, 2]);
+
+        assert!(GraphTask::new(1, 1, model, GraphObjective::Components).is_err());
+        assert!(GraphTask::new(
+            1,
+            5,
+            GraphModel::ErdosRenyi { edge_prob: 1.5 },
+            GraphObjective::Components
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,456 @@
+//! Inputs for inference on user data, and writing predictions
+//!
+//! Every format holds one example per row. Targets are optional; when they
+//! are present, predictions can be scored.
+//!
+//! Supported formats:
+//! - **JSON** (`.json`): a list of input rows, or
+//!   `{"inputs": [[...]], "targets": [[...]]}`
+//! - **JSON Lines** (`.jsonl`): one `{"input": [...], "target": [...]}` object
+//!   per line, `target` optional
+//! - **CSV** (`.csv`): a header of `input_*` columns, optionally followed by
+//!   `target_*` columns
+//! - **NumPy** (`.npy`): a 2-D array of inputs; (`.npz`): `inputs` and
+//!   optional `targets` arrays
+
+use super::io::{CsvLayout, JsonMatrix};
+use super::TrainingExample;
+use crate::model::TRMConfig;
+use crate::utils::npy;
+use crate::utils::{Result, TRMError};
+use ndarray::{Array2, Axis};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Cursor, Read, Write};
+use std::path::Path;
+
+/// File format of inference inputs or predictions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InferenceFormat {
+    Json,
+    JsonLines,
+    Csv,
+    Npy,
+    Npz,
+}
+
+impl InferenceFormat {
+    /// Infer the format from a file extension
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let extension = path
+            .as_ref()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        match extension.as_str() {
+            "json" => Ok(InferenceFormat::Json),
+            "jsonl" | "ndjson" => Ok(InferenceFormat::JsonLines),
+            "csv" => Ok(InferenceFormat::Csv),
+            "npy" => Ok(InferenceFormat::Npy),
+            "npz" => Ok(InferenceFormat::Npz),
+            _ => Err(TRMError::InvalidData(format!(
+                "unknown inference file extension: {:?}",
+                extension
+            ))),
+        }
+    }
+}
+
+/// Inputs to run a model on, one per row, with optional targets
+#[derive(Debug, Clone, PartialEq)]
+pub struct InferenceData {
+    pub inputs: Array2<f32>,
+    pub targets: Option<Array2<f32>>,
+}
+
+impl InferenceData {
+    /// Create inference data, checking that targets match the inputs
+    pub fn new(inputs: Array2<f32>, targets: Option<Array2<f32>>) -> Result<Self> {
+        if let Some(targets) = &targets {
+            if targets.nrows() != inputs.nrows() {
+                return Err(TRMError::InvalidData(format!(
+                    "{} inputs but {} targets",
+                    inputs.nrows(),
+                    targets.nrows()
+                )));
+            }
+        }
+        Ok(Self { inputs, targets })
+    }
+
+    /// Number of inputs
+    pub fn len(&self) -> usize {
+        self.inputs.nrows()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check the input (and target) width against the model
+    pub fn check_dims(&self, config: &TRMConfig) -> Result<()> {
+        let mut checks = vec![("inputs", self.inputs.ncols(), "input_dim", config.input_dim)];
+        if let Some(targets) = &self.targets {
+            checks.push(("targets", targets.ncols(), "output_dim", config.output_dim));
+        }
+        for (what, actual, field, expected) in checks {
+            if actual != expected && !self.is_empty() {
+                return Err(TRMError::InvalidData(format!(
+                    "{} have {} values, but the model's {} is {}",
+                    what, actual, field, expected
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Input rows, each as a `1 x input_dim` matrix
+    pub fn input_rows(&self) -> impl Iterator<Item = Array2<f32>> + '_ {
+        self.inputs
+            .rows()
+            .into_iter()
+            .map(|row| row.insert_axis(Axis(0)).to_owned())
+    }
+
+    /// Inputs paired with targets, if there are targets
+    pub fn examples(&self) -> Option<Vec<TrainingExample>> {
+        let targets = self.targets.as_ref()?;
+        Some(
+            self.input_rows()
+                .zip(targets.rows())
+                .map(|(input, target)| {
+                    TrainingExample::new(input, target.insert_axis(Axis(0)).to_owned())
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Load inference inputs, inferring the format from the extension
+pub fn load_inference_data<P: AsRef<Path>>(path: P) -> Result<InferenceData> {
+    let format = InferenceFormat::from_path(&path)?;
+    read_inference_data(BufReader::new(File::open(path)?), format)
+}
+
+/// Read inference inputs in the given format
+pub fn read_inference_data<R: BufRead>(
+    reader: R,
+    format: InferenceFormat,
+) -> Result<InferenceData> {
+    match format {
+        InferenceFormat::Json => read_json(reader),
+        InferenceFormat::JsonLines => read_json_lines(reader),
+        InferenceFormat::Csv => read_csv(reader),
+        InferenceFormat::Npy => InferenceData::new(npy::to_array2(npy::read_npy(reader)?)?, None),
+        InferenceFormat::Npz => read_npz(reader),
+    }
+}
+
+/// Save predictions, one per row, inferring the format from the extension
+pub fn save_predictions<P: AsRef<Path>>(path: P, predictions: &Array2<f32>) -> Result<()> {
+    let format = InferenceFormat::from_path(&path)?;
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_predictions(&mut writer, predictions, format)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write predictions, one per row, in the given format
+///
+/// JSON Lines writes one `{"output": [...]}` object per line, CSV an
+/// `output_*` header, and `.npz` an `outputs` array.
+pub fn write_predictions<W: Write>(
+    writer: &mut W,
+    predictions: &Array2<f32>,
+    format: InferenceFormat,
+) -> Result<()> {
+    let rows = || predictions.rows().into_iter().map(|row| row.to_vec());
+    match format {
+        InferenceFormat::Json => {
+            serde_json::to_writer(&mut *writer, &rows().collect::<Vec<_>>())?;
+            writeln!(writer)?;
+        }
+        InferenceFormat::JsonLines => {
+            for row in rows() {
+                serde_json::to_writer(&mut *writer, &serde_json::json!({ "output": row }))?;
+                writeln!(writer)?;
+            }
+        }
+        InferenceFormat::Csv => {
+            let header: Vec<String> = (0..predictions.ncols())
+                .map(|i| format!("output_{}", i))
+                .collect();
+            writeln!(writer, "{}", header.join(","))?;
+            for row in rows() {
+                let values: Vec<String> = row.iter().map(|v| v.to_string()).collect();
+                writeln!(writer, "{}", values.join(","))?;
+            }
+        }
+        InferenceFormat::Npy => npy::write_npy(&mut *writer, predictions.view().into_dyn())?,
+        InferenceFormat::Npz => {
+            let mut buffer = Cursor::new(Vec::new());
+            npy::write_npz(&mut buffer, &[("outputs", predictions.view().into_dyn())])?;
+            writer.write_all(buffer.get_ref())?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonInputs {
+    Split {
+        inputs: Vec<Vec<f32>>,
+        #[serde(default)]
+        targets: Option<Vec<Vec<f32>>>,
+    },
+    Rows(Vec<Vec<f32>>),
+}
+
+#[derive(Deserialize)]
+struct JsonLinesRecord {
+    input: JsonMatrix,
+    #[serde(default)]
+    target: Option<JsonMatrix>,
+}
+
+fn read_json<R: Read>(reader: R) -> Result<InferenceData> {
+    let (inputs, targets) = match serde_json::from_reader(reader)? {
+        JsonInputs::Split { inputs, targets } => (inputs, targets),
+        JsonInputs::Rows(rows) => (rows, None),
+    };
+    InferenceData::new(
+        stack_rows(inputs, "inputs")?,
+        targets.map(|t| stack_rows(t, "targets")).transpose()?,
+    )
+}
+
+fn read_json_lines<R: BufRead>(reader: R) -> Result<InferenceData> {
+    let mut inputs = Vec::new();
+    let mut targets = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: JsonLinesRecord = serde_json::from_str(&line)?;
+        inputs.push(single_row(
+            record.input.into_array(line_number)?,
+            line_number,
+        )?);
+        match record.target {
+            Some(target) => targets.push(single_row(target.into_array(line_number)?, line_number)?),
+            None if !targets.is_empty() => {
+                return Err(TRMError::InvalidData(format!(
+                    "line {} has no target, but earlier lines do",
+                    line_number
+                )))
+            }
+            None => {}
+        }
+    }
+    if !targets.is_empty() && targets.len() != inputs.len() {
+        return Err(TRMError::InvalidData(
+            "either every line or no line must have a target".to_string(),
+        ));
+    }
+    let targets = (!targets.is_empty())
+        .then(|| stack_rows(targets, "targets"))
+        .transpose()?;
+    InferenceData::new(stack_rows(inputs, "inputs")?, targets)
+}
+
+fn read_csv<R: BufRead>(reader: R) -> Result<InferenceData> {
+    let mut lines = reader.lines();
+    let header = lines
+        .next()
+        .transpose()?
+        .ok_or_else(|| TRMError::InvalidData("missing CSV header".to_string()))?;
+    let layout = CsvLayout::parse(&header)?;
+
+    let mut examples = Vec::new();
+    for (index, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        examples.push(layout.parse_row(&line, index + 2)?);
+    }
+
+    let inputs = stack_rows(
+        examples.iter().map(|e| e.input.row(0).to_vec()).collect(),
+        "inputs",
+    )?;
+    let targets = (layout.target_dim() > 0)
+        .then(|| {
+            stack_rows(
+                examples.iter().map(|e| e.target.row(0).to_vec()).collect(),
+                "targets",
+            )
+        })
+        .transpose()?;
+    InferenceData::new(inputs, targets)
+}
+
+fn read_npz<R: Read>(mut reader: R) -> Result<InferenceData> {
+    // Zip archives need random access, so buffer the whole file
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let mut arrays = npy::read_npz(Cursor::new(bytes))?;
+    let inputs = arrays
+        .remove("inputs")
+        .ok_or_else(|| TRMError::InvalidData("npz is missing `inputs`".to_string()))?;
+    let targets = arrays.remove("targets").map(npy::to_array2).transpose()?;
+    InferenceData::new(npy::to_array2(inputs)?, targets)
+}
+
+fn single_row(array: Array2<f32>, line: usize) -> Result<Vec<f32>> {
+    if array.nrows() != 1 {
+        return Err(TRMError::InvalidData(format!(
+            "line {} has {} rows; inference needs one row per example",
+            line,
+            array.nrows()
+        )));
+    }
+    Ok(array.into_raw_vec())
+}
+
+/// Stack equal-length rows into a matrix
+fn stack_rows(rows: Vec<Vec<f32>>, what: &str) -> Result<Array2<f32>> {
+    let cols = rows.first().map_or(0, Vec::len);
+    if let Some(index) = rows.iter().position(|row| row.len() != cols) {
+        return Err(TRMError::InvalidData(format!(
+            "{} row {} has {} values, expected {}",
+            what,
+            index + 1,
+            rows[index].len(),
+            cols
+        )));
+    }
+    Ok(Array2::from_shape_vec((rows.len(), cols), rows.concat()).expect("rows have equal length"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    fn read(text: &str, format: InferenceFormat) -> Result<InferenceData> {
+        read_inference_data(text.as_bytes(), format)
+    }
+
+    fn config(input_dim: usize, output_dim: usize) -> TRMConfig {
+        TRMConfig {
+            input_dim,
+            output_dim,
+            ..TRMConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_json_with_and_without_targets() {
+        let plain = read("[[1, 2], [3, 4]]", InferenceFormat::Json).unwrap();
+        assert_eq!(plain.inputs, array![[1.0, 2.0], [3.0, 4.0]]);
+        assert!(plain.targets.is_none());
+        assert!(plain.examples().is_none());
+
+        let split = read(
+            r#"{"inputs": [[1, 2]], "targets": [[0.5]]}"#,
+            InferenceFormat::Json,
+        )
+        .unwrap();
+        assert_eq!(split.targets, Some(array![[0.5]]));
+        assert_eq!(split.examples().unwrap()[0].target, array![[0.5]]);
+
+        assert!(read(r#"{"inputs": [[1]], "targets": []}"#, InferenceFormat::Json).is_err());
+        assert!(read("[[1, 2], [3]]", InferenceFormat::Json).is_err());
+    }
+
+    #[test]
+    fn test_json_lines_and_csv() {
+        let lines =
+            "{\"input\": [1, 2], \"target\": [3]}\n\n{\"input\": [[4, 5]], \"target\": [6]}\n";
+        let data = read(lines, InferenceFormat::JsonLines).unwrap();
+        assert_eq!(data.inputs, array![[1.0, 2.0], [4.0, 5.0]]);
+        assert_eq!(data.targets, Some(array![[3.0], [6.0]]));
+
+        let mixed = "{\"input\": [1], \"target\": [3]}\n{\"input\": [4]}\n";
+        assert!(read(mixed, InferenceFormat::JsonLines).is_err());
+
+        let csv = read("input_0,input_1\n1,2\n3,4\n", InferenceFormat::Csv).unwrap();
+        assert_eq!(csv.len(), 2);
+        assert!(csv.targets.is_none());
+
+        let csv = read("input_0,target_0\n1,2\n", InferenceFormat::Csv).unwrap();
+        assert_eq!(csv.targets, Some(array![[2.0]]));
+    }
+
+    #[test]
+    fn test_numpy_inputs() {
+        let inputs = array![[1.0, 2.0, 3.0]];
+        let mut npy_bytes = Vec::new();
+        npy::write_npy(&mut npy_bytes, inputs.view().into_dyn()).unwrap();
+        let data = read_inference_data(npy_bytes.as_slice(), InferenceFormat::Npy).unwrap();
+        assert_eq!(data.inputs, inputs);
+
+        let mut npz_bytes = Cursor::new(Vec::new());
+        let targets = array![[1.0]];
+        npy::write_npz(
+            &mut npz_bytes,
+            &[
+                ("inputs", inputs.view().into_dyn()),
+                ("targets", targets.view().into_dyn()),
+            ],
+        )
+        .unwrap();
+        let data =
+            read_inference_data(npz_bytes.get_ref().as_slice(), InferenceFormat::Npz).unwrap();
+        assert_eq!(data.targets, Some(targets));
+    }
+
+    #[test]
+    fn test_check_dims() {
+        let data = read(
+            r#"{"inputs": [[1, 2]], "targets": [[1]]}"#,
+            InferenceFormat::Json,
+        )
+        .unwrap();
+        assert!(data.check_dims(&config(2, 1)).is_ok());
+        let error = data.check_dims(&config(3, 1)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid data: inputs have 2 values, but the model's input_dim is 3"
+        );
+        assert!(data.check_dims(&config(2, 4)).is_err());
+    }
+
+    #[test]
+    fn test_write_predictions() {
+        let predictions = array![[0.5, 1.0], [2.0, 3.0]];
+        let write = |format| {
+            let mut buffer = Vec::new();
+            write_predictions(&mut buffer, &predictions, format).unwrap();
+            buffer
+        };
+        assert_eq!(write(InferenceFormat::Json), b"[[0.5,1.0],[2.0,3.0]]\n");
+        assert_eq!(
+            String::from_utf8(write(InferenceFormat::Csv)).unwrap(),
+            "output_0,output_1\n0.5,1\n2,3\n"
+        );
+        assert!(String::from_utf8(write(InferenceFormat::JsonLines))
+            .unwrap()
+            .starts_with("{\"output\":[0.5,1.0]}\n"));
+        let npy_bytes = write(InferenceFormat::Npy);
+        let round_trip = read_inference_data(npy_bytes.as_slice(), InferenceFormat::Npy).unwrap();
+        assert_eq!(round_trip.inputs, predictions);
+
+        assert!(InferenceFormat::from_path("inputs.txt").is_err());
+        assert_eq!(
+            InferenceFormat::from_path("in.NPY").unwrap(),
+            InferenceFormat::Npy
+        );
+    }
+}
@@ -0,0 +1,616 @@
+//! Reading and writing `TrainingExample` collections
+//!
+//! Supported formats:
+//! - **JSON Lines** (`.jsonl`): one `{"input": [[...]], "target": [[...]]}` object per line
+//! - **CSV** (`.csv`): a header of `input_*` and `target_*` columns, one example per row
+//! - **Binary** (`.bin`): compact little-endian `f32` records with a `TRMD` header
+//! - **NumPy** (`.npz`): `inputs` and `targets` arrays with one example per row
+
+use super::TrainingExample;
+use crate::model::TRMConfig;
+use crate::utils::npy;
+use crate::utils::{read_bytes, Result, TRMError};
+use ndarray::{Array2, Axis};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Cursor, Read, Write};
+use std::path::Path;
+
+/// Magic bytes at the start of a binary dataset
+const BINARY_MAGIC: &[u8; 4] = b"TRMD";
+/// Binary format version
+const BINARY_VERSION: u32 = 1;
+
+/// On-disk dataset format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatasetFormat {
+    JsonLines,
+    Csv,
+    Binary,
+    Npz,
+}
+
+impl DatasetFormat {
+    /// Infer the format from a file extension
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let extension = path
+            .as_ref()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        match extension.as_str() {
+            "jsonl" | "ndjson" => Ok(DatasetFormat::JsonLines),
+            "csv" => Ok(DatasetFormat::Csv),
+            "bin" | "trmd" => Ok(DatasetFormat::Binary),
+            "npz" => Ok(DatasetFormat::Npz),
+            _ => Err(TRMError::InvalidData(format!(
+                "unknown dataset extension: {:?}",
+                extension
+            ))),
+        }
+    }
+}
+
+/// A 2-D array in JSON, written as nested rows; a flat list is read as one row
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub(super) enum JsonMatrix {
+    Rows(Vec<Vec<f32>>),
+    Row(Vec<f32>),
+}
+
+impl JsonMatrix {
+    fn from_array(array: &Array2<f32>) -> Self {
+        JsonMatrix::Rows(array.rows().into_iter().map(|r| r.to_vec()).collect())
+    }
+
+    pub(super) fn into_array(self, line: usize) -> Result<Array2<f32>> {
+        let rows = match self {
+            JsonMatrix::Rows(rows) => rows,
+            JsonMatrix::Row(row) => vec![row],
+        };
+        let cols = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|row| row.len() != cols) {
+            return Err(TRMError::InvalidData(format!(
+                "ragged rows on line {}",
+                line
+            )));
+        }
+        let shape = (rows.len(), cols);
+        Ok(Array2::from_shape_vec(shape, rows.concat()).expect("rows have equal length"))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonRecord {
+    input: JsonMatrix,
+    target: JsonMatrix,
+}
+
+/// Load a dataset, inferring the format from the extension
+///
+/// When `config` is given, every example is checked against its
+/// `input_dim`/`output_dim`.
+pub fn load_dataset<P: AsRef<Path>>(
+    path: P,
+    config: Option<&TRMConfig>,
+) -> Result<Vec<TrainingExample>> {
+    let format = DatasetFormat::from_path(&path)?;
+    let reader = BufReader::new(File::open(path)?);
+    read_examples(reader, format, config)
+}
+
+/// Save a dataset, inferring the format from the extension
+pub fn save_dataset<P: AsRef<Path>>(path: P, examples: &[TrainingExample]) -> Result<()> {
+    let format = DatasetFormat::from_path(&path)?;
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_examples(&mut writer, examples, format)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read examples in the given format
+pub fn read_examples<R: BufRead>(
+    reader: R,
+    format: DatasetFormat,
+    config: Option<&TRMConfig>,
+) -> Result<Vec<TrainingExample>> {
+    let examples = match format {
+        DatasetFormat::JsonLines => read_json_lines(reader)?,
+        DatasetFormat::Csv => read_csv(reader)?,
+        DatasetFormat::Binary => read_binary(reader)?,
+        DatasetFormat::Npz => read_npz(reader)?,
+    };
+
+    if let Some(config) = config {
+        for (line, example) in &examples {
+            check_dims(example, config, *line)?;
+        }
+    }
+
+    Ok(examples.into_iter().map(|(_, example)| example).collect())
+}
+
+/// Write examples in the given format
+pub fn write_examples<W: Write>(
+    writer: &mut W,
+    examples: &[TrainingExample],
+    format: DatasetFormat,
+) -> Result<()> {
+    match format {
+        DatasetFormat::JsonLines => write_json_lines(writer, examples),
+        DatasetFormat::Csv => write_csv(writer, examples),
+        DatasetFormat::Binary => write_binary(writer, examples),
+        DatasetFormat::Npz => write_npz(writer, examples),
+    }
+}
+
+/// Check an example against the model's input and output dimensions
+pub fn check_dims(example: &TrainingExample, config: &TRMConfig, line: usize) -> Result<()> {
+    for (actual, expected) in [
+        (example.input.ncols(), config.input_dim),
+        (example.target.ncols(), config.output_dim),
+    ] {
+        if actual != expected {
+            return Err(TRMError::DimensionMismatch {
+                expected,
+                actual,
+                line: Some(line),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn read_json_lines<R: BufRead>(reader: R) -> Result<Vec<(usize, TrainingExample)>> {
+    let mut examples = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        examples.push((line_number, parse_json_line(&line, line_number)?));
+    }
+    Ok(examples)
+}
+
+/// Parse one JSON Lines record
+pub(super) fn parse_json_line(line: &str, line_number: usize) -> Result<TrainingExample> {
+    let record: JsonRecord = serde_json::from_str(line)?;
+    Ok(TrainingExample::new(
+        record.input.into_array(line_number)?,
+        record.target.into_array(line_number)?,
+    ))
+}
+
+fn write_json_lines<W: Write>(writer: &mut W, examples: &[TrainingExample]) -> Result<()> {
+    for example in examples {
+        let record = JsonRecord {
+            input: JsonMatrix::from_array(&example.input),
+            target: JsonMatrix::from_array(&example.target),
+        };
+        serde_json::to_writer(&mut *writer, &record)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+fn read_csv<R: BufRead>(reader: R) -> Result<Vec<(usize, TrainingExample)>> {
+    let mut lines = reader.lines();
+    let header = lines
+        .next()
+        .transpose()?
+        .ok_or_else(|| TRMError::InvalidData("missing CSV header".to_string()))?;
+
+    let layout = CsvLayout::parse(&header)?;
+
+    let mut examples = Vec::new();
+    for (index, line) in lines.enumerate() {
+        let line_number = index + 2;
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        examples.push((line_number, layout.parse_row(&line, line_number)?));
+    }
+    Ok(examples)
+}
+
+/// Column layout taken from a CSV header
+#[derive(Debug, Clone, Copy)]
+pub(super) struct CsvLayout {
+    input_dim: usize,
+    target_dim: usize,
+}
+
+impl CsvLayout {
+    /// Parse the header line: `input` columns followed by `target` columns
+    pub(super) fn parse(header: &str) -> Result<Self> {
+        let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+        let input_dim = columns.iter().filter(|c| c.starts_with("input")).count();
+        if !columns[input_dim..].iter().all(|c| c.starts_with("target")) {
+            return Err(TRMError::InvalidData(
+                "CSV header must list `input` columns followed by `target` columns (line 1)"
+                    .to_string(),
+            ));
+        }
+        Ok(Self {
+            input_dim,
+            target_dim: columns.len() - input_dim,
+        })
+    }
+
+    /// Number of `target` columns
+    pub(super) fn target_dim(&self) -> usize {
+        self.target_dim
+    }
+
+    /// Parse one data row
+    pub(super) fn parse_row(&self, line: &str, line_number: usize) -> Result<TrainingExample> {
+        let values: Vec<f32> = line
+            .split(',')
+            .map(|v| v.trim().parse::<f32>())
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|e| TRMError::InvalidData(format!("line {}: {}", line_number, e)))?;
+
+        let columns = self.input_dim + self.target_dim;
+        if values.len() != columns {
+            return Err(TRMError::DimensionMismatch {
+                expected: columns,
+                actual: values.len(),
+                line: Some(line_number),
+            });
+        }
+
+        let input = Array2::from_shape_vec((1, self.input_dim), values[..self.input_dim].to_vec())
+            .expect("slice length matches input dim");
+        let target =
+            Array2::from_shape_vec((1, self.target_dim), values[self.input_dim..].to_vec())
+                .expect("slice length matches target dim");
+        Ok(TrainingExample::new(input, target))
+    }
+}
+
+fn write_csv<W: Write>(writer: &mut W, examples: &[TrainingExample]) -> Result<()> {
+    let Some(first) = examples.first() else {
+        return Ok(());
+    };
+
+    let header: Vec<String> = (0..first.input.ncols())
+        .map(|i| format!("input_{}", i))
+        .chain((0..first.target.ncols()).map(|i| format!("target_{}", i)))
+        .collect();
+    writeln!(writer, "{}", header.join(","))?;
+
+    for example in examples {
+        if example.input.nrows() != 1 || example.target.nrows() != 1 {
+            return Err(TRMError::InvalidData(
+                "CSV supports only single-row inputs and targets".to_string(),
+            ));
+        }
+        if example.input.ncols() != first.input.ncols()
+            || example.target.ncols() != first.target.ncols()
+        {
+            return Err(TRMError::InvalidData(
+                "CSV requires every example to have the same shape".to_string(),
+            ));
+        }
+
+        let row: Vec<String> = example
+            .input
+            .iter()
+            .chain(example.target.iter())
+            .map(|v| v.to_string())
+            .collect();
+        writeln!(writer, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+fn read_binary<R: Read>(mut reader: R) -> Result<Vec<(usize, TrainingExample)>> {
+    // The count is untrusted, so let the vector grow with the records read
+    let count = read_binary_header(&mut reader)?;
+    let mut examples = Vec::new();
+    for index in 0..count {
+        examples.push((index + 1, read_binary_example(&mut reader)?));
+    }
+    Ok(examples)
+}
+
+/// Check the binary header and return the number of examples
+pub(super) fn read_binary_header<R: Read>(reader: &mut R) -> Result<usize> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != BINARY_MAGIC {
+        return Err(TRMError::InvalidData(
+            "not a binary TRM dataset".to_string(),
+        ));
+    }
+
+    let version = read_u32(reader)?;
+    if version != BINARY_VERSION {
+        return Err(TRMError::InvalidData(format!(
+            "unsupported dataset version {}",
+            version
+        )));
+    }
+
+    let count = read_u64(reader)?;
+    usize::try_from(count)
+        .map_err(|_| TRMError::InvalidData(format!("implausible example count {}", count)))
+}
+
+/// Read one binary input/target record
+pub(super) fn read_binary_example<R: Read>(reader: &mut R) -> Result<TrainingExample> {
+    let input = read_binary_array(reader)?;
+    let target = read_binary_array(reader)?;
+    Ok(TrainingExample::new(input, target))
+}
+
+fn write_binary<W: Write>(writer: &mut W, examples: &[TrainingExample]) -> Result<()> {
+    writer.write_all(BINARY_MAGIC)?;
+    writer.write_all(&BINARY_VERSION.to_le_bytes())?;
+    writer.write_all(&(examples.len() as u64).to_le_bytes())?;
+    for example in examples {
+        write_binary_array(writer, &example.input)?;
+        write_binary_array(writer, &example.target)?;
+    }
+    Ok(())
+}
+
+fn read_npz<R: Read>(mut reader: R) -> Result<Vec<(usize, TrainingExample)>> {
+    // Zip archives need random access, so buffer the whole file
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let mut arrays = npy::read_npz(Cursor::new(bytes))?;
+
+    let mut take = |name: &str| -> Result<Array2<f32>> {
+        let array = arrays
+            .remove(name)
+            .ok_or_else(|| TRMError::InvalidData(format!("npz is missing `{}`", name)))?;
+        npy::to_array2(array)
+    };
+    let inputs = take("inputs")?;
+    let targets = take("targets")?;
+
+    if inputs.nrows() != targets.nrows() {
+        return Err(TRMError::InvalidData(format!(
+            "npz has {} inputs but {} targets",
+            inputs.nrows(),
+            targets.nrows()
+        )));
+    }
+
+    Ok(inputs
+        .rows()
+        .into_iter()
+        .zip(targets.rows())
+        .enumerate()
+        .map(|(index, (input, target))| {
+            let example = TrainingExample::new(
+                input.insert_axis(Axis(0)).to_owned(),
+                target.insert_axis(Axis(0)).to_owned(),
+            );
+            (index + 1, example)
+        })
+        .collect())
+}
+
+fn write_npz<W: Write>(writer: &mut W, examples: &[TrainingExample]) -> Result<()> {
+    let stack = |arrays: Vec<_>| -> Result<Array2<f32>> {
+        if arrays.is_empty() {
+            return Ok(Array2::zeros((0, 0)));
+        }
+        ndarray::concatenate(Axis(0), &arrays).map_err(|_| {
+            TRMError::InvalidData("npz requires every example to have the same shape".to_string())
+        })
+    };
+    if examples
+        .iter()
+        .any(|e| e.input.nrows() != 1 || e.target.nrows() != 1)
+    {
+        return Err(TRMError::InvalidData(
+            "npz supports only single-row inputs and targets".to_string(),
+        ));
+    }
+
+    let inputs = stack(examples.iter().map(|e| e.input.view()).collect())?;
+    let targets = stack(examples.iter().map(|e| e.target.view()).collect())?;
+
+    let mut buffer = Cursor::new(Vec::new());
+    npy::write_npz(
+        &mut buffer,
+        &[
+            ("inputs", inputs.view().into_dyn()),
+            ("targets", targets.view().into_dyn()),
+        ],
+    )?;
+    writer.write_all(buffer.get_ref())?;
+    Ok(())
+}
+
+fn read_binary_array<R: Read>(reader: &mut R) -> Result<Array2<f32>> {
+    let rows = read_u32(reader)? as usize;
+    let cols = read_u32(reader)? as usize;
+    let len = rows
+        .checked_mul(cols)
+        .and_then(|values| values.checked_mul(4))
+        .ok_or_else(|| {
+            TRMError::InvalidData(format!("array shape {}x{} is too large", rows, cols))
+        })?;
+    let bytes = read_bytes(reader, len)?;
+    let values = bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    Ok(Array2::from_shape_vec((rows, cols), values).expect("value count matches shape"))
+}
+
+fn write_binary_array<W: Write>(writer: &mut W, array: &Array2<f32>) -> Result<()> {
+    writer.write_all(&(array.nrows() as u32).to_le_bytes())?;
+    writer.write_all(&(array.ncols() as u32).to_le_bytes())?;
+    for value in array.iter() {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::tasks::CopyTask;
+    use ndarray::array;
+
+    fn round_trip(format: DatasetFormat, examples: &[TrainingExample]) -> Vec<TrainingExample> {
+        let mut buffer = Vec::new();
+        write_examples(&mut buffer, examples, format).unwrap();
+        read_examples(buffer.as_slice(), format, None).unwrap()
+    }
+
+    fn config(input_dim: usize, output_dim: usize) -> TRMConfig {
+        TRMConfig {
+            input_dim,
+            output_dim,
+            ..TRMConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            DatasetFormat::from_path("data/set.jsonl").unwrap(),
+            DatasetFormat::JsonLines
+        );
+        assert_eq!(
+            DatasetFormat::from_path("set.CSV").unwrap(),
+            DatasetFormat::Csv
+        );
+        assert_eq!(
+            DatasetFormat::from_path("set.bin").unwrap(),
+            DatasetFormat::Binary
+        );
+        assert_eq!(
+            DatasetFormat::from_path("set.npz").unwrap(),
+            DatasetFormat::Npz
+        );
+        assert!(DatasetFormat::from_path("set.txt").is_err());
+    }
+
+    #[test]
+    fn test_round_trip_all_formats() {
+        let task = CopyTask::new(5, 4);
+        for format in [
+            DatasetFormat::JsonLines,
+            DatasetFormat::Csv,
+            DatasetFormat::Binary,
+            DatasetFormat::Npz,
+        ] {
+            let loaded = round_trip(format, task.examples());
+            assert_eq!(loaded.len(), 5);
+            for (a, b) in loaded.iter().zip(task.examples()) {
+                assert_eq!(a.input, b.input);
+                assert_eq!(a.target, b.target);
+            }
+        }
+    }
+
+    #[test]
+    fn test_json_lines_multi_row_and_flat() {
+        let examples = vec![TrainingExample::new(
+            array![[1.0, 2.0], [3.0, 4.0]],
+            array![[5.0]],
+        )];
+        assert_eq!(
+            round_trip(DatasetFormat::JsonLines, &examples)[0]
+                .input
+                .shape(),
+            &[2, 2]
+        );
+
+        let flat = r#"{"input": [1.0, 2.0], "target": [3.0]}"#;
+        let loaded = read_examples(flat.as_bytes(), DatasetFormat::JsonLines, None).unwrap();
+        assert_eq!(loaded[0].input, array![[1.0, 2.0]]);
+    }
+
+    #[test]
+    fn test_dimension_mismatch_reports_line() {
+        let jsonl = "{\"input\": [1.0, 2.0], \"target\": [1.0]}\n\
+                     {\"input\": [1.0, 2.0, 3.0], \"target\": [1.0]}\n";
+        let err = read_examples(
+            jsonl.as_bytes(),
+            DatasetFormat::JsonLines,
+            Some(&config(2, 1)),
+        )
+        .unwrap_err();
+        match err {
+            TRMError::DimensionMismatch {
+                expected,
+                actual,
+                line,
+            } => {
+                assert_eq!((expected, actual, line), (2, 3, Some(2)));
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+
+        let csv = "input_0,input_1,target_0\n1,2,3\n4,5\n";
+        let err = read_examples(csv.as_bytes(), DatasetFormat::Csv, None).unwrap_err();
+        assert!(err.to_string().contains("line 3"), "{}", err);
+
+        let csv = "input_0,target_0\n1,2\n";
+        let err =
+            read_examples(csv.as_bytes(), DatasetFormat::Csv, Some(&config(2, 1))).unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{}", err);
+    }
+
+    #[test]
+    fn test_binary_rejects_bad_header() {
+        assert!(read_examples(&b"NOPE"[..], DatasetFormat::Binary, None).is_err());
+    }
+
+    #[test]
+    fn test_binary_rejects_huge_and_truncated_headers() {
+        let header = |count: u64, rows: u32, cols: u32| {
+            let mut bytes = BINARY_MAGIC.to_vec();
+            bytes.extend(BINARY_VERSION.to_le_bytes());
+            bytes.extend(count.to_le_bytes());
+            bytes.extend(rows.to_le_bytes());
+            bytes.extend(cols.to_le_bytes());
+            bytes.extend([0u8; 8]);
+            bytes
+        };
+        for (count, rows, cols) in [(u64::MAX, 1, 1), (1, u32::MAX, u32::MAX), (1, 1000, 1000)] {
+            // Fails on the missing data instead of allocating for the header
+            let bytes = header(count, rows, cols);
+            assert!(read_examples(&bytes[..], DatasetFormat::Binary, None).is_err());
+        }
+    }
+
+    #[test]
+    fn test_save_and_load_file() {
+        let path = std::env::temp_dir().join("train-trm-io-test.bin");
+        let task = CopyTask::new(3, 2);
+        save_dataset(&path, task.examples()).unwrap();
+
+        let loaded = load_dataset(&path, Some(&config(2, 2))).unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert!(load_dataset(&path, Some(&config(3, 2))).is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,241 @@
+//! Cell encodings for maze inputs
+//!
+//! Every encoding lays the grid out row by row with a fixed number of
+//! channels per cell, so a `width x height` maze becomes
+//! `width * height * channels` values.
+
+use super::{Cell, Maze};
+use crate::data::augment::Symmetry;
+use crate::utils::TRMError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Cell types in one-hot channel order
+const CELLS: [Cell; 4] = [Cell::Wall, Cell::Path, Cell::Start, Cell::Goal];
+
+/// How maze cells are turned into network inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MazeEncoding {
+    /// One value per cell from [`Cell::to_f32`]
+    #[default]
+    Scalar,
+    /// Four channels per cell: wall, path, start, goal
+    OneHot,
+    /// Three binary channels per cell: wall, start, goal (open path is all zero)
+    Channels,
+    /// The three binary channels plus row and column scaled to `[0, 1]`
+    Coordinates,
+}
+
+impl MazeEncoding {
+    /// Every encoding, in declaration order
+    pub const ALL: [MazeEncoding; 4] = [
+        MazeEncoding::Scalar,
+        MazeEncoding::OneHot,
+        MazeEncoding::Channels,
+        MazeEncoding::Coordinates,
+    ];
+
+    /// Lowercase name, as accepted by `from_str`
+    pub fn name(self) -> &'static str {
+        match self {
+            MazeEncoding::Scalar => "scalar",
+            MazeEncoding::OneHot => "onehot",
+            MazeEncoding::Channels => "channels",
+            MazeEncoding::Coordinates => "coordinates",
+        }
+    }
+
+    /// Values per cell
+    pub fn channels(self) -> usize {
+        match self {
+            MazeEncoding::Scalar => 1,
+            MazeEncoding::OneHot => 4,
+            MazeEncoding::Channels => 3,
+            MazeEncoding::Coordinates => 5,
+        }
+    }
+
+    /// The encoding that turns `cells` cells into `input_dim` values, if any
+    pub fn for_input_dim(input_dim: usize, cells: usize) -> Option<Self> {
+        MazeEncoding::ALL
+            .into_iter()
+            .find(|encoding| encoding.channels() * cells == input_dim)
+    }
+
+    /// Encode a whole maze
+    pub fn encode(self, maze: &Maze) -> Vec<f32> {
+        let mut values = Vec::with_capacity(maze.width * maze.height * self.channels());
+        for (row, cells) in maze.grid.iter().enumerate() {
+            for (col, &cell) in cells.iter().enumerate() {
+                self.encode_cell(cell, &mut values);
+                if self == MazeEncoding::Coordinates {
+                    values.push(row as f32 / (maze.height - 1) as f32);
+                    values.push(col as f32 / (maze.width - 1) as f32);
+                }
+            }
+        }
+        values
+    }
+
+    fn encode_cell(self, cell: Cell, values: &mut Vec<f32>) {
+        let flag = |on: bool| if on { 1.0 } else { 0.0 };
+        match self {
+            MazeEncoding::Scalar => values.push(cell.to_f32()),
+            MazeEncoding::OneHot => values.extend(CELLS.iter().map(|&c| flag(c == cell))),
+            MazeEncoding::Channels | MazeEncoding::Coordinates => values.extend([
+                flag(cell == Cell::Wall),
+                flag(cell == Cell::Start),
+                flag(cell == Cell::Goal),
+            ]),
+        }
+    }
+
+    /// Decode one cell's channels into the most likely cell and its confidence
+    ///
+    /// For multi-channel encodings the confidence is the softmax probability
+    /// of the winning cell type; for `Scalar` it falls from 1.0 at a cell's
+    /// level to 0.5 halfway to the next one.
+    pub fn decode_cell(self, values: &[f32]) -> (Cell, f32) {
+        let scores = match self {
+            MazeEncoding::Scalar => {
+                let cell = Cell::from_f32(values[0]);
+                let confidence = 1.0 - (values[0] - cell.to_f32()).abs() * 4.0;
+                return (cell, confidence.clamp(0.0, 1.0));
+            }
+            MazeEncoding::OneHot => [values[0], values[1], values[2], values[3]],
+            MazeEncoding::Channels | MazeEncoding::Coordinates => {
+                let (wall, start, goal) = (values[0], values[1], values[2]);
+                [wall, 1.0 - wall.max(start).max(goal), start, goal]
+            }
+        };
+
+        let (best, &best_score) = scores
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .expect("four scores");
+        let total: f32 = scores.iter().map(|s| (s - best_score).exp()).sum();
+        (CELLS[best], 1.0 / total)
+    }
+
+    /// Decode an encoded maze into per-cell argmax cells and confidences
+    ///
+    /// Returns `None` if the length does not match a `width`-wide grid.
+    pub fn decode(self, values: &[f32], width: usize) -> Option<Vec<Vec<(Cell, f32)>>> {
+        let channels = self.channels();
+        if width == 0 || values.len() % (width * channels) != 0 {
+            return None;
+        }
+        let cells: Vec<(Cell, f32)> = values
+            .chunks(channels)
+            .map(|cell| self.decode_cell(cell))
+            .collect();
+        Some(cells.chunks(width).map(<[_]>::to_vec).collect())
+    }
+
+    /// Rotations and reflections that are valid for this encoding
+    ///
+    /// `None` for `Coordinates`, whose position channels must not move.
+    pub fn symmetry(self, height: usize, width: usize) -> Option<Symmetry> {
+        match self {
+            MazeEncoding::Coordinates => None,
+            _ => Some(Symmetry::Dihedral {
+                height,
+                width,
+                input_channels: self.channels(),
+                target_channels: 1,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for MazeEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for MazeEncoding {
+    type Err = TRMError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MazeEncoding::ALL
+            .into_iter()
+            .find(|encoding| encoding.name() == s.to_ascii_lowercase())
+            .ok_or_else(|| TRMError::InvalidData(format!("unknown maze encoding: {:?}", s)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_maze() -> Maze {
+        Maze::from_ascii("#####\n#S  #\n### #\n#G  #\n#####").unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_every_encoding() {
+        let maze = sample_maze();
+        for encoding in MazeEncoding::ALL {
+            let values = encoding.encode(&maze);
+            assert_eq!(values.len(), 25 * encoding.channels());
+
+            let decoded = encoding.decode(&values, 5).unwrap();
+            for (row, cells) in decoded.iter().enumerate() {
+                for (col, &(cell, confidence)) in cells.iter().enumerate() {
+                    assert_eq!(cell, maze.grid[row][col], "{} ({}, {})", encoding, row, col);
+                    assert!(confidence > 0.4, "{}", encoding);
+                }
+            }
+            assert_eq!(encoding.name().parse::<MazeEncoding>().unwrap(), encoding);
+        }
+    }
+
+    #[test]
+    fn test_encoding_for_input_dim() {
+        assert_eq!(
+            MazeEncoding::for_input_dim(49, 49),
+            Some(MazeEncoding::Scalar)
+        );
+        assert_eq!(
+            MazeEncoding::for_input_dim(4 * 49, 49),
+            Some(MazeEncoding::OneHot)
+        );
+        assert_eq!(MazeEncoding::for_input_dim(2 * 49, 49), None);
+    }
+
+    #[test]
+    fn test_channel_layouts() {
+        let maze = sample_maze();
+
+        let one_hot = MazeEncoding::OneHot.encode(&maze);
+        // (1, 1) is the start: cell 6, one-hot index 2
+        assert_eq!(&one_hot[24..28], &[0.0, 0.0, 1.0, 0.0]);
+
+        let coords = MazeEncoding::Coordinates.encode(&maze);
+        // Cell (3, 1) is the goal, at 3/4 of the height and 1/4 of the width
+        assert_eq!(&coords[16 * 5..17 * 5], &[0.0, 0.0, 1.0, 0.75, 0.25]);
+    }
+
+    #[test]
+    fn test_decode_confidence_under_noise() {
+        let (cell, sure) = MazeEncoding::OneHot.decode_cell(&[0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(cell, Cell::Goal);
+        let (cell, unsure) = MazeEncoding::OneHot.decode_cell(&[0.4, 0.1, 0.0, 0.5]);
+        assert_eq!(cell, Cell::Goal);
+        assert!(unsure < sure);
+
+        // Open path is all-zero in the binary channels
+        assert_eq!(
+            MazeEncoding::Channels.decode_cell(&[0.1, 0.05, 0.0]).0,
+            Cell::Path
+        );
+        let (cell, confidence) = MazeEncoding::Scalar.decode_cell(&[0.3]);
+        assert_eq!(cell, Cell::Path);
+        assert!((confidence - 0.8).abs() < 1e-6);
+
+        assert!(MazeEncoding::OneHot.decode(&[0.0; 10], 5).is_none());
+    }
+}
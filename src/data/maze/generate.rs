@@ -0,0 +1,644 @@
+//! Maze generation algorithms
+//!
+//! Generators work on a lattice of cells at odd grid coordinates separated by
+//! walls at even coordinates, and produce perfect mazes: exactly one route
+//! between any two cells. Braiding then removes dead ends, which adds loops
+//! and alternative solutions. When a dimension is even, the last lattice
+//! column (or row) is doubled to fill the spare line next to the border.
+//!
+//! All generators are iterative, so large mazes do not exhaust the stack.
+
+use super::{Cell, Maze};
+use crate::utils::{Result, TRMError};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Algorithm used to carve a perfect maze
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MazeAlgorithm {
+    /// Depth-first backtracker: long, winding corridors
+    #[default]
+    Backtracker,
+    /// Randomised Prim's: many short branches
+    Prim,
+    /// Randomised Kruskal's: uniform-looking, lots of short dead ends
+    Kruskal,
+    /// Wilson's loop-erased random walks: uniform spanning tree, unbiased
+    Wilson,
+    /// Eller's row-by-row set merging: horizontal bias
+    Eller,
+}
+
+impl MazeAlgorithm {
+    /// Every algorithm, in declaration order
+    pub const ALL: [MazeAlgorithm; 5] = [
+        MazeAlgorithm::Backtracker,
+        MazeAlgorithm::Prim,
+        MazeAlgorithm::Kruskal,
+        MazeAlgorithm::Wilson,
+        MazeAlgorithm::Eller,
+    ];
+
+    /// Lowercase name, as accepted by `from_str`
+    pub fn name(self) -> &'static str {
+        match self {
+            MazeAlgorithm::Backtracker => "backtracker",
+            MazeAlgorithm::Prim => "prim",
+            MazeAlgorithm::Kruskal => "kruskal",
+            MazeAlgorithm::Wilson => "wilson",
+            MazeAlgorithm::Eller => "eller",
+        }
+    }
+}
+
+impl fmt::Display for MazeAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for MazeAlgorithm {
+    type Err = TRMError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        MazeAlgorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name() == s.to_ascii_lowercase())
+            .ok_or_else(|| TRMError::InvalidData(format!("unknown maze algorithm: {:?}", s)))
+    }
+}
+
+/// Where the start and goal go
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Placement {
+    /// Start in the top-left cell, goal in the bottom-right cell
+    #[default]
+    Corners,
+    /// Two distinct random cells
+    Random,
+    /// Given positions; both must be distinct lattice cells (odd row and odd
+    /// column inside the border)
+    Fixed {
+        start: (usize, usize),
+        goal: (usize, usize),
+    },
+}
+
+/// Maze generation settings
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MazeGenerator {
+    /// Carving algorithm
+    pub algorithm: MazeAlgorithm,
+    /// Fraction of dead ends to remove (0.0 = perfect maze, 1.0 = none left)
+    pub braid: f32,
+    /// Start and goal placement
+    pub placement: Placement,
+}
+
+impl MazeGenerator {
+    /// Perfect mazes from the given algorithm with corner start and goal
+    pub fn new(algorithm: MazeAlgorithm) -> Self {
+        Self {
+            algorithm,
+            ..Self::default()
+        }
+    }
+
+    /// Generate a `width x height` maze
+    ///
+    /// Fails if either dimension is under 3, if a fixed start or goal is not
+    /// a lattice cell, or if random placement has fewer than two lattice cells
+    /// to choose from.
+    pub fn generate(&self, width: usize, height: usize, rng: &mut impl Rng) -> Result<Maze> {
+        let mut maze = Maze::new(width, height)?;
+        let lattice = Lattice::new(width, height);
+
+        match self.algorithm {
+            MazeAlgorithm::Backtracker => lattice.backtracker(&mut maze, rng),
+            MazeAlgorithm::Prim => lattice.prim(&mut maze, rng),
+            MazeAlgorithm::Kruskal => lattice.kruskal(&mut maze, rng),
+            MazeAlgorithm::Wilson => lattice.wilson(&mut maze, rng),
+            MazeAlgorithm::Eller => lattice.eller(&mut maze, rng),
+        }
+
+        if self.braid > 0.0 {
+            lattice.braid(&mut maze, self.braid, rng);
+        }
+        lattice.fill_even_edges(&mut maze);
+
+        let (start, goal) = match self.placement {
+            Placement::Corners => (lattice.pos(0), (height - 2, width - 2)),
+            Placement::Random => {
+                let cells: Vec<usize> = (0..lattice.len()).collect();
+                match cells.choose_multiple(rng, 2).collect::<Vec<_>>()[..] {
+                    [&a, &b] => (lattice.pos(a), lattice.pos(b)),
+                    _ => {
+                        return Err(TRMError::InvalidData(format!(
+                            "a {}x{} maze has no room for a random start and goal",
+                            width, height
+                        )))
+                    }
+                }
+            }
+            Placement::Fixed { start, goal } => {
+                if !lattice.contains(start) || !lattice.contains(goal) || start == goal {
+                    return Err(TRMError::InvalidData(format!(
+                        "start {:?} and goal {:?} must be distinct cells at odd coordinates \
+                         inside a {}x{} maze",
+                        start, goal, width, height
+                    )));
+                }
+                (start, goal)
+            }
+        };
+
+        maze.start = start;
+        maze.goal = goal;
+        maze.grid[start.0][start.1] = Cell::Start;
+        maze.grid[goal.0][goal.1] = Cell::Goal;
+        Ok(maze)
+    }
+}
+
+/// Cells at odd coordinates, indexed row by row
+struct Lattice {
+    rows: usize,
+    cols: usize,
+}
+
+impl Lattice {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            rows: (height - 1) / 2,
+            cols: (width - 1) / 2,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.rows * self.cols
+    }
+
+    /// Whether a grid position is a lattice cell
+    fn contains(&self, (row, col): (usize, usize)) -> bool {
+        row % 2 == 1 && col % 2 == 1 && row < 2 * self.rows && col < 2 * self.cols
+    }
+
+    /// Grid position of a cell
+    fn pos(&self, cell: usize) -> (usize, usize) {
+        (2 * (cell / self.cols) + 1, 2 * (cell % self.cols) + 1)
+    }
+
+    fn neighbors(&self, cell: usize) -> Vec<usize> {
+        let (row, col) = (cell / self.cols, cell % self.cols);
+        let mut neighbors = Vec::with_capacity(4);
+        if row > 0 {
+            neighbors.push(cell - self.cols);
+        }
+        if row + 1 < self.rows {
+            neighbors.push(cell + self.cols);
+        }
+        if col > 0 {
+            neighbors.push(cell - 1);
+        }
+        if col + 1 < self.cols {
+            neighbors.push(cell + 1);
+        }
+        neighbors
+    }
+
+    /// Grid position of the wall between two adjacent cells
+    fn wall(&self, a: usize, b: usize) -> (usize, usize) {
+        let (pa, pb) = (self.pos(a), self.pos(b));
+        ((pa.0 + pb.0) / 2, (pa.1 + pb.1) / 2)
+    }
+
+    fn open_all(&self, maze: &mut Maze) {
+        for cell in 0..self.len() {
+            let (row, col) = self.pos(cell);
+            maze.grid[row][col] = Cell::Path;
+        }
+    }
+
+    fn connect(&self, maze: &mut Maze, a: usize, b: usize) {
+        let (row, col) = self.wall(a, b);
+        maze.grid[row][col] = Cell::Path;
+    }
+
+    fn connected(&self, maze: &Maze, a: usize, b: usize) -> bool {
+        let (row, col) = self.wall(a, b);
+        maze.grid[row][col] != Cell::Wall
+    }
+
+    fn is_dead_end(&self, maze: &Maze, cell: usize) -> bool {
+        self.neighbors(cell)
+            .into_iter()
+            .filter(|&n| self.connected(maze, cell, n))
+            .count()
+            == 1
+    }
+
+    /// Copy the last lattice column and row into the spare line left by an
+    /// even width or height
+    fn fill_even_edges(&self, maze: &mut Maze) {
+        let (width, height) = (maze.width, maze.height);
+        if width % 2 == 0 {
+            for row in maze.grid.iter_mut() {
+                row[width - 2] = row[width - 3];
+            }
+        }
+        if height % 2 == 0 {
+            maze.grid[height - 2] = maze.grid[height - 3].clone();
+        }
+    }
+
+    /// Depth-first search with an explicit stack, carving into a random
+    /// unvisited neighbour until every cell is reached
+    fn backtracker(&self, maze: &mut Maze, rng: &mut impl Rng) {
+        self.open_all(maze);
+        let mut visited = vec![false; self.len()];
+        let mut stack = vec![0];
+        visited[0] = true;
+
+        while let Some(&cell) = stack.last() {
+            let unvisited: Vec<usize> = self
+                .neighbors(cell)
+                .into_iter()
+                .filter(|&n| !visited[n])
+                .collect();
+            match unvisited.choose(rng) {
+                Some(&next) => {
+                    self.connect(maze, cell, next);
+                    visited[next] = true;
+                    stack.push(next);
+                }
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    /// Grow the maze from a random cell, joining a random frontier cell each step
+    fn prim(&self, maze: &mut Maze, rng: &mut impl Rng) {
+        self.open_all(maze);
+        let mut in_maze = vec![false; self.len()];
+        let mut in_frontier = vec![false; self.len()];
+        let mut frontier = Vec::new();
+
+        let mut add = |cell: usize, in_maze: &mut Vec<bool>, frontier: &mut Vec<usize>| {
+            in_maze[cell] = true;
+            for neighbor in self.neighbors(cell) {
+                if !in_maze[neighbor] && !in_frontier[neighbor] {
+                    in_frontier[neighbor] = true;
+                    frontier.push(neighbor);
+                }
+            }
+        };
+
+        add(rng.gen_range(0..self.len()), &mut in_maze, &mut frontier);
+        while !frontier.is_empty() {
+            let cell = frontier.swap_remove(rng.gen_range(0..frontier.len()));
+            let linked: Vec<usize> = self
+                .neighbors(cell)
+                .into_iter()
+                .filter(|&n| in_maze[n])
+                .collect();
+            let &from = linked.choose(rng).expect("frontier cells touch the maze");
+            self.connect(maze, from, cell);
+            add(cell, &mut in_maze, &mut frontier);
+        }
+    }
+
+    /// Knock down walls in random order unless they would close a loop
+    fn kruskal(&self, maze: &mut Maze, rng: &mut impl Rng) {
+        self.open_all(maze);
+        let mut edges: Vec<(usize, usize)> = (0..self.len())
+            .flat_map(|cell| {
+                self.neighbors(cell)
+                    .into_iter()
+                    .filter(move |&n| n > cell)
+                    .map(move |n| (cell, n))
+            })
+            .collect();
+        edges.shuffle(rng);
+
+        let mut parent: Vec<usize> = (0..self.len()).collect();
+        fn find(parent: &mut [usize], mut cell: usize) -> usize {
+            while parent[cell] != cell {
+                parent[cell] = parent[parent[cell]];
+                cell = parent[cell];
+            }
+            cell
+        }
+
+        for (a, b) in edges {
+            let (root_a, root_b) = (find(&mut parent, a), find(&mut parent, b));
+            if root_a != root_b {
+                parent[root_a] = root_b;
+                self.connect(maze, a, b);
+            }
+        }
+    }
+
+    /// Add loop-erased random walks until every cell is in the maze
+    fn wilson(&self, maze: &mut Maze, rng: &mut impl Rng) {
+        self.open_all(maze);
+        let mut in_maze = vec![false; self.len()];
+        in_maze[rng.gen_range(0..self.len())] = true;
+
+        let mut order: Vec<usize> = (0..self.len()).collect();
+        order.shuffle(rng);
+
+        // Each walk step overwrites `next`, which erases any loop it closed
+        let mut next = vec![0; self.len()];
+        for start in order {
+            let mut cell = start;
+            while !in_maze[cell] {
+                let step = *self
+                    .neighbors(cell)
+                    .choose(rng)
+                    .expect("lattice cells have neighbours");
+                next[cell] = step;
+                cell = step;
+            }
+
+            let mut cell = start;
+            while !in_maze[cell] {
+                self.connect(maze, cell, next[cell]);
+                in_maze[cell] = true;
+                cell = next[cell];
+            }
+        }
+    }
+
+    /// Build the maze one row at a time, tracking connected sets
+    fn eller(&self, maze: &mut Maze, rng: &mut impl Rng) {
+        self.open_all(maze);
+        let index = |row: usize, col: usize| row * self.cols + col;
+
+        let mut next_set = 0;
+        let mut sets: Vec<usize> = (0..self.cols)
+            .map(|_| {
+                next_set += 1;
+                next_set
+            })
+            .collect();
+
+        for row in 0..self.rows {
+            let last = row + 1 == self.rows;
+
+            // Randomly join neighbours in different sets; the last row joins all
+            for col in 0..self.cols.saturating_sub(1) {
+                if sets[col] != sets[col + 1] && (last || rng.gen_bool(0.5)) {
+                    self.connect(maze, index(row, col), index(row, col + 1));
+                    let (keep, merge) = (sets[col], sets[col + 1]);
+                    for set in sets.iter_mut().filter(|set| **set == merge) {
+                        *set = keep;
+                    }
+                }
+            }
+            if last {
+                break;
+            }
+
+            // Every set continues down through at least one cell
+            let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+            for (col, &set) in sets.iter().enumerate() {
+                members.entry(set).or_default().push(col);
+            }
+            let mut below = vec![0; self.cols];
+            for (set, mut cols) in members {
+                cols.shuffle(rng);
+                let count = rng.gen_range(1..=cols.len());
+                for &col in &cols[..count] {
+                    self.connect(maze, index(row, col), index(row + 1, col));
+                    below[col] = set;
+                }
+            }
+            for set in below.iter_mut().filter(|set| **set == 0) {
+                next_set += 1;
+                *set = next_set;
+            }
+            sets = below;
+        }
+    }
+
+    /// Remove a fraction of dead ends by opening one more wall at each
+    ///
+    /// Opening towards another dead end is preferred, since it removes two.
+    fn braid(&self, maze: &mut Maze, fraction: f32, rng: &mut impl Rng) {
+        let mut dead_ends: Vec<usize> = (0..self.len())
+            .filter(|&cell| self.is_dead_end(maze, cell))
+            .collect();
+        dead_ends.shuffle(rng);
+        let count = (dead_ends.len() as f32 * fraction.clamp(0.0, 1.0)).round() as usize;
+
+        for &cell in &dead_ends[..count] {
+            if !self.is_dead_end(maze, cell) {
+                continue;
+            }
+            let closed: Vec<usize> = self
+                .neighbors(cell)
+                .into_iter()
+                .filter(|&n| !self.connected(maze, cell, n))
+                .collect();
+            let preferred: Vec<usize> = closed
+                .iter()
+                .copied()
+                .filter(|&n| self.is_dead_end(maze, n))
+                .collect();
+            let choices = if preferred.is_empty() {
+                closed
+            } else {
+                preferred
+            };
+            if let Some(&neighbor) = choices.choose(rng) {
+                self.connect(maze, cell, neighbor);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn open_cells(maze: &Maze) -> usize {
+        maze.grid
+            .iter()
+            .flatten()
+            .filter(|&&cell| cell != Cell::Wall)
+            .count()
+    }
+
+    fn dead_ends(maze: &Maze) -> usize {
+        let lattice = Lattice::new(maze.width, maze.height);
+        (0..lattice.len())
+            .filter(|&cell| lattice.is_dead_end(maze, cell))
+            .count()
+    }
+
+    #[test]
+    fn test_algorithms_produce_perfect_mazes() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for algorithm in MazeAlgorithm::ALL {
+            let mut maze = MazeGenerator::new(algorithm)
+                .generate(15, 11, &mut rng)
+                .unwrap();
+
+            // A spanning tree over 7x5 cells opens 35 cells and 34 walls
+            assert_eq!(open_cells(&maze), 35 + 34, "{}", algorithm);
+            assert!(maze.solve(), "{}", algorithm);
+            assert_eq!(maze.start, (1, 1));
+            assert_eq!(maze.goal, (9, 13));
+        }
+    }
+
+    #[test]
+    fn test_algorithm_names_round_trip() {
+        for algorithm in MazeAlgorithm::ALL {
+            assert_eq!(
+                algorithm.to_string().parse::<MazeAlgorithm>().unwrap(),
+                algorithm
+            );
+        }
+        assert_eq!(
+            "Prim".parse::<MazeAlgorithm>().unwrap(),
+            MazeAlgorithm::Prim
+        );
+        assert!("dfs".parse::<MazeAlgorithm>().is_err());
+    }
+
+    #[test]
+    fn test_braiding_removes_dead_ends() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for algorithm in MazeAlgorithm::ALL {
+            let perfect = MazeGenerator::new(algorithm)
+                .generate(21, 21, &mut rng)
+                .unwrap();
+            assert!(dead_ends(&perfect) > 0);
+
+            let generator = MazeGenerator {
+                braid: 1.0,
+                ..MazeGenerator::new(algorithm)
+            };
+            let mut braided = generator.generate(21, 21, &mut rng).unwrap();
+            assert_eq!(dead_ends(&braided), 0, "{}", algorithm);
+            // Loops mean more open walls than a spanning tree has
+            assert!(open_cells(&braided) > 100 + 99);
+            assert!(braided.solve());
+        }
+    }
+
+    #[test]
+    fn test_placement() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let random = MazeGenerator {
+            placement: Placement::Random,
+            ..MazeGenerator::new(MazeAlgorithm::Wilson)
+        };
+        for _ in 0..10 {
+            let mut maze = random.generate(9, 9, &mut rng).unwrap();
+            assert_ne!(maze.start, maze.goal);
+            assert_eq!(maze.grid[maze.start.0][maze.start.1], Cell::Start);
+            assert_eq!(maze.grid[maze.goal.0][maze.goal.1], Cell::Goal);
+            assert!(maze.solve());
+        }
+
+        let fixed = MazeGenerator {
+            placement: Placement::Fixed {
+                start: (7, 1),
+                goal: (1, 7),
+            },
+            ..MazeGenerator::new(MazeAlgorithm::Kruskal)
+        };
+        let mut maze = fixed.generate(9, 9, &mut rng).unwrap();
+        assert_eq!((maze.start, maze.goal), ((7, 1), (1, 7)));
+        assert!(maze.solve());
+    }
+
+    #[test]
+    fn test_large_maze_does_not_overflow_stack() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut maze = MazeGenerator::default()
+            .generate(501, 501, &mut rng)
+            .unwrap();
+        assert_eq!(open_cells(&maze), 2 * 250 * 250 - 1);
+        assert!(maze.solve());
+    }
+
+    #[test]
+    fn test_even_and_rectangular_sizes() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for algorithm in MazeAlgorithm::ALL {
+            for (width, height) in [(3, 3), (4, 4), (8, 6), (10, 7), (5, 12)] {
+                let mut maze = MazeGenerator::new(algorithm)
+                    .generate(width, height, &mut rng)
+                    .unwrap();
+                assert_eq!((maze.width, maze.height), (width, height));
+                assert_eq!(maze.goal, (height - 2, width - 2));
+                assert!(maze.solve(), "{} {}x{}", algorithm, width, height);
+
+                // The border stays closed
+                assert!(maze.grid[0].iter().all(|&cell| cell == Cell::Wall));
+                assert!(maze.grid.iter().all(|row| row[width - 1] == Cell::Wall));
+            }
+        }
+
+        assert!(MazeGenerator::default().generate(2, 9, &mut rng).is_err());
+        let outside = MazeGenerator {
+            placement: Placement::Fixed {
+                start: (1, 1),
+                goal: (9, 9),
+            },
+            ..MazeGenerator::default()
+        };
+        assert!(outside.generate(9, 9, &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_placement_needs_lattice_cells() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let fixed = |start, goal| MazeGenerator {
+            placement: Placement::Fixed { start, goal },
+            ..MazeGenerator::default()
+        };
+        // Border, wall and even-edge cells, and a shared cell
+        for (start, goal) in [
+            ((0, 0), (7, 7)),
+            ((1, 1), (2, 2)),
+            ((1, 1), (8, 7)),
+            ((3, 3), (3, 3)),
+        ] {
+            assert!(fixed(start, goal).generate(10, 9, &mut rng).is_err());
+        }
+        assert!(fixed((1, 7), (7, 1)).generate(10, 9, &mut rng).is_ok());
+
+        let random = MazeGenerator {
+            placement: Placement::Random,
+            ..MazeGenerator::default()
+        };
+        assert!(random.generate(3, 3, &mut rng).is_err());
+        assert!(random.generate(3, 9, &mut rng).is_ok());
+    }
+
+    #[test]
+    fn test_seeded_generation_is_reproducible() {
+        let generator = MazeGenerator {
+            braid: 0.5,
+            ..MazeGenerator::new(MazeAlgorithm::Eller)
+        };
+        let a = generator
+            .generate(13, 13, &mut StdRng::seed_from_u64(7))
+            .unwrap();
+        let b = generator
+            .generate(13, 13, &mut StdRng::seed_from_u64(7))
+            .unwrap();
+        assert_eq!(a.grid, b.grid);
+    }
+}
@@ -0,0 +1,341 @@
+//! Reading and writing mazes as ASCII art and JSON
+//!
+//! ASCII mazes use `█` or `#` for walls, a space for open cells, `S` and `G`
+//! for the start and goal, and `·` for open cells on the solution path.
+//! Short lines are padded with open cells, so trailing spaces may be dropped.
+//!
+//! Maze files hold several mazes:
+//! - **JSON** (`.json`): an array of `{"rows": [...], "solution": [...]}` objects
+//! - **ASCII** (any other extension): mazes separated by blank lines; lines
+//!   starting with `;` are comments
+
+use super::{Cell, Maze};
+use crate::utils::{Result, TRMError};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Marker for open cells on the solution path
+const PATH_MARKER: char = '·';
+
+impl Maze {
+    /// Render as ASCII art, marking the solution path if there is one
+    pub fn to_ascii(&self) -> String {
+        let mut on_path = vec![vec![false; self.width]; self.height];
+        for &(row, col) in self.solution.iter().flatten() {
+            on_path[row][col] = true;
+        }
+
+        let mut text = String::with_capacity(self.height * (self.width * 3 + 1));
+        for (row_idx, row) in self.grid.iter().enumerate() {
+            for (col_idx, &cell) in row.iter().enumerate() {
+                text.push(match cell {
+                    Cell::Path if on_path[row_idx][col_idx] => PATH_MARKER,
+                    Cell::Wall => '█',
+                    Cell::Path => ' ',
+                    Cell::Start => 'S',
+                    Cell::Goal => 'G',
+                });
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Parse ASCII art as produced by [`Maze::to_ascii`]
+    ///
+    /// If any cell carries a `·` marker, the marked cells must connect the
+    /// start to the goal and become the maze's solution.
+    pub fn from_ascii(text: &str) -> Result<Self> {
+        let lines: Vec<(usize, &str)> = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line))
+            .collect();
+        parse_lines(&lines)
+    }
+}
+
+/// Parse a block of numbered lines into a maze
+fn parse_lines(lines: &[(usize, &str)]) -> Result<Maze> {
+    let first_line = lines.first().map_or(1, |&(number, _)| number);
+    let height = lines.len();
+    let width = lines
+        .iter()
+        .map(|(_, line)| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut maze = Maze::new(width, height)
+        .map_err(|e| TRMError::InvalidData(format!("maze at line {}: {}", first_line, e)))?;
+
+    let mut start = None;
+    let mut goal = None;
+    let mut marked = vec![0.0; width * height];
+
+    for (row, &(number, line)) in lines.iter().enumerate() {
+        for (col, ch) in line.chars().enumerate() {
+            let cell = match ch {
+                '█' | '#' => Cell::Wall,
+                ' ' => Cell::Path,
+                PATH_MARKER => {
+                    marked[row * width + col] = 1.0;
+                    Cell::Path
+                }
+                'S' | 'G' => {
+                    let slot = if ch == 'S' { &mut start } else { &mut goal };
+                    if slot.replace((row, col)).is_some() {
+                        return Err(TRMError::InvalidData(format!(
+                            "line {}: more than one '{}'",
+                            number, ch
+                        )));
+                    }
+                    if ch == 'S' {
+                        Cell::Start
+                    } else {
+                        Cell::Goal
+                    }
+                }
+                other => {
+                    return Err(TRMError::InvalidData(format!(
+                        "line {}: unexpected character {:?}",
+                        number, other
+                    )))
+                }
+            };
+            maze.grid[row][col] = cell;
+        }
+        // Pad short lines with open cells
+        for cell in &mut maze.grid[row][line.chars().count()..] {
+            *cell = Cell::Path;
+        }
+    }
+
+    let missing =
+        |what: &str| TRMError::InvalidData(format!("maze at line {} has no {}", first_line, what));
+    maze.start = start.ok_or_else(|| missing("start 'S'"))?;
+    maze.goal = goal.ok_or_else(|| missing("goal 'G'"))?;
+
+    if marked.iter().any(|&m| m > 0.0) {
+        let path = maze.decode_path(&marked).ok_or_else(|| {
+            TRMError::InvalidData(format!(
+                "maze at line {}: marked path does not connect start and goal",
+                first_line
+            ))
+        })?;
+        maze.solution = Some(path);
+    }
+
+    Ok(maze)
+}
+
+/// JSON form of a maze: grid rows plus the optional solution
+#[derive(Serialize, Deserialize)]
+pub(super) struct MazeRecord {
+    rows: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    solution: Option<Vec<(usize, usize)>>,
+}
+
+impl From<Maze> for MazeRecord {
+    fn from(maze: Maze) -> Self {
+        let rows = maze
+            .grid
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        Cell::Wall => '#',
+                        Cell::Path => ' ',
+                        Cell::Start => 'S',
+                        Cell::Goal => 'G',
+                    })
+                    .collect()
+            })
+            .collect();
+        Self {
+            rows,
+            solution: maze.solution,
+        }
+    }
+}
+
+impl TryFrom<MazeRecord> for Maze {
+    type Error = TRMError;
+
+    fn try_from(record: MazeRecord) -> Result<Self> {
+        if record.rows.iter().any(|row| row.contains(PATH_MARKER)) {
+            return Err(TRMError::InvalidData(
+                "JSON maze rows cannot mark the path; use \"solution\"".to_string(),
+            ));
+        }
+        let lines: Vec<(usize, &str)> = record
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| (index + 1, row.as_str()))
+            .collect();
+        let mut maze = parse_lines(&lines)?;
+
+        if let Some(solution) = record.solution {
+            if !maze.is_valid_path(&solution) {
+                return Err(TRMError::InvalidData(
+                    "solution is not a path from start to goal".to_string(),
+                ));
+            }
+            maze.solution = Some(solution);
+        }
+        Ok(maze)
+    }
+}
+
+/// Parse several ASCII mazes separated by blank lines
+///
+/// Lines starting with `;` are comments.
+pub fn parse_mazes(text: &str) -> Result<Vec<Maze>> {
+    let mut mazes = Vec::new();
+    let mut block: Vec<(usize, &str)> = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        if line.starts_with(';') {
+            continue;
+        }
+        if line.trim().is_empty() {
+            if !block.is_empty() {
+                mazes.push(parse_lines(&block)?);
+                block.clear();
+            }
+            continue;
+        }
+        block.push((index + 1, line));
+    }
+    if !block.is_empty() {
+        mazes.push(parse_lines(&block)?);
+    }
+    Ok(mazes)
+}
+
+/// Render several mazes as ASCII, separated by blank lines
+pub fn format_mazes(mazes: &[Maze]) -> String {
+    mazes
+        .iter()
+        .map(Maze::to_ascii)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Load mazes from a `.json` or ASCII maze file
+pub fn load_mazes<P: AsRef<Path>>(path: P) -> Result<Vec<Maze>> {
+    let text = fs::read_to_string(&path)?;
+    if is_json(path.as_ref()) {
+        Ok(serde_json::from_str(&text)?)
+    } else {
+        parse_mazes(&text)
+    }
+}
+
+/// Save mazes to a `.json` or ASCII maze file
+pub fn save_mazes<P: AsRef<Path>>(path: P, mazes: &[Maze]) -> Result<()> {
+    let text = if is_json(path.as_ref()) {
+        serde_json::to_string_pretty(mazes)?
+    } else {
+        format_mazes(mazes)
+    };
+    fs::write(path, text)?;
+    Ok(())
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORRIDOR: &str = "\
+#####
+#S··#
+###·#
+#G··#
+#####
+";
+
+    #[test]
+    fn test_ascii_round_trip() {
+        let mut maze = Maze::generate_random(11, 9).unwrap();
+        maze.solve();
+
+        let parsed = Maze::from_ascii(&maze.to_ascii()).unwrap();
+        assert_eq!(parsed.grid, maze.grid);
+        assert_eq!(parsed.start, maze.start);
+        assert_eq!(parsed.goal, maze.goal);
+        assert_eq!(parsed.solution, maze.solution);
+    }
+
+    #[test]
+    fn test_parse_hash_walls_and_markers() {
+        let maze = Maze::from_ascii(CORRIDOR).unwrap();
+        assert_eq!((maze.width, maze.height), (5, 5));
+        assert_eq!(maze.start, (1, 1));
+        assert_eq!(maze.goal, (3, 1));
+        assert_eq!(maze.solution.as_ref().unwrap().len(), 7);
+
+        // Without markers there is no solution, and short lines are padded
+        let maze = Maze::from_ascii("###\n#S\n#G#\n###").unwrap();
+        assert!(maze.solution.is_none());
+        assert_eq!(maze.grid[1][2], Cell::Path);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Maze::from_ascii("###\n#S#\n###").is_err()); // no goal
+        assert!(Maze::from_ascii("####\n#SS#\n#G #\n####").is_err());
+        assert!(Maze::from_ascii("###\n#Sx\n#G#").is_err());
+        assert!(Maze::from_ascii("SG").is_err()); // too small
+
+        // Markers that do not reach the goal
+        let err = Maze::from_ascii("#####\n#S· #\n### #\n#G  #\n#####").unwrap_err();
+        assert!(err.to_string().contains("does not connect"));
+    }
+
+    #[test]
+    fn test_json_round_trip_and_validation() {
+        let maze = Maze::from_ascii(CORRIDOR).unwrap();
+        let json = serde_json::to_string(&maze).unwrap();
+        assert!(json.contains("\"solution\""));
+
+        let parsed: Maze = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.grid, maze.grid);
+        assert_eq!(parsed.solution, maze.solution);
+
+        let bad = serde_json::json!({
+            "rows": ["#####", "#S  #", "### #", "#G  #", "#####"],
+            "solution": [[1, 1], [3, 1]],
+        });
+        assert!(serde_json::from_value::<Maze>(bad).is_err());
+    }
+
+    #[test]
+    fn test_multi_maze_files() {
+        let text = format!("; corridor\n{}\n; tiny\n###\n#S#\n#G#\n###\n", CORRIDOR);
+        let mazes = parse_mazes(&text).unwrap();
+        assert_eq!(mazes.len(), 2);
+        assert_eq!(mazes[1].goal, (2, 1));
+
+        let dir = std::env::temp_dir();
+        for name in ["train-trm-mazes.txt", "train-trm-mazes.json"] {
+            let path = dir.join(name);
+            save_mazes(&path, &mazes).unwrap();
+            let loaded = load_mazes(&path).unwrap();
+            assert_eq!(loaded.len(), 2);
+            assert_eq!(loaded[0].grid, mazes[0].grid);
+            assert_eq!(loaded[0].solution, mazes[0].solution);
+            fs::remove_file(path).unwrap();
+        }
+
+        // Errors report the line within the file
+        let err = parse_mazes(&format!("{}\n###\n#S#\n###\n", CORRIDOR)).unwrap_err();
+        assert!(err.to_string().contains("line 7"), "{}", err);
+    }
+}
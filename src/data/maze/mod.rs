@@ -0,0 +1,965 @@
+//! Maze generation and solving task
+
+pub mod encoding;
+pub mod generate;
+pub mod io;
+pub mod render;
+pub mod solve;
+pub mod stats;
+
+use super::problem::check_len;
+use super::{Task, TrainingExample, Validation};
+use crate::utils::{Result, TRMError};
+use ndarray::Array2;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+pub use encoding::MazeEncoding;
+pub use generate::{MazeAlgorithm, MazeGenerator, Placement};
+pub use io::{format_mazes, load_mazes, parse_mazes, save_mazes};
+pub use render::{maze_montage, RenderOptions};
+pub use solve::{PathCheck, PathError, Solver};
+pub use stats::{bucket_of, quantile_edges, MazeFilter, MazeMetric, MazeStats};
+
+/// Minimum model output for a cell to count as part of the predicted path
+pub const PATH_THRESHOLD: f32 = 0.5;
+
+/// Generated mazes tried per requested maze before filtered generation gives up
+pub const MAX_ATTEMPTS_PER_MAZE: usize = 200;
+
+/// Direction in the maze
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Cell type in the maze
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cell {
+    Wall,
+    Path,
+    Start,
+    Goal,
+}
+
+impl Cell {
+    /// Convert cell to f32 for neural network
+    pub fn to_f32(self) -> f32 {
+        match self {
+            Cell::Wall => 0.0,
+            Cell::Path => 0.25,
+            Cell::Start => 0.5,
+            Cell::Goal => 1.0,
+        }
+    }
+
+    /// Convert from f32 (for decoding)
+    pub fn from_f32(value: f32) -> Self {
+        if value < 0.125 {
+            Cell::Wall
+        } else if value < 0.375 {
+            Cell::Path
+        } else if value < 0.75 {
+            Cell::Start
+        } else {
+            Cell::Goal
+        }
+    }
+}
+
+/// A maze instance
+///
+/// Serializes as grid rows plus the optional solution (see [`io`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "io::MazeRecord", try_from = "io::MazeRecord")]
+pub struct Maze {
+    /// Maze grid
+    pub grid: Vec<Vec<Cell>>,
+    /// Width of maze
+    pub width: usize,
+    /// Height of maze
+    pub height: usize,
+    /// Start position (row, col)
+    pub start: (usize, usize),
+    /// Goal position (row, col)
+    pub goal: (usize, usize),
+    /// Solution path (if computed)
+    pub solution: Option<Vec<(usize, usize)>>,
+}
+
+impl Maze {
+    /// Create a new maze filled with walls
+    ///
+    /// Both dimensions must be at least 3 to leave room for the border.
+    pub fn new(width: usize, height: usize) -> Result<Self> {
+        if width < 3 || height < 3 {
+            return Err(TRMError::InvalidData(format!(
+                "maze must be at least 3x3, got {}x{}",
+                width, height
+            )));
+        }
+
+        let grid = vec![vec![Cell::Wall; width]; height];
+        Ok(Self {
+            grid,
+            width,
+            height,
+            start: (1, 1),
+            goal: (height - 2, width - 2),
+            solution: None,
+        })
+    }
+
+    /// Generate a random maze using depth-first backtracking
+    pub fn generate_random(width: usize, height: usize) -> Result<Self> {
+        Self::generate_with_rng(width, height, &mut rand::thread_rng())
+    }
+
+    /// Generate a random maze using depth-first backtracking with the given RNG
+    ///
+    /// See [`MazeGenerator`] for other algorithms, braiding and placement.
+    pub fn generate_with_rng(width: usize, height: usize, rng: &mut impl Rng) -> Result<Self> {
+        MazeGenerator::default().generate(width, height, rng)
+    }
+
+    /// Solve the maze using BFS
+    pub fn solve(&mut self) -> bool {
+        let mut queue = VecDeque::new();
+        let mut visited = vec![vec![false; self.width]; self.height];
+        let mut parent = vec![vec![None; self.width]; self.height];
+
+        queue.push_back(self.start);
+        visited[self.start.0][self.start.1] = true;
+
+        while let Some((row, col)) = queue.pop_front() {
+            if (row, col) == self.goal {
+                // Reconstruct path
+                let mut path = Vec::new();
+                let mut current = Some(self.goal);
+
+                while let Some(pos) = current {
+                    path.push(pos);
+                    current = parent[pos.0][pos.1];
+                }
+
+                path.reverse();
+                self.solution = Some(path);
+                return true;
+            }
+
+            for dir in &[
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+            ] {
+                let (new_row, new_col) = match dir {
+                    Direction::Up if row > 0 => (row - 1, col),
+                    Direction::Down if row + 1 < self.height => (row + 1, col),
+                    Direction::Left if col > 0 => (row, col - 1),
+                    Direction::Right if col + 1 < self.width => (row, col + 1),
+                    _ => continue,
+                };
+
+                if !visited[new_row][new_col] && self.grid[new_row][new_col] != Cell::Wall {
+                    visited[new_row][new_col] = true;
+                    parent[new_row][new_col] = Some((row, col));
+                    queue.push_back((new_row, new_col));
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Convert maze to flat array for neural network
+    pub fn to_array(&self) -> Vec<f32> {
+        self.grid
+            .iter()
+            .flat_map(|row| row.iter().map(|&cell| cell.to_f32()))
+            .collect()
+    }
+
+    /// Mask with 1.0 for every cell on the solution path and 0.0 elsewhere
+    pub fn solution_mask(&self) -> Option<Vec<f32>> {
+        let solution = self.solution.as_ref()?;
+        let mut mask = vec![0.0; self.width * self.height];
+        for &(row, col) in solution {
+            mask[row * self.width + col] = 1.0;
+        }
+        Some(mask)
+    }
+
+    /// Encode the maze and its solution as a training example
+    ///
+    /// The input is the flattened grid (`to_array`) and the target is the
+    /// on-path mask (`solution_mask`). Returns `None` if the maze is unsolved.
+    pub fn to_training_example(&self) -> Option<TrainingExample> {
+        self.to_training_example_with(MazeEncoding::Scalar)
+    }
+
+    /// Encode the maze with the given cell encoding and its solution mask
+    pub fn to_training_example_with(&self, encoding: MazeEncoding) -> Option<TrainingExample> {
+        let cells = self.width * self.height;
+        let input =
+            Array2::from_shape_vec((1, cells * encoding.channels()), encoding.encode(self)).ok()?;
+        let target = Array2::from_shape_vec((1, cells), self.solution_mask()?).ok()?;
+        Some(TrainingExample::new(input, target))
+    }
+
+    /// Decode a model output (per-cell on-path scores) into a path
+    ///
+    /// Cells scoring at least `PATH_THRESHOLD` are treated as predicted path
+    /// cells; the shortest route from start to goal through those cells is
+    /// returned, or `None` if the prediction does not connect them.
+    pub fn decode_path(&self, output: &[f32]) -> Option<Vec<(usize, usize)>> {
+        if output.len() != self.width * self.height {
+            return None;
+        }
+
+        let predicted = |(row, col): (usize, usize)| {
+            (row, col) == self.start
+                || (row, col) == self.goal
+                || output[row * self.width + col] >= PATH_THRESHOLD
+        };
+
+        let mut queue = VecDeque::new();
+        let mut parent = vec![vec![None; self.width]; self.height];
+        let mut visited = vec![vec![false; self.width]; self.height];
+
+        queue.push_back(self.start);
+        visited[self.start.0][self.start.1] = true;
+
+        while let Some(pos) = queue.pop_front() {
+            if pos == self.goal {
+                let mut path = vec![pos];
+                let mut current = parent[pos.0][pos.1];
+                while let Some(prev) = current {
+                    path.push(prev);
+                    current = parent[prev.0][prev.1];
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for next in self.open_neighbors(pos) {
+                if !visited[next.0][next.1] && predicted(next) {
+                    visited[next.0][next.1] = true;
+                    parent[next.0][next.1] = Some(pos);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Check that a path runs from start to goal through adjacent open cells
+    ///
+    /// See [`Maze::check_path`] for the reason a path fails and whether it
+    /// is optimal.
+    pub fn is_valid_path(&self, path: &[(usize, usize)]) -> bool {
+        self.check_path(path).is_ok()
+    }
+
+    /// Neighbouring cells that are not walls
+    fn open_neighbors(&self, (row, col): (usize, usize)) -> Vec<(usize, usize)> {
+        let mut neighbors = Vec::with_capacity(4);
+        if row > 0 {
+            neighbors.push((row - 1, col));
+        }
+        if row + 1 < self.height {
+            neighbors.push((row + 1, col));
+        }
+        if col > 0 {
+            neighbors.push((row, col - 1));
+        }
+        if col + 1 < self.width {
+            neighbors.push((row, col + 1));
+        }
+        neighbors.retain(|&(r, c)| self.grid[r][c] != Cell::Wall);
+        neighbors
+    }
+
+    /// Convert solution path to direction array
+    pub fn solution_to_directions(&self) -> Option<Vec<Direction>> {
+        let solution = self.solution.as_ref()?;
+        let mut directions = Vec::new();
+
+        for i in 0..solution.len() - 1 {
+            let (r1, c1) = solution[i];
+            let (r2, c2) = solution[i + 1];
+
+            let dir = if r2 > r1 {
+                Direction::Down
+            } else if r2 < r1 {
+                Direction::Up
+            } else if c2 > c1 {
+                Direction::Right
+            } else {
+                Direction::Left
+            };
+
+            directions.push(dir);
+        }
+
+        Some(directions)
+    }
+
+    /// Convert to SVG string
+    pub fn to_svg(&self, cell_size: usize) -> String {
+        let width_px = self.width * cell_size;
+        let height_px = self.height * cell_size;
+
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+            width_px, height_px, width_px, height_px
+        );
+
+        svg.push_str("\n  <!-- Maze grid -->");
+
+        // Draw cells
+        for (row_idx, row) in self.grid.iter().enumerate() {
+            for (col_idx, &cell) in row.iter().enumerate() {
+                let x = col_idx * cell_size;
+                let y = row_idx * cell_size;
+
+                let (fill, stroke) = match cell {
+                    Cell::Wall => ("#2c3e50", "#34495e"),
+                    Cell::Path => ("#ecf0f1", "#bdc3c7"),
+                    Cell::Start => ("#2ecc71", "#27ae60"),
+                    Cell::Goal => ("#e74c3c", "#c0392b"),
+                };
+
+                svg.push_str(&format!(
+                    "\n  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\"/>",
+                    x, y, cell_size, cell_size, fill, stroke
+                ));
+            }
+        }
+
+        // Draw solution path if available
+        if let Some(path) = &self.solution {
+            svg.push_str("\n  <!-- Solution path -->");
+            if path.len() > 1 {
+                svg.push_str("\n  <polyline points=\"");
+
+                for &(row, col) in path {
+                    let x = col * cell_size + cell_size / 2;
+                    let y = row * cell_size + cell_size / 2;
+                    svg.push_str(&format!("{},{} ", x, y));
+                }
+
+                svg.push_str("\" fill=\"none\" stroke=\"#3498db\" stroke-width=\"3\" stroke-linecap=\"round\" stroke-linejoin=\"round\" opacity=\"0.8\"/>");
+            }
+        }
+
+        svg.push_str("\n</svg>");
+        svg
+    }
+
+    /// Convert to SVG with a predicted path drawn over the solution
+    ///
+    /// Each predicted cell gets an orange square in its middle quarter, so
+    /// cells the prediction misses or adds stand out against the solution.
+    pub fn to_svg_with_prediction(&self, cell_size: usize, predicted: &[(usize, usize)]) -> String {
+        let mut svg = self.to_svg(cell_size);
+        svg.truncate(svg.len() - "\n</svg>".len());
+
+        svg.push_str("\n  <!-- Predicted path -->");
+        let inset = cell_size * 3 / 8;
+        let side = cell_size - 2 * inset;
+        for &(row, col) in predicted {
+            svg.push_str(&format!(
+                "\n  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#e67e22\" opacity=\"0.9\"/>",
+                col * cell_size + inset,
+                row * cell_size + inset,
+                side,
+                side
+            ));
+        }
+
+        svg.push_str("\n</svg>");
+        svg
+    }
+
+    /// Print maze as ASCII art
+    pub fn print(&self) {
+        print!("{}", self.to_ascii());
+    }
+}
+
+/// Maze solving task
+///
+/// Each example's input is the maze grid in the task's [`MazeEncoding`]
+/// (scalar by default) and its target is the mask of cells on the BFS
+/// solution path.
+pub struct MazeTask {
+    mazes: Vec<Maze>,
+    examples: Vec<TrainingExample>,
+    width: usize,
+    height: usize,
+    encoding: MazeEncoding,
+}
+
+impl MazeTask {
+    /// Create a new maze task
+    pub fn new(num_mazes: usize, width: usize, height: usize) -> Result<Self> {
+        Self::with_generator(num_mazes, width, height, &MazeGenerator::default())
+    }
+
+    /// Create a maze task from the given generator
+    pub fn with_generator(
+        num_mazes: usize,
+        width: usize,
+        height: usize,
+        generator: &MazeGenerator,
+    ) -> Result<Self> {
+        Self::with_filters(num_mazes, width, height, generator, &[])
+    }
+
+    /// Create a maze task from mazes that pass every filter
+    ///
+    /// Fails if too few generated mazes pass after
+    /// [`MAX_ATTEMPTS_PER_MAZE`] tries per requested maze.
+    pub fn with_filters(
+        num_mazes: usize,
+        width: usize,
+        height: usize,
+        generator: &MazeGenerator,
+        filters: &[MazeFilter],
+    ) -> Result<Self> {
+        Self::with_rng(
+            num_mazes,
+            width,
+            height,
+            generator,
+            filters,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Create a maze task from filtered mazes drawn from `rng`
+    pub fn with_rng<R: Rng>(
+        num_mazes: usize,
+        width: usize,
+        height: usize,
+        generator: &MazeGenerator,
+        filters: &[MazeFilter],
+        rng: &mut R,
+    ) -> Result<Self> {
+        let mut mazes = Vec::with_capacity(num_mazes);
+        for _ in 0..num_mazes.saturating_mul(MAX_ATTEMPTS_PER_MAZE) {
+            if mazes.len() == num_mazes {
+                break;
+            }
+            let Some((maze, stats)) = generate_solved(width, height, generator, rng)? else {
+                continue;
+            };
+            if filters.iter().all(|filter| filter.matches(&stats)) {
+                mazes.push(maze);
+            }
+        }
+        if mazes.len() < num_mazes {
+            return Err(TRMError::InvalidData(format!(
+                "only {} of {} generated {}x{} mazes passed the filters",
+                mazes.len(),
+                num_mazes,
+                width,
+                height
+            )));
+        }
+        Self::from_mazes_sized(mazes, width, height)
+    }
+
+    /// Create a maze task with `per_bucket` mazes in each difficulty bucket
+    ///
+    /// Buckets split `metric` at `edges` as in [`bucket_of`]; mazes are
+    /// drawn from `rng` and ordered bucket by bucket, easiest first.
+    pub fn balanced(
+        per_bucket: usize,
+        width: usize,
+        height: usize,
+        generator: &MazeGenerator,
+        metric: MazeMetric,
+        edges: &[f32],
+        rng: &mut impl Rng,
+    ) -> Result<Self> {
+        let mut buckets: Vec<Vec<Maze>> = vec![Vec::with_capacity(per_bucket); edges.len() + 1];
+        let attempts = (per_bucket * buckets.len()).saturating_mul(MAX_ATTEMPTS_PER_MAZE);
+        for _ in 0..attempts {
+            if buckets.iter().all(|bucket| bucket.len() == per_bucket) {
+                break;
+            }
+            let Some((maze, stats)) = generate_solved(width, height, generator, rng)? else {
+                continue;
+            };
+            let bucket = &mut buckets[bucket_of(metric.value(&stats), edges)];
+            if bucket.len() < per_bucket {
+                bucket.push(maze);
+            }
+        }
+        if let Some(short) = buckets.iter().position(|bucket| bucket.len() < per_bucket) {
+            return Err(TRMError::InvalidData(format!(
+                "found only {} of {} {}x{} mazes for {} bucket {}",
+                buckets[short].len(),
+                per_bucket,
+                width,
+                height,
+                metric,
+                short
+            )));
+        }
+        Self::from_mazes_sized(buckets.concat(), width, height)
+    }
+
+    /// Create a maze task from existing mazes, e.g. loaded from a maze file
+    ///
+    /// All mazes must have the same size; unsolved mazes are solved, and
+    /// unsolvable ones or stored solutions that are not a shortest path are
+    /// rejected.
+    pub fn from_mazes(mazes: Vec<Maze>) -> Result<Self> {
+        let (width, height) = mazes
+            .first()
+            .map(|maze| (maze.width, maze.height))
+            .ok_or_else(|| TRMError::InvalidData("no mazes given".to_string()))?;
+        Self::from_mazes_sized(mazes, width, height)
+    }
+
+    fn from_mazes_sized(mazes: Vec<Maze>, width: usize, height: usize) -> Result<Self> {
+        let mut solved = Vec::with_capacity(mazes.len());
+        let mut examples = Vec::with_capacity(mazes.len());
+        for (index, mut maze) in mazes.into_iter().enumerate() {
+            if (maze.width, maze.height) != (width, height) {
+                return Err(TRMError::InvalidData(format!(
+                    "maze {} is {}x{}, expected {}x{}",
+                    index, maze.width, maze.height, width, height
+                )));
+            }
+            if maze.solution.is_none() && !maze.solve() {
+                return Err(TRMError::InvalidData(format!(
+                    "maze {} has no solution",
+                    index
+                )));
+            }
+            let solution = maze.solution.as_deref().expect("maze is solved");
+            if !maze.is_shortest_path_set(solution) {
+                return Err(TRMError::InvalidData(format!(
+                    "maze {} stores a {}-move solution, but a shortest path takes {} moves",
+                    index,
+                    solution.len().saturating_sub(1),
+                    maze.shortest_path_length().unwrap_or(0)
+                )));
+            }
+            examples.push(maze.to_training_example().expect("maze is solved"));
+            solved.push(maze);
+        }
+
+        Ok(Self {
+            mazes: solved,
+            examples,
+            width,
+            height,
+            encoding: MazeEncoding::Scalar,
+        })
+    }
+
+    /// Get all mazes
+    pub fn mazes(&self) -> &[Maze] {
+        &self.mazes
+    }
+
+    /// Get a specific maze
+    pub fn get(&self, index: usize) -> Option<&Maze> {
+        self.mazes.get(index)
+    }
+
+    /// Get all training examples
+    pub fn examples(&self) -> &[TrainingExample] {
+        &self.examples
+    }
+
+    /// Re-encode every example's input with the given cell encoding
+    pub fn with_encoding(mut self, encoding: MazeEncoding) -> Self {
+        self.examples = self
+            .mazes
+            .iter()
+            .map(|maze| {
+                maze.to_training_example_with(encoding)
+                    .expect("task mazes are solved")
+            })
+            .collect();
+        self.encoding = encoding;
+        self
+    }
+
+    /// Cell encoding of the example inputs
+    pub fn encoding(&self) -> MazeEncoding {
+        self.encoding
+    }
+
+    /// Difficulty statistics of every maze, in example order
+    pub fn stats(&self) -> Vec<MazeStats> {
+        self.mazes
+            .iter()
+            .map(|maze| maze.stats().expect("task mazes are solved"))
+            .collect()
+    }
+
+    /// Example indices grouped into buckets of `metric` split at `edges`
+    ///
+    /// Returns `edges.len() + 1` buckets; pass the groups to
+    /// [`BatchValidation::select`](super::BatchValidation::select) to break
+    /// accuracy down by difficulty.
+    pub fn buckets(&self, metric: MazeMetric, edges: &[f32]) -> Vec<Vec<usize>> {
+        let mut buckets = vec![Vec::new(); edges.len() + 1];
+        for (index, stats) in self.stats().iter().enumerate() {
+            buckets[bucket_of(metric.value(stats), edges)].push(index);
+        }
+        buckets
+    }
+}
+
+/// Generate and solve one maze, or `None` if it has no solution
+fn generate_solved(
+    width: usize,
+    height: usize,
+    generator: &MazeGenerator,
+    rng: &mut impl Rng,
+) -> Result<Option<(Maze, MazeStats)>> {
+    let mut maze = generator.generate(width, height, rng)?;
+    if !maze.solve() {
+        return Ok(None);
+    }
+    Ok(maze.stats().map(|stats| (maze, stats)))
+}
+
+impl Task for MazeTask {
+    fn examples(&self) -> &[TrainingExample] {
+        &self.examples
+    }
+
+    /// Correct if the predicted cells are exactly one shortest path from
+    /// start to goal, with no extra cells; the score is the fraction of cells
+    /// classified like the target mask
+    fn validate_example(&self, index: usize, output: &Array2<f32>) -> Validation {
+        if let Some(invalid) = check_len(output, self.output_dim()) {
+            return invalid;
+        }
+        let maze = &self.mazes[index];
+        let values: Vec<f32> = output.iter().cloned().collect();
+        let cells: Vec<(usize, usize)> = values
+            .iter()
+            .enumerate()
+            .filter(|(_, &v)| v >= PATH_THRESHOLD)
+            .map(|(cell, _)| (cell / self.width, cell % self.width))
+            .collect();
+        let shortest = maze.solution.as_ref().map_or(0, Vec::len);
+        let reason = if maze.is_shortest_path_set(&cells) {
+            None
+        } else if cells.len() != shortest {
+            Some(format!(
+                "{} cells marked, a shortest path has {}",
+                cells.len(),
+                shortest
+            ))
+        } else {
+            Some("marked cells are not a shortest path".to_string())
+        };
+
+        let target = &self.examples[index].target;
+        let agree = values
+            .iter()
+            .zip(target.iter())
+            .filter(|(&p, &t)| (p >= PATH_THRESHOLD) == (t >= PATH_THRESHOLD))
+            .count();
+        Validation::check(agree as f32 / values.len() as f32, reason)
+    }
+
+    fn input_dim(&self) -> usize {
+        self.width * self.height * self.encoding.channels()
+    }
+
+    fn output_dim(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// Moves on the shortest solution
+    fn difficulty(&self, index: usize) -> Option<usize> {
+        self.mazes[index]
+            .solution
+            .as_ref()
+            .map(|path| path.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_maze_creation() {
+        let maze = Maze::new(5, 5).unwrap();
+        assert_eq!(maze.width, 5);
+        assert_eq!(maze.height, 5);
+        assert_eq!(maze.grid.len(), 5);
+        assert_eq!(maze.grid[0].len(), 5);
+
+        assert!(Maze::new(2, 5).is_err());
+        assert!(Maze::new(5, 0).is_err());
+    }
+
+    #[test]
+    fn test_maze_generation() {
+        let maze = Maze::generate_random(11, 11).unwrap();
+        assert_eq!(maze.grid[maze.start.0][maze.start.1], Cell::Start);
+        assert_eq!(maze.grid[maze.goal.0][maze.goal.1], Cell::Goal);
+
+        // Check that there are some paths
+        let path_count = maze
+            .grid
+            .iter()
+            .flat_map(|row| row.iter())
+            .filter(|&&cell| cell == Cell::Path)
+            .count();
+        assert!(path_count > 0);
+    }
+
+    #[test]
+    fn test_maze_solving() {
+        let mut maze = Maze::generate_random(11, 11).unwrap();
+        assert!(maze.solve());
+        assert!(maze.solution.is_some());
+
+        let solution = maze.solution.unwrap();
+        assert_eq!(solution.first(), Some(&maze.start));
+        assert_eq!(solution.last(), Some(&maze.goal));
+    }
+
+    #[test]
+    fn test_maze_to_array() {
+        let maze = Maze::new(3, 3).unwrap();
+        let array = maze.to_array();
+        assert_eq!(array.len(), 9);
+    }
+
+    #[test]
+    fn test_cell_conversion() {
+        assert_eq!(Cell::from_f32(Cell::Wall.to_f32()), Cell::Wall);
+        assert_eq!(Cell::from_f32(Cell::Path.to_f32()), Cell::Path);
+        assert_eq!(Cell::from_f32(Cell::Start.to_f32()), Cell::Start);
+        assert_eq!(Cell::from_f32(Cell::Goal.to_f32()), Cell::Goal);
+    }
+
+    #[test]
+    fn test_maze_task_from_mazes() {
+        let mazes =
+            parse_mazes("#####\n#S  #\n### #\n#G  #\n#####\n\n#####\n#S#G#\n#####\n#####\n#####")
+                .unwrap();
+        // The second maze is the same size but has no route
+        assert!(MazeTask::from_mazes(mazes.clone()).is_err());
+
+        let task = MazeTask::from_mazes(mazes[..1].to_vec()).unwrap();
+        assert_eq!(task.examples().len(), 1);
+        assert_eq!(task.input_dim(), 25);
+        // S -> right two, down two, left two
+        assert_eq!(task.difficulty(0), Some(6));
+        assert!(MazeTask::from_mazes(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_maze_task_rejects_detour_solutions() {
+        // The marked route takes six moves around the room; straight down takes two
+        let detour = Maze::from_ascii("#####\n#S··#\n#  ·#\n#G··#\n#####").unwrap();
+        assert_eq!(detour.solution.as_ref().map(Vec::len), Some(7));
+        let err = MazeTask::from_mazes(vec![detour.clone()]).err().unwrap();
+        assert!(err.to_string().contains("6-move"));
+
+        // Without the stored detour the maze is solved afresh
+        let mut unsolved = detour;
+        unsolved.solution = None;
+        let task = MazeTask::from_mazes(vec![unsolved]).unwrap();
+        assert_eq!(task.difficulty(0), Some(2));
+        let target = task.examples()[0].target.clone();
+        assert!(task.validate_example(0, &target).correct);
+    }
+
+    #[test]
+    fn test_maze_task_encoding() {
+        let task = MazeTask::new(2, 7, 7)
+            .unwrap()
+            .with_encoding(MazeEncoding::OneHot);
+        assert_eq!(task.encoding(), MazeEncoding::OneHot);
+        assert_eq!(task.input_dim(), 7 * 7 * 4);
+        assert_eq!(task.output_dim(), 49);
+        assert_eq!(task.examples()[0].input.ncols(), 196);
+
+        // Validation still works on the solution mask
+        let example = &task.examples()[1];
+        assert!(task.validate_example(1, &example.target).correct);
+    }
+
+    #[test]
+    fn test_maze_task_creation() {
+        let task = MazeTask::new(5, 11, 11).unwrap();
+        assert_eq!(task.mazes().len(), 5);
+        assert_eq!(task.width, 11);
+        assert_eq!(task.height, 11);
+    }
+
+    #[test]
+    fn test_maze_training_example() {
+        let mut maze = Maze::generate_random(7, 7).unwrap();
+        assert!(maze.to_training_example().is_none());
+        maze.solve();
+
+        let example = maze.to_training_example().unwrap();
+        assert_eq!(example.input.shape(), &[1, 49]);
+        assert_eq!(example.target.shape(), &[1, 49]);
+
+        let on_path = example.target.iter().filter(|&&v| v == 1.0).count();
+        assert_eq!(on_path, maze.solution.as_ref().unwrap().len());
+    }
+
+    #[test]
+    fn test_decode_path_round_trip() {
+        let mut maze = Maze::generate_random(9, 9).unwrap();
+        maze.solve();
+        let mask = maze.solution_mask().unwrap();
+
+        let path = maze.decode_path(&mask).unwrap();
+        assert_eq!(Some(&path), maze.solution.as_ref());
+        assert!(maze.is_valid_path(&path));
+
+        // An all-zero prediction does not connect start and goal
+        assert!(maze.decode_path(&vec![0.0; 81]).is_none());
+    }
+
+    #[test]
+    fn test_is_valid_path() {
+        let mut maze = Maze::generate_random(7, 7).unwrap();
+        maze.solve();
+        let solution = maze.solution.clone().unwrap();
+        assert!(maze.is_valid_path(&solution));
+
+        // Skipping a cell breaks contiguity
+        let mut gapped = solution.clone();
+        gapped.remove(1);
+        assert!(!maze.is_valid_path(&gapped));
+
+        // Must end at the goal
+        assert!(!maze.is_valid_path(&solution[..solution.len() - 1]));
+
+        // Must never pass through a wall
+        assert!(!maze.is_valid_path(&[(0, 0)]));
+    }
+
+    #[test]
+    fn test_maze_task_problem() {
+        let task = MazeTask::new(3, 7, 7).unwrap();
+        assert_eq!(task.examples().len(), 3);
+        assert_eq!(task.input_dim(), 49);
+        assert_eq!(task.output_dim(), 49);
+
+        for (index, example) in task.examples().iter().enumerate() {
+            assert!(task.validate_example(index, &example.target).correct);
+            let empty = task.validate_example(index, &Array2::zeros((1, 49)));
+            assert!(!empty.correct);
+            assert!(empty.score > 0.0 && empty.score < 1.0);
+        }
+    }
+
+    #[test]
+    fn test_extra_cells_are_incorrect() {
+        let mut rng = StdRng::seed_from_u64(43);
+        let task = MazeTask::with_rng(2, 9, 9, &MazeGenerator::default(), &[], &mut rng).unwrap();
+        for (index, example) in task.examples().iter().enumerate() {
+            let all_ones = task.validate_example(index, &Array2::ones((1, 81)));
+            assert!(!all_ones.correct);
+            assert!(all_ones.score < 1.0);
+
+            // The true path plus one open cell off the path
+            let maze = &task.mazes()[index];
+            let off_path = (0..81)
+                .find(|&cell| {
+                    maze.grid[cell / 9][cell % 9] == Cell::Path && example.target[[0, cell]] == 0.0
+                })
+                .unwrap();
+            let mut noisy = example.target.clone();
+            noisy[[0, off_path]] = 1.0;
+            let noisy = task.validate_example(index, &noisy);
+            assert!(!noisy.correct);
+            assert!(noisy.reason.unwrap().contains("cells marked"));
+        }
+    }
+
+    #[test]
+    fn test_filtered_and_balanced_tasks() {
+        let generator = MazeGenerator::default();
+        let filter: MazeFilter = "solution_length=12..".parse().unwrap();
+        let task = MazeTask::with_filters(4, 9, 9, &generator, &[filter]).unwrap();
+        assert_eq!(task.num_instances(), 4);
+        assert!(task.stats().iter().all(|s| s.solution_length >= 12));
+
+        let impossible: MazeFilter = "solution_length=..1".parse().unwrap();
+        assert!(MazeTask::with_filters(1, 7, 7, &generator, &[impossible]).is_err());
+
+        let edges = [3.0];
+        let balanced = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            MazeTask::balanced(3, 9, 9, &generator, MazeMetric::DeadEnds, &edges, &mut rng).unwrap()
+        };
+        let task = balanced(48);
+        // The same seed draws the same mazes
+        let grids = |task: &MazeTask| {
+            task.mazes()
+                .iter()
+                .map(|m| m.grid.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(grids(&balanced(48)), grids(&task));
+        let buckets = task.buckets(MazeMetric::DeadEnds, &edges);
+        assert_eq!(buckets, vec![vec![0, 1, 2], vec![3, 4, 5]]);
+
+        let outputs = Array2::from_shape_fn((6, 81), |(row, col)| {
+            if row < 3 {
+                task.examples()[row].target[[0, col]]
+            } else {
+                0.0
+            }
+        });
+        let batch = task.validate_batch(0, &outputs);
+        assert_eq!(batch.select(&buckets[0]).accuracy(), 1.0);
+        assert_eq!(batch.select(&buckets[1]).accuracy(), 0.0);
+    }
+
+    #[test]
+    fn test_svg_generation() {
+        let mut maze = Maze::generate_random(7, 7).unwrap();
+        maze.solve();
+        let svg = maze.to_svg(20);
+
+        assert!(svg.contains("<svg"));
+        assert!(svg.contains("</svg>"));
+        assert!(svg.contains("rect"));
+    }
+
+    #[test]
+    fn test_svg_with_prediction() {
+        let mut maze = Maze::generate_random(7, 7).unwrap();
+        maze.solve();
+        let predicted = maze.solution.clone().unwrap();
+        let svg = maze.to_svg_with_prediction(16, &predicted);
+
+        assert!(svg.starts_with(&maze.to_svg(16)[..100]));
+        assert!(svg.ends_with("\n</svg>"));
+        assert_eq!(svg.matches("#e67e22").count(), predicted.len());
+        assert!(svg.contains("width=\"4\" height=\"4\""));
+    }
+}
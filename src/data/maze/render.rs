@@ -0,0 +1,175 @@
+//! Raster rendering of mazes, paths and model confidence
+//!
+//! Uses the same palette as [`Maze::to_svg`]. Output is an [`Image`], which
+//! can be saved as PNG or PPM.
+
+use super::solve::Pos;
+use super::{Cell, Maze};
+use crate::utils::image::{heat_color, Image, Rgb};
+
+const WALL: Rgb = [0x2c, 0x3e, 0x50];
+const OPEN: Rgb = [0xec, 0xf0, 0xf1];
+const START: Rgb = [0x2e, 0xcc, 0x71];
+const GOAL: Rgb = [0xe7, 0x4c, 0x3c];
+const SOLUTION: Rgb = [0x34, 0x98, 0xdb];
+const PREDICTED: Rgb = [0xe6, 0x7e, 0x22];
+
+/// Opacity of the confidence heatmap over open cells
+const HEATMAP_ALPHA: f32 = 0.6;
+
+/// What to draw on top of the maze
+#[derive(Debug, Clone, Copy)]
+pub struct RenderOptions<'a> {
+    /// Side of one cell in pixels
+    pub cell_size: usize,
+    /// Draw the maze's stored solution as a blue square in each cell
+    pub solution: bool,
+    /// A predicted path, drawn as a smaller orange square in each cell
+    pub predicted: Option<&'a [Pos]>,
+    /// Per-cell confidence in `[0, 1]`, row by row, blended over open cells
+    pub heatmap: Option<&'a [f32]>,
+}
+
+impl Default for RenderOptions<'_> {
+    fn default() -> Self {
+        Self {
+            cell_size: 8,
+            solution: true,
+            predicted: None,
+            heatmap: None,
+        }
+    }
+}
+
+impl Maze {
+    /// Render the maze as a raster image
+    ///
+    /// The true path fills the middle half of each of its cells and a
+    /// predicted path the middle quarter, so agreement and misses are both
+    /// visible when the two are drawn together.
+    pub fn to_image(&self, options: &RenderOptions) -> Image {
+        let size = options.cell_size.max(1);
+        let mut image = Image::new(self.width * size, self.height * size, WALL);
+
+        for (row, cells) in self.grid.iter().enumerate() {
+            for (col, &cell) in cells.iter().enumerate() {
+                let color = match cell {
+                    Cell::Wall => continue,
+                    Cell::Path => OPEN,
+                    Cell::Start => START,
+                    Cell::Goal => GOAL,
+                };
+                image.fill_rect(col * size, row * size, size, size, color);
+                if let (Cell::Path, Some(heat)) = (cell, options.heatmap) {
+                    if let Some(&value) = heat.get(row * self.width + col) {
+                        image.blend_rect(
+                            col * size,
+                            row * size,
+                            size,
+                            size,
+                            heat_color(value),
+                            HEATMAP_ALPHA,
+                        );
+                    }
+                }
+            }
+        }
+
+        if options.solution {
+            if let Some(path) = &self.solution {
+                mark_cells(&mut image, path, size, size / 4, SOLUTION);
+            }
+        }
+        if let Some(path) = options.predicted {
+            mark_cells(&mut image, path, size, size * 3 / 8, PREDICTED);
+        }
+        image
+    }
+}
+
+/// Fill each path cell, inset by `margin` pixels on every side
+fn mark_cells(image: &mut Image, path: &[Pos], size: usize, margin: usize, color: Rgb) {
+    let inner = size.saturating_sub(2 * margin).max(1);
+    for &(row, col) in path {
+        image.fill_rect(
+            col * size + margin,
+            row * size + margin,
+            inner,
+            inner,
+            color,
+        );
+    }
+}
+
+/// Render mazes side by side in a grid of `columns`
+pub fn maze_montage(mazes: &[Maze], columns: usize, options: &RenderOptions) -> Image {
+    let images: Vec<Image> = mazes.iter().map(|maze| maze.to_image(options)).collect();
+    Image::montage(&images, columns, options.cell_size.max(1), [255, 255, 255])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corridor() -> Maze {
+        let mut maze = Maze::from_ascii("#####\n#S G#\n#####").unwrap();
+        maze.solve();
+        maze
+    }
+
+    #[test]
+    fn test_cells_and_paths() {
+        let maze = corridor();
+        let image = maze.to_image(&RenderOptions {
+            cell_size: 8,
+            ..Default::default()
+        });
+        assert_eq!((image.width(), image.height()), (40, 24));
+        assert_eq!(image.get(0, 0), Some(WALL));
+        assert_eq!(image.get(8, 8), Some(START));
+        // Middle cell: open at the edge, solution in the centre
+        assert_eq!(image.get(16, 8), Some(OPEN));
+        assert_eq!(image.get(20, 12), Some(SOLUTION));
+
+        let predicted = [(1, 1), (1, 2)];
+        let image = maze.to_image(&RenderOptions {
+            cell_size: 8,
+            solution: false,
+            predicted: Some(&predicted),
+            heatmap: None,
+        });
+        assert_eq!(image.get(20, 12), Some(PREDICTED));
+        assert_eq!(image.get(18, 10), Some(OPEN));
+    }
+
+    #[test]
+    fn test_heatmap_tints_open_cells_only() {
+        let maze = corridor();
+        let mut heat = vec![1.0; 15];
+        heat[7] = 0.0;
+        let options = RenderOptions {
+            cell_size: 4,
+            solution: false,
+            predicted: None,
+            heatmap: Some(&heat),
+        };
+        let image = maze.to_image(&options);
+        assert_eq!(image.get(0, 0), Some(WALL));
+        assert_eq!(image.get(4, 4), Some(START));
+        let cold = image.get(8, 4).unwrap();
+        assert_ne!(cold, OPEN);
+        assert!(cold[2] > cold[0], "low confidence is blue");
+    }
+
+    #[test]
+    fn test_montage_of_mazes() {
+        let mazes = vec![corridor(), corridor(), corridor()];
+        let options = RenderOptions {
+            cell_size: 2,
+            ..Default::default()
+        };
+        let montage = maze_montage(&mazes, 2, &options);
+        assert_eq!(montage.width(), 2 * 10 + 3 * 2);
+        assert_eq!(montage.height(), 2 * 6 + 3 * 2);
+    }
+}
@@ -0,0 +1,399 @@
+//! Maze solvers and solution analysis
+//!
+//! Complements the BFS in [`Maze::solve`] with A*, BFS distance maps,
+//! shortest-path counting, dead-end and junction detection, and a checker for
+//! candidate paths such as decoded model predictions.
+
+use super::{Cell, Maze};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use thiserror::Error;
+
+/// A grid position (row, col)
+pub type Pos = (usize, usize);
+
+/// Search algorithm for [`Maze::solve_with`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Solver {
+    /// Breadth-first search
+    #[default]
+    Bfs,
+    /// A* with a Manhattan-distance heuristic
+    AStar,
+}
+
+/// Why a candidate path is not a valid solution
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PathError {
+    #[error("path is empty")]
+    Empty,
+    #[error("path starts at {0:?}, not at the start")]
+    WrongStart(Pos),
+    #[error("path ends at {0:?}, not at the goal")]
+    WrongEnd(Pos),
+    #[error("step {index} at {pos:?} is outside the maze")]
+    OutOfBounds { index: usize, pos: Pos },
+    #[error("step {index} at {pos:?} goes through a wall")]
+    ThroughWall { index: usize, pos: Pos },
+    #[error("step {index} at {pos:?} is not adjacent to the previous cell")]
+    NotContiguous { index: usize, pos: Pos },
+}
+
+/// Result of checking a valid path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathCheck {
+    /// Moves taken by the path
+    pub steps: usize,
+    /// Moves taken by a shortest path
+    pub shortest: usize,
+}
+
+impl PathCheck {
+    /// Whether the path is as short as possible
+    pub fn is_optimal(&self) -> bool {
+        self.steps == self.shortest
+    }
+}
+
+impl Maze {
+    /// Solve with the given algorithm, storing a shortest path in `solution`
+    pub fn solve_with(&mut self, solver: Solver) -> bool {
+        match solver {
+            Solver::Bfs => self.solve(),
+            Solver::AStar => {
+                self.solution = self.astar();
+                self.solution.is_some()
+            }
+        }
+    }
+
+    /// Find a shortest path from start to goal with A*
+    pub fn astar(&self) -> Option<Vec<Pos>> {
+        let heuristic = |(row, col): Pos| row.abs_diff(self.goal.0) + col.abs_diff(self.goal.1);
+
+        let mut cost = vec![vec![usize::MAX; self.width]; self.height];
+        let mut parent = vec![vec![None; self.width]; self.height];
+        let mut open = BinaryHeap::new();
+
+        cost[self.start.0][self.start.1] = 0;
+        open.push(Reverse((heuristic(self.start), 0, self.start)));
+
+        while let Some(Reverse((_, steps, pos))) = open.pop() {
+            if pos == self.goal {
+                return Some(trace_back(&parent, pos));
+            }
+            if steps > cost[pos.0][pos.1] {
+                continue; // stale entry
+            }
+            for next in self.open_neighbors(pos) {
+                if steps + 1 < cost[next.0][next.1] {
+                    cost[next.0][next.1] = steps + 1;
+                    parent[next.0][next.1] = Some(pos);
+                    open.push(Reverse((steps + 1 + heuristic(next), steps + 1, next)));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// BFS distance in moves from `from` to every cell; `None` if unreachable
+    pub fn distance_map(&self, from: Pos) -> Vec<Vec<Option<usize>>> {
+        let mut distance = vec![vec![None; self.width]; self.height];
+        if self.grid[from.0][from.1] == Cell::Wall {
+            return distance;
+        }
+
+        let mut queue = VecDeque::from([from]);
+        distance[from.0][from.1] = Some(0);
+        while let Some(pos) = queue.pop_front() {
+            let next_distance = distance[pos.0][pos.1].map(|d| d + 1);
+            for next in self.open_neighbors(pos) {
+                if distance[next.0][next.1].is_none() {
+                    distance[next.0][next.1] = next_distance;
+                    queue.push_back(next);
+                }
+            }
+        }
+        distance
+    }
+
+    /// Moves on a shortest path from start to goal, if one exists
+    pub fn shortest_path_length(&self) -> Option<usize> {
+        self.distance_map(self.start)[self.goal.0][self.goal.1]
+    }
+
+    /// Number of distinct shortest paths from start to goal
+    ///
+    /// Saturates at `u64::MAX` on very open grids.
+    pub fn count_shortest_paths(&self) -> u64 {
+        let distance = self.distance_map(self.start);
+        let Some(goal_distance) = distance[self.goal.0][self.goal.1] else {
+            return 0;
+        };
+
+        // Visit cells in BFS order, summing counts from the previous layer
+        let mut layers = vec![Vec::new(); goal_distance + 1];
+        for (row, cells) in distance.iter().enumerate() {
+            for (col, d) in cells.iter().enumerate() {
+                if let Some(d) = *d {
+                    if d <= goal_distance {
+                        layers[d].push((row, col));
+                    }
+                }
+            }
+        }
+
+        let mut count = vec![vec![0u64; self.width]; self.height];
+        count[self.start.0][self.start.1] = 1;
+        for layer in layers.iter().skip(1) {
+            for &pos in layer {
+                let d = distance[pos.0][pos.1];
+                count[pos.0][pos.1] = self
+                    .open_neighbors(pos)
+                    .into_iter()
+                    .filter(|&(r, c)| distance[r][c].map(|p| p + 1) == d)
+                    .fold(0u64, |total, (r, c)| total.saturating_add(count[r][c]));
+            }
+        }
+        count[self.goal.0][self.goal.1]
+    }
+
+    /// Open cells other than start and goal with exactly one open neighbour
+    pub fn dead_ends(&self) -> Vec<Pos> {
+        self.open_cells()
+            .filter(|&pos| pos != self.start && pos != self.goal)
+            .filter(|&pos| self.open_neighbors(pos).len() == 1)
+            .collect()
+    }
+
+    /// Open cells with three or more open neighbours
+    pub fn junctions(&self) -> Vec<Pos> {
+        self.open_cells()
+            .filter(|&pos| self.open_neighbors(pos).len() >= 3)
+            .collect()
+    }
+
+    /// Check a candidate path and compare it with a shortest path
+    ///
+    /// A valid path runs from start to goal through adjacent cells and never
+    /// enters a wall; it may revisit cells, which makes it non-optimal.
+    pub fn check_path(&self, path: &[Pos]) -> Result<PathCheck, PathError> {
+        let (&first, &last) = match (path.first(), path.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Err(PathError::Empty),
+        };
+
+        for (index, &pos) in path.iter().enumerate() {
+            if pos.0 >= self.height || pos.1 >= self.width {
+                return Err(PathError::OutOfBounds { index, pos });
+            }
+            if self.grid[pos.0][pos.1] == Cell::Wall {
+                return Err(PathError::ThroughWall { index, pos });
+            }
+            if index > 0 {
+                let prev = path[index - 1];
+                if prev.0.abs_diff(pos.0) + prev.1.abs_diff(pos.1) != 1 {
+                    return Err(PathError::NotContiguous { index, pos });
+                }
+            }
+        }
+        if first != self.start {
+            return Err(PathError::WrongStart(first));
+        }
+        if last != self.goal {
+            return Err(PathError::WrongEnd(last));
+        }
+
+        let shortest = self
+            .shortest_path_length()
+            .expect("a valid path proves the goal is reachable");
+        Ok(PathCheck {
+            steps: path.len() - 1,
+            shortest,
+        })
+    }
+
+    /// Whether `cells`, in any order, are exactly one shortest path from
+    /// start to goal
+    ///
+    /// Every cell must lie on some shortest path, with exactly one cell per
+    /// step and consecutive steps adjacent, so extra or missing cells fail.
+    pub fn is_shortest_path_set(&self, cells: &[Pos]) -> bool {
+        let from_start = self.distance_map(self.start);
+        let from_goal = self.distance_map(self.goal);
+        let Some(length) = from_start[self.goal.0][self.goal.1] else {
+            return false;
+        };
+        if cells.len() != length + 1 {
+            return false;
+        }
+
+        let mut by_step = vec![None; length + 1];
+        for &(row, col) in cells {
+            if row >= self.height || col >= self.width {
+                return false;
+            }
+            match (from_start[row][col], from_goal[row][col]) {
+                (Some(d), Some(rest)) if d + rest == length && by_step[d].is_none() => {
+                    by_step[d] = Some((row, col));
+                }
+                _ => return false,
+            }
+        }
+        by_step.windows(2).all(
+            |pair| matches!(pair, [Some(a), Some(b)] if a.0.abs_diff(b.0) + a.1.abs_diff(b.1) == 1),
+        )
+    }
+
+    fn open_cells(&self) -> impl Iterator<Item = Pos> + '_ {
+        (0..self.height)
+            .flat_map(move |row| (0..self.width).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.grid[row][col] != Cell::Wall)
+    }
+}
+
+fn trace_back(parent: &[Vec<Option<Pos>>], end: Pos) -> Vec<Pos> {
+    let mut path = vec![end];
+    let mut current = parent[end.0][end.1];
+    while let Some(pos) = current {
+        path.push(pos);
+        current = parent[pos.0][pos.1];
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::maze::{MazeAlgorithm, MazeGenerator};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// Open 5x5 room: many shortest paths from corner to corner
+    const ROOM: &str = "\
+#######
+#S    #
+#     #
+#     #
+#     #
+#    G#
+#######";
+
+    #[test]
+    fn test_astar_matches_bfs() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let generator = MazeGenerator {
+            braid: 0.7,
+            ..MazeGenerator::new(MazeAlgorithm::Kruskal)
+        };
+        for _ in 0..5 {
+            let mut maze = generator.generate(21, 15, &mut rng).unwrap();
+            assert!(maze.solve());
+            let bfs = maze.solution.clone().unwrap();
+
+            assert!(maze.solve_with(Solver::AStar));
+            let astar = maze.solution.clone().unwrap();
+            assert_eq!(astar.len(), bfs.len());
+            assert!(maze.check_path(&astar).unwrap().is_optimal());
+        }
+    }
+
+    #[test]
+    fn test_distance_map_and_counts() {
+        let maze = Maze::from_ascii(ROOM).unwrap();
+        let distance = maze.distance_map(maze.start);
+        assert_eq!(distance[1][1], Some(0));
+        assert_eq!(distance[5][5], Some(8));
+        assert_eq!(distance[0][0], None);
+        assert_eq!(maze.shortest_path_length(), Some(8));
+
+        // Monotone lattice paths across a 5x5 room: C(8, 4)
+        assert_eq!(maze.count_shortest_paths(), 70);
+
+        // A perfect maze has exactly one
+        let maze = Maze::generate_random(15, 15).unwrap();
+        assert_eq!(maze.count_shortest_paths(), 1);
+    }
+
+    #[test]
+    fn test_dead_ends_and_junctions() {
+        let maze = Maze::from_ascii(
+            "\
+#######
+#S   G#
+## ## #
+#######",
+        )
+        .unwrap();
+        // Stubs below the corridor; the start is an endpoint, not a dead end
+        assert_eq!(maze.dead_ends(), vec![(2, 2), (2, 5)]);
+        assert_eq!(maze.junctions(), vec![(1, 2)]);
+    }
+
+    #[test]
+    fn test_check_path() {
+        let maze = Maze::from_ascii(ROOM).unwrap();
+        let straight: Vec<Pos> = (1..=5)
+            .map(|c| (1, c))
+            .chain((2..=5).map(|r| (r, 5)))
+            .collect();
+        assert!(maze.check_path(&straight).unwrap().is_optimal());
+
+        let detour: Vec<Pos> = [(1, 1), (2, 1), (1, 1)]
+            .into_iter()
+            .chain(straight[1..].iter().copied())
+            .collect();
+        let check = maze.check_path(&detour).unwrap();
+        assert_eq!((check.steps, check.shortest), (10, 8));
+        assert!(!check.is_optimal());
+
+        assert_eq!(maze.check_path(&[]), Err(PathError::Empty));
+        assert_eq!(
+            maze.check_path(&straight[1..]),
+            Err(PathError::WrongStart((1, 2)))
+        );
+        assert_eq!(
+            maze.check_path(&straight[..8]),
+            Err(PathError::WrongEnd((4, 5)))
+        );
+        assert_eq!(
+            maze.check_path(&[(1, 1), (1, 0)]),
+            Err(PathError::ThroughWall {
+                index: 1,
+                pos: (1, 0)
+            })
+        );
+        assert_eq!(
+            maze.check_path(&[(1, 1), (2, 2)]),
+            Err(PathError::NotContiguous {
+                index: 1,
+                pos: (2, 2)
+            })
+        );
+    }
+
+    #[test]
+    fn test_shortest_path_set() {
+        let maze = Maze::from_ascii(ROOM).unwrap();
+        let mut straight: Vec<Pos> = (1..=5)
+            .map(|c| (1, c))
+            .chain((2..=5).map(|r| (r, 5)))
+            .collect();
+        straight.reverse();
+        assert!(maze.is_shortest_path_set(&straight));
+
+        // An extra cell, a missing cell, two cells on one step, a gap
+        let mut extra = straight.clone();
+        extra.push((2, 1));
+        assert!(!maze.is_shortest_path_set(&extra));
+        assert!(!maze.is_shortest_path_set(&straight[1..]));
+        let mut doubled = straight.clone();
+        doubled[1] = (5, 3);
+        assert!(!maze.is_shortest_path_set(&doubled));
+        let mut gap = straight.clone();
+        gap[4] = (2, 2);
+        assert!(!maze.is_shortest_path_set(&gap));
+    }
+}
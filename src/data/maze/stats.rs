@@ -0,0 +1,267 @@
+//! Per-maze difficulty statistics and filters
+//!
+//! Mazes of one size vary a lot in how hard they are to solve. [`MazeStats`]
+//! summarizes a maze's solution and structure, [`MazeMetric`] picks one of
+//! those numbers, and [`MazeFilter`] keeps mazes whose metric lies in a range.
+
+use super::solve::Pos;
+use super::Maze;
+use crate::utils::{Result, TRMError};
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Difficulty statistics of a solvable maze
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MazeStats {
+    /// Moves on a shortest solution
+    pub solution_length: usize,
+    /// Open cells other than start and goal with one open neighbour
+    pub dead_ends: usize,
+    /// Open cells with three or more open neighbours
+    pub junctions: usize,
+    /// Mean number of ways forward at each step of the solution
+    pub branching_factor: f32,
+    /// Solution length over the Manhattan distance from start to goal
+    pub tortuosity: f32,
+    /// Changes of direction along the solution
+    pub turns: usize,
+}
+
+impl Maze {
+    /// Difficulty statistics, or `None` if the goal is unreachable
+    ///
+    /// Uses the stored solution if there is one, otherwise a shortest path.
+    pub fn stats(&self) -> Option<MazeStats> {
+        let path = match &self.solution {
+            Some(path) => path.clone(),
+            None => self.astar()?,
+        };
+        let steps = path.len() - 1;
+
+        // Ways forward: open neighbours other than the cell we came from
+        let choices: usize = path[..steps]
+            .iter()
+            .enumerate()
+            .map(|(i, &pos)| {
+                let neighbors = self.open_neighbors(pos).len();
+                if i == 0 {
+                    neighbors
+                } else {
+                    neighbors - 1
+                }
+            })
+            .sum();
+
+        let direct = self.start.0.abs_diff(self.goal.0) + self.start.1.abs_diff(self.goal.1);
+        Some(MazeStats {
+            solution_length: steps,
+            dead_ends: self.dead_ends().len(),
+            junctions: self.junctions().len(),
+            branching_factor: if steps == 0 {
+                0.0
+            } else {
+                choices as f32 / steps as f32
+            },
+            tortuosity: steps as f32 / direct.max(1) as f32,
+            turns: count_turns(&path),
+        })
+    }
+}
+
+fn count_turns(path: &[Pos]) -> usize {
+    let step = |a: Pos, b: Pos| (b.0 as isize - a.0 as isize, b.1 as isize - a.1 as isize);
+    path.windows(3)
+        .filter(|w| step(w[0], w[1]) != step(w[1], w[2]))
+        .count()
+}
+
+/// One number from [`MazeStats`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MazeMetric {
+    SolutionLength,
+    DeadEnds,
+    Junctions,
+    BranchingFactor,
+    Tortuosity,
+    Turns,
+}
+
+impl MazeMetric {
+    /// Every metric, in declaration order
+    pub const ALL: [MazeMetric; 6] = [
+        MazeMetric::SolutionLength,
+        MazeMetric::DeadEnds,
+        MazeMetric::Junctions,
+        MazeMetric::BranchingFactor,
+        MazeMetric::Tortuosity,
+        MazeMetric::Turns,
+    ];
+
+    /// Snake-case name, as accepted by `from_str`
+    pub fn name(self) -> &'static str {
+        match self {
+            MazeMetric::SolutionLength => "solution_length",
+            MazeMetric::DeadEnds => "dead_ends",
+            MazeMetric::Junctions => "junctions",
+            MazeMetric::BranchingFactor => "branching_factor",
+            MazeMetric::Tortuosity => "tortuosity",
+            MazeMetric::Turns => "turns",
+        }
+    }
+
+    /// The metric's value for a maze
+    pub fn value(self, stats: &MazeStats) -> f32 {
+        match self {
+            MazeMetric::SolutionLength => stats.solution_length as f32,
+            MazeMetric::DeadEnds => stats.dead_ends as f32,
+            MazeMetric::Junctions => stats.junctions as f32,
+            MazeMetric::BranchingFactor => stats.branching_factor,
+            MazeMetric::Tortuosity => stats.tortuosity,
+            MazeMetric::Turns => stats.turns as f32,
+        }
+    }
+}
+
+impl fmt::Display for MazeMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for MazeMetric {
+    type Err = TRMError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        MazeMetric::ALL
+            .into_iter()
+            .find(|metric| metric.name() == s.to_ascii_lowercase())
+            .ok_or_else(|| TRMError::InvalidData(format!("unknown maze metric: {:?}", s)))
+    }
+}
+
+/// Keep mazes whose metric lies in `min..=max`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MazeFilter {
+    pub metric: MazeMetric,
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+}
+
+impl MazeFilter {
+    /// Whether a maze with these statistics passes
+    pub fn matches(&self, stats: &MazeStats) -> bool {
+        let value = self.metric.value(stats);
+        self.min.map_or(true, |min| value >= min) && self.max.map_or(true, |max| value <= max)
+    }
+}
+
+/// Parses `metric=min..max`, where either bound may be left out
+/// (e.g. `solution_length=20..`, `dead_ends=..5`)
+impl FromStr for MazeFilter {
+    type Err = TRMError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid =
+            || TRMError::InvalidData(format!("maze filter {:?} is not metric=min..max", s));
+        let (metric, range) = s.split_once('=').ok_or_else(invalid)?;
+        let (min, max) = range.split_once("..").ok_or_else(invalid)?;
+        let bound = |text: &str| -> Result<Option<f32>> {
+            let text = text.trim();
+            if text.is_empty() {
+                Ok(None)
+            } else {
+                text.parse().map(Some).map_err(|_| invalid())
+            }
+        };
+        Ok(Self {
+            metric: metric.trim().parse()?,
+            min: bound(min)?,
+            max: bound(max)?,
+        })
+    }
+}
+
+/// Index of the bucket that `value` falls in
+///
+/// `edges` must be ascending; bucket `i` holds values below `edges[i]` and at
+/// or above `edges[i - 1]`, so there are `edges.len() + 1` buckets.
+pub fn bucket_of(value: f32, edges: &[f32]) -> usize {
+    edges.iter().take_while(|&&edge| value >= edge).count()
+}
+
+/// Edges that split `values` into `buckets` groups of near-equal size
+pub fn quantile_edges(values: &[f32], buckets: usize) -> Vec<f32> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let mut edges: Vec<f32> = (1..buckets)
+        .filter_map(|i| sorted.get(i * sorted.len() / buckets).copied())
+        .collect();
+    edges.dedup();
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_of_known_maze() {
+        let maze = Maze::from_ascii(
+            "\
+#######
+#S   G#
+## ## #
+#######",
+        )
+        .unwrap();
+        let stats = maze.stats().unwrap();
+        assert_eq!(stats.solution_length, 4);
+        assert_eq!(stats.dead_ends, 2);
+        assert_eq!(stats.junctions, 1);
+        assert_eq!(stats.turns, 0);
+        assert_eq!(stats.tortuosity, 1.0);
+        // One way forward everywhere except the junction at (1, 2)
+        assert_eq!(stats.branching_factor, 5.0 / 4.0);
+
+        let winding = Maze::from_ascii("#####\n#S  #\n### #\n#G  #\n#####").unwrap();
+        let stats = winding.stats().unwrap();
+        assert_eq!(stats.solution_length, 6);
+        assert_eq!(stats.turns, 2);
+        assert_eq!(stats.tortuosity, 3.0);
+
+        let blocked = Maze::from_ascii("#####\n#S#G#\n#####").unwrap();
+        assert!(blocked.stats().is_none());
+    }
+
+    #[test]
+    fn test_filter_parse_and_match() {
+        let filter: MazeFilter = "solution_length=5..10".parse().unwrap();
+        assert_eq!(filter.metric, MazeMetric::SolutionLength);
+        assert_eq!((filter.min, filter.max), (Some(5.0), Some(10.0)));
+
+        let open: MazeFilter = "dead_ends=..2".parse().unwrap();
+        assert_eq!(open.min, None);
+
+        let maze = Maze::from_ascii("#####\n#S  #\n### #\n#G  #\n#####").unwrap();
+        let stats = maze.stats().unwrap();
+        assert!(filter.matches(&stats));
+        assert!(open.matches(&stats));
+        assert!(!"turns=3..".parse::<MazeFilter>().unwrap().matches(&stats));
+
+        assert!("solution_length".parse::<MazeFilter>().is_err());
+        assert!("nope=1..2".parse::<MazeFilter>().is_err());
+        assert!("turns=a..2".parse::<MazeFilter>().is_err());
+    }
+
+    #[test]
+    fn test_buckets() {
+        assert_eq!(bucket_of(1.0, &[2.0, 4.0]), 0);
+        assert_eq!(bucket_of(2.0, &[2.0, 4.0]), 1);
+        assert_eq!(bucket_of(9.0, &[2.0, 4.0]), 2);
+
+        let values: Vec<f32> = (0..9).map(|v| v as f32).collect();
+        assert_eq!(quantile_edges(&values, 3), vec![3.0, 6.0]);
+        assert_eq!(quantile_edges(&[1.0; 5], 3), vec![1.0]);
+    }
+}
@@ -1,31 +1,44 @@
 //! Data structures and task definitions
 
+pub mod algorithmic;
+pub mod arc;
+pub mod augment;
+pub mod dataset;
+pub mod graph;
+pub mod inference;
+pub mod io;
 pub mod maze;
+pub mod problem;
+pub mod registry;
+pub mod split;
+pub mod sudoku;
 pub mod tasks;
 
 use ndarray::Array2;
 
-pub use maze::{Cell, Direction, Maze, MazeTask};
+pub use algorithmic::{AlgorithmicKind, AlgorithmicTask, TokenEncoding};
+pub use arc::{ArcEncoder, ArcProblemSet, ArcTask};
+pub use augment::{
+    augment_dataset, AugmentationConfig, AugmentationMode, AugmentedDataset, GridTransform,
+    Symmetry,
+};
+pub use dataset::{generated_mazes, generated_sudoku, Dataset, FileDataset, GeneratedDataset};
+pub use graph::{Graph, GraphModel, GraphObjective, GraphTask};
+pub use inference::{load_inference_data, save_predictions, InferenceData, InferenceFormat};
+pub use io::{load_dataset, save_dataset, DatasetFormat};
+pub use maze::{
+    load_mazes, save_mazes, Cell, Direction, Maze, MazeAlgorithm, MazeGenerator, MazeTask,
+    Placement,
+};
+pub use problem::{instances, BatchValidation, Instance, Problem, Task, Validation};
+pub use registry::{build_task, build_task_with_rng, BuiltTask, TaskSpec};
+pub use split::{
+    k_fold, split_indices, stratified_k_fold, stratified_split_indices, Fold, SplitIndices,
+    SplitRatios, Subset,
+};
+pub use sudoku::{SudokuDifficulty, SudokuGrid, SudokuPuzzle, SudokuTask};
 pub use tasks::{CopyTask, SequenceTask};
 
-/// Trait for problem definitions
-pub trait Problem {
-    /// Get the input tensor for this problem
-    fn input(&self) -> &Array2<f32>;
-
-    /// Get the target output tensor
-    fn target(&self) -> &Array2<f32>;
-
-    /// Validate if a solution is correct
-    fn validate_solution(&self, output: &Array2<f32>) -> bool;
-
-    /// Get input dimension
-    fn input_dim(&self) -> usize;
-
-    /// Get output dimension
-    fn output_dim(&self) -> usize;
-}
-
 /// A training example with input and target
 #[derive(Debug, Clone)]
 pub struct TrainingExample {
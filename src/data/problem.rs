@@ -0,0 +1,306 @@
+//! Problem instances, tasks and structured validation
+//!
+//! A [`Task`] owns a set of examples and knows how to check a prediction for
+//! any one of them. A [`Problem`] is a single instance: one input, its
+//! target, and a validator. [`Instance`] is the problem view of one example
+//! of a task, so code written against `Problem` never silently falls back to
+//! the first example.
+
+use super::TrainingExample;
+use ndarray::{Array2, Axis};
+use std::fmt;
+
+/// Outcome of checking one prediction
+#[derive(Debug, Clone, PartialEq)]
+pub struct Validation {
+    /// Whether the prediction solves the problem
+    pub correct: bool,
+    /// Partial credit in `[0, 1]`; 1.0 when correct
+    pub score: f32,
+    /// Why an incorrect prediction failed
+    pub reason: Option<String>,
+}
+
+impl Validation {
+    /// A correct prediction
+    pub fn correct() -> Self {
+        Self {
+            correct: true,
+            score: 1.0,
+            reason: None,
+        }
+    }
+
+    /// An incorrect prediction with partial credit and a reason
+    pub fn incorrect(score: f32, reason: impl Into<String>) -> Self {
+        Self {
+            correct: false,
+            score: score.clamp(0.0, 1.0),
+            reason: Some(reason.into()),
+        }
+    }
+
+    /// Correct if `reason` is `None`, otherwise incorrect with `score`
+    pub fn check(score: f32, reason: Option<String>) -> Self {
+        match reason {
+            None => Self::correct(),
+            Some(reason) => Self::incorrect(score, reason),
+        }
+    }
+}
+
+impl fmt::Display for Validation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            None => write!(f, "correct"),
+            Some(reason) => write!(f, "incorrect ({:.3}): {}", self.score, reason),
+        }
+    }
+}
+
+/// Validations for a batch of predictions
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BatchValidation {
+    /// One result per prediction, in order
+    pub results: Vec<Validation>,
+}
+
+impl BatchValidation {
+    /// Number of correct predictions
+    pub fn num_correct(&self) -> usize {
+        self.results.iter().filter(|v| v.correct).count()
+    }
+
+    /// Fraction of correct predictions (0.0 for an empty batch)
+    pub fn accuracy(&self) -> f32 {
+        self.num_correct() as f32 / self.results.len().max(1) as f32
+    }
+
+    /// Mean partial score (0.0 for an empty batch)
+    pub fn mean_score(&self) -> f32 {
+        self.results.iter().map(|v| v.score).sum::<f32>() / self.results.len().max(1) as f32
+    }
+
+    /// Positions and results of the incorrect predictions
+    pub fn failures(&self) -> impl Iterator<Item = (usize, &Validation)> {
+        self.results.iter().enumerate().filter(|(_, v)| !v.correct)
+    }
+
+    /// The results at `positions`, e.g. one difficulty bucket
+    ///
+    /// Positions past the end are skipped.
+    pub fn select(&self, positions: &[usize]) -> BatchValidation {
+        BatchValidation {
+            results: positions
+                .iter()
+                .filter_map(|&i| self.results.get(i).cloned())
+                .collect(),
+        }
+    }
+}
+
+/// A single problem instance
+pub trait Problem {
+    /// The input tensor
+    fn input(&self) -> &Array2<f32>;
+
+    /// The expected output tensor
+    fn target(&self) -> &Array2<f32>;
+
+    /// Check a predicted output
+    fn validate(&self, output: &Array2<f32>) -> Validation;
+}
+
+/// A set of problem instances of one shape
+pub trait Task {
+    /// All examples, in instance order
+    fn examples(&self) -> &[TrainingExample];
+
+    /// Check a predicted output for the example at `index`
+    ///
+    /// Outputs with the wrong number of values are incorrect.
+    fn validate_example(&self, index: usize, output: &Array2<f32>) -> Validation;
+
+    /// Input dimension of every example
+    fn input_dim(&self) -> usize;
+
+    /// Output dimension of every example
+    fn output_dim(&self) -> usize;
+
+    /// Difficulty key of the example at `index`, for stratified splits
+    ///
+    /// Larger is harder; `None` if the task has no notion of difficulty.
+    fn difficulty(&self, _index: usize) -> Option<usize> {
+        None
+    }
+
+    /// Number of instances
+    fn num_instances(&self) -> usize {
+        self.examples().len()
+    }
+
+    /// Check a batch of predictions, one per row, for examples `start..`
+    ///
+    /// Rows past the last example are reported as incorrect.
+    fn validate_batch(&self, start: usize, outputs: &Array2<f32>) -> BatchValidation {
+        let results = outputs
+            .axis_iter(Axis(0))
+            .enumerate()
+            .map(|(row, output)| {
+                let index = start + row;
+                if index >= self.num_instances() {
+                    return Validation::incorrect(0.0, format!("no example {}", index));
+                }
+                self.validate_example(index, &output.insert_axis(Axis(0)).to_owned())
+            })
+            .collect();
+        BatchValidation { results }
+    }
+}
+
+/// Check that an output has the expected number of values
+pub(crate) fn check_len(output: &Array2<f32>, expected: usize) -> Option<Validation> {
+    (output.len() != expected).then(|| {
+        Validation::incorrect(
+            0.0,
+            format!("expected {} values, got {}", expected, output.len()),
+        )
+    })
+}
+
+/// One example of a task, viewed as a problem
+#[derive(Debug)]
+pub struct Instance<'a, T: Task + ?Sized> {
+    task: &'a T,
+    index: usize,
+}
+
+impl<T: Task + ?Sized> Clone for Instance<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Task + ?Sized> Copy for Instance<'_, T> {}
+
+impl<'a, T: Task + ?Sized> Instance<'a, T> {
+    /// The instance at `index`, if the task has one
+    pub fn new(task: &'a T, index: usize) -> Option<Self> {
+        (index < task.num_instances()).then_some(Self { task, index })
+    }
+
+    /// Position of this instance in its task
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<T: Task + ?Sized> Problem for Instance<'_, T> {
+    fn input(&self) -> &Array2<f32> {
+        &self.task.examples()[self.index].input
+    }
+
+    fn target(&self) -> &Array2<f32> {
+        &self.task.examples()[self.index].target
+    }
+
+    fn validate(&self, output: &Array2<f32>) -> Validation {
+        self.task.validate_example(self.index, output)
+    }
+}
+
+/// Every instance of a task, in order
+pub fn instances<T: Task + ?Sized>(task: &T) -> impl Iterator<Item = Instance<'_, T>> {
+    (0..task.num_instances()).map(move |index| Instance { task, index })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    /// Targets are single values; a prediction is correct within 0.5
+    struct Rounding(Vec<TrainingExample>);
+
+    impl Task for Rounding {
+        fn examples(&self) -> &[TrainingExample] {
+            &self.0
+        }
+
+        fn validate_example(&self, index: usize, output: &Array2<f32>) -> Validation {
+            if let Some(invalid) = check_len(output, 1) {
+                return invalid;
+            }
+            let error = (output[[0, 0]] - self.0[index].target[[0, 0]]).abs();
+            let reason = (error >= 0.5).then(|| format!("off by {}", error));
+            Validation::check(1.0 - error.min(1.0), reason)
+        }
+
+        fn input_dim(&self) -> usize {
+            1
+        }
+
+        fn output_dim(&self) -> usize {
+            1
+        }
+    }
+
+    fn task() -> Rounding {
+        Rounding(
+            (0..3)
+                .map(|i| TrainingExample::new(array![[i as f32]], array![[i as f32 * 2.0]]))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_instances_use_their_own_example() {
+        let task = task();
+        let all: Vec<_> = instances(&task).collect();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[2].index(), 2);
+        assert_eq!(all[2].target(), &array![[4.0]]);
+        assert!(all[2].validate(&array![[4.2]]).correct);
+        assert!(!all[1].validate(&array![[4.2]]).correct);
+        assert!(Instance::new(&task, 3).is_none());
+    }
+
+    #[test]
+    fn test_validation_reasons() {
+        let task = task();
+        let wrong = task.validate_example(1, &array![[2.75]]);
+        assert!(!wrong.correct);
+        assert!((wrong.score - 0.25).abs() < 1e-6);
+        assert_eq!(wrong.to_string(), "incorrect (0.250): off by 0.75");
+
+        let short = task.validate_example(0, &Array2::zeros((1, 2)));
+        assert_eq!(short.reason.as_deref(), Some("expected 1 values, got 2"));
+        assert_eq!(Validation::correct().to_string(), "correct");
+    }
+
+    #[test]
+    fn test_validate_batch() {
+        let task = task();
+        // Rows for examples 1, 2 and a missing example 3
+        let batch = task.validate_batch(1, &array![[2.0], [3.0], [6.0]]);
+        assert_eq!(batch.results.len(), 3);
+        assert_eq!(batch.num_correct(), 1);
+        assert!((batch.accuracy() - 1.0 / 3.0).abs() < 1e-6);
+        let failures: Vec<usize> = batch.failures().map(|(i, _)| i).collect();
+        assert_eq!(failures, vec![1, 2]);
+        assert_eq!(batch.results[2].reason.as_deref(), Some("no example 3"));
+        assert_eq!(BatchValidation::default().accuracy(), 0.0);
+
+        let first = batch.select(&[0, 7]);
+        assert_eq!(first.results.len(), 1);
+        assert_eq!(first.accuracy(), 1.0);
+    }
+
+    #[test]
+    fn test_dyn_task() {
+        let task: Box<dyn Task> = Box::new(task());
+        let instance = Instance::new(task.as_ref(), 1).unwrap();
+        assert_eq!(instance.input(), &array![[1.0]]);
+        assert_eq!(instances(task.as_ref()).count(), 3);
+    }
+}
@@ -0,0 +1,518 @@
+//! Named tasks and their parameters
+//!
+//! A [`TaskSpec`] names a task and sets some of its parameters, written as
+//! `name` or `name:key=value,key=value` (e.g. `maze:size=11,encoding=onehot`).
+//! The registry fills in defaults, rejects unknown keys, and builds the task,
+//! so model dimensions can be taken from the built [`Task`]. Trained models
+//! record their resolved spec so evaluation can rebuild the same task.
+
+use super::algorithmic::{AlgorithmicKind, AlgorithmicTask, TokenEncoding};
+use super::arc::{ArcEncoder, ArcProblemSet, ArcTask};
+use super::graph::{GraphModel, GraphObjective, GraphTask};
+use super::maze::{MazeAlgorithm, MazeEncoding, MazeFilter, MazeGenerator, MazeTask};
+use super::sudoku::SudokuTask;
+use super::tasks::{CopyTask, SequenceTask};
+use super::{Task, TrainingExample};
+use crate::utils::{Result, TRMError};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// A task name plus parameter overrides
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskSpec {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, String>,
+}
+
+impl TaskSpec {
+    /// A spec with no parameter overrides
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_ascii_lowercase(),
+            params: BTreeMap::new(),
+        }
+    }
+
+    /// Set a parameter
+    pub fn with_param(mut self, key: &str, value: impl ToString) -> Self {
+        self.params.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Whether a parameter is set
+    pub fn has(&self, key: &str) -> bool {
+        self.params.contains_key(key)
+    }
+
+    /// Parse a parameter, which must be set (see [`resolve`])
+    pub fn get<T>(&self, key: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let value = self.params.get(key).ok_or_else(|| {
+            TRMError::InvalidData(format!("task {}: missing parameter `{}`", self.name, key))
+        })?;
+        value.parse().map_err(|e| {
+            TRMError::InvalidData(format!(
+                "task {}: invalid {} {:?}: {}",
+                self.name, key, value, e
+            ))
+        })
+    }
+}
+
+impl fmt::Display for TaskSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        for (i, (key, value)) in self.params.iter().enumerate() {
+            write!(f, "{}{}={}", if i == 0 { ':' } else { ',' }, key, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TaskSpec {
+    type Err = TRMError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, params) = s.split_once(':').unwrap_or((s, ""));
+        let mut spec = TaskSpec::new(name.trim());
+        for pair in params.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').ok_or_else(|| {
+                TRMError::InvalidData(format!("task parameter {:?} is not key=value", pair))
+            })?;
+            spec = spec.with_param(key.trim(), value.trim());
+        }
+        Ok(spec)
+    }
+}
+
+/// A parameter accepted by a registered task
+#[derive(Debug, Clone, Copy)]
+pub struct ParamInfo {
+    pub name: &'static str,
+    /// Value used when the spec does not set one; `None` makes it required
+    /// and an empty string leaves it unset
+    pub default: Option<&'static str>,
+    pub help: &'static str,
+}
+
+const fn param(name: &'static str, default: &'static str, help: &'static str) -> ParamInfo {
+    ParamInfo {
+        name,
+        default: Some(default),
+        help,
+    }
+}
+
+/// A built task and any separate training examples
+pub struct BuiltTask {
+    pub task: Box<dyn Task>,
+    /// Dedicated training examples; when absent, the task's examples are split instead
+    pub training: Option<Vec<TrainingExample>>,
+}
+
+impl BuiltTask {
+    fn generated<T: Task + 'static>(task: T) -> Self {
+        Self {
+            task: Box::new(task),
+            training: None,
+        }
+    }
+}
+
+/// A task that can be built by name
+pub struct TaskEntry {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub params: &'static [ParamInfo],
+    /// Suggested hidden width for models trained on this task
+    pub hidden_dim: usize,
+    build: fn(&TaskSpec, usize, &mut StdRng) -> Result<BuiltTask>,
+}
+
+impl TaskEntry {
+    /// Build `num_examples` examples from a resolved spec, drawing them
+    /// from `rng`
+    pub fn build(
+        &self,
+        spec: &TaskSpec,
+        num_examples: usize,
+        rng: &mut StdRng,
+    ) -> Result<BuiltTask> {
+        (self.build)(spec, num_examples, rng)
+    }
+}
+
+const ALGORITHMIC_PARAMS: &[ParamInfo] = &[
+    param(
+        "length",
+        "6",
+        "sequence length (digits per number for addition)",
+    ),
+    param("encoding", "token", "token or onehot"),
+];
+
+static REGISTRY: &[TaskEntry] = &[
+    TaskEntry {
+        name: "copy",
+        description: "Copy a random vector",
+        params: &[param("dim", "5", "vector length")],
+        hidden_dim: 16,
+        build: |spec, n, rng| {
+            Ok(BuiltTask::generated(CopyTask::with_rng(
+                n,
+                spec.get("dim")?,
+                rng,
+            )))
+        },
+    },
+    TaskEntry {
+        name: "sequence",
+        description: "Predict the next term of an arithmetic sequence",
+        params: &[param("length", "5", "terms shown")],
+        hidden_dim: 16,
+        build: |spec, n, rng| {
+            Ok(BuiltTask::generated(SequenceTask::with_rng(
+                n,
+                spec.get("length")?,
+                rng,
+            )))
+        },
+    },
+    TaskEntry {
+        name: "maze",
+        description: "Predict the solution path of a maze",
+        params: &[
+            param("size", "7", "side length"),
+            param("width", "", "width, if different from size"),
+            param("height", "", "height, if different from size"),
+            param(
+                "algorithm",
+                "backtracker",
+                "backtracker, prim, kruskal, wilson or eller",
+            ),
+            param("braid", "0", "fraction of dead ends to remove"),
+            param(
+                "encoding",
+                "scalar",
+                "scalar, onehot, channels or coordinates",
+            ),
+            param(
+                "filter",
+                "",
+                "difficulty filters metric=min..max, joined by ';'",
+            ),
+        ],
+        hidden_dim: 64,
+        build: |spec, n, rng| {
+            let size: usize = spec.get("size")?;
+            let side = |key: &str| -> Result<usize> {
+                match spec.params.get(key).map(String::as_str) {
+                    None | Some("") => Ok(size),
+                    Some(_) => spec.get(key),
+                }
+            };
+            let generator = MazeGenerator {
+                braid: spec.get("braid")?,
+                ..MazeGenerator::new(spec.get::<MazeAlgorithm>("algorithm")?)
+            };
+            let filters = spec
+                .params
+                .get("filter")
+                .map(|value| value.split(';').filter(|f| !f.trim().is_empty()))
+                .into_iter()
+                .flatten()
+                .map(str::parse)
+                .collect::<Result<Vec<MazeFilter>>>()?;
+            let task = MazeTask::with_rng(
+                n,
+                side("width")?,
+                side("height")?,
+                &generator,
+                &filters,
+                rng,
+            )?
+            .with_encoding(spec.get::<MazeEncoding>("encoding")?);
+            Ok(BuiltTask::generated(task))
+        },
+    },
+    TaskEntry {
+        name: "sudoku",
+        description: "Fill in a 9x9 Sudoku grid",
+        params: &[param("givens", "30", "filled cells per puzzle")],
+        hidden_dim: 128,
+        build: |spec, n, rng| {
+            Ok(BuiltTask::generated(SudokuTask::with_rng(
+                n,
+                spec.get("givens")?,
+                rng,
+            )))
+        },
+    },
+    TaskEntry {
+        name: "arc",
+        description: "ARC-style grid puzzles loaded from a directory of JSON tasks",
+        params: &[
+            ParamInfo {
+                name: "dir",
+                default: None,
+                help: "directory of ARC JSON task files",
+            },
+            param("size", "30", "side length of the padded canvas"),
+        ],
+        hidden_dim: 64,
+        build: |spec, _, _| {
+            let tasks = ArcTask::load_dir(spec.get::<String>("dir")?)?;
+            let encoder = ArcEncoder::square(spec.get("size")?);
+            let train = ArcProblemSet::train(&tasks, encoder)?;
+            let test = ArcProblemSet::test(&tasks, encoder)?;
+            Ok(BuiltTask {
+                task: Box::new(test),
+                training: Some(train.examples().to_vec()),
+            })
+        },
+    },
+    algorithmic_entry("sort", "Sort a sequence of digits"),
+    algorithmic_entry("reverse", "Reverse a sequence of digits"),
+    algorithmic_entry("addition", "Add two multi-digit numbers with carry"),
+    algorithmic_entry("parity", "Parity of a bit sequence"),
+    algorithmic_entry("parentheses", "Check whether parentheses are balanced"),
+    TaskEntry {
+        name: "graph",
+        description: "Shortest paths or connected components on random graphs",
+        params: &[
+            param("nodes", "8", "nodes per graph"),
+            param("model", "erdos-renyi", "erdos-renyi or geometric"),
+            param("p", "0.2", "edge probability (erdos-renyi)"),
+            param("radius", "0.3", "connection radius (geometric)"),
+            param("objective", "path", "path or components"),
+        ],
+        hidden_dim: 64,
+        build: |spec, n, rng| {
+            let model = match spec.get::<String>("model")?.as_str() {
+                "erdos-renyi" | "er" => GraphModel::ErdosRenyi {
+                    edge_prob: spec.get("p")?,
+                },
+                "geometric" => GraphModel::Geometric {
+                    radius: spec.get("radius")?,
+                },
+                other => {
+                    return Err(TRMError::InvalidData(format!(
+                        "unknown graph model: {:?}",
+                        other
+                    )))
+                }
+            };
+            let objective: GraphObjective = spec.get("objective")?;
+            let task = GraphTask::with_rng(n, spec.get("nodes")?, model, objective, rng)?;
+            Ok(BuiltTask::generated(task))
+        },
+    },
+];
+
+/// Sequence tasks share parameters and are built by name
+const fn algorithmic_entry(name: &'static str, description: &'static str) -> TaskEntry {
+    TaskEntry {
+        name,
+        description,
+        params: ALGORITHMIC_PARAMS,
+        hidden_dim: 64,
+        build: |spec, n, rng| {
+            let task = AlgorithmicTask::with_rng(
+                spec.name.parse::<AlgorithmicKind>()?,
+                n,
+                spec.get("length")?,
+                rng,
+            )?
+            .with_encoding(spec.get::<TokenEncoding>("encoding")?);
+            Ok(BuiltTask::generated(task))
+        },
+    }
+}
+
+/// Every registered task
+pub fn tasks() -> &'static [TaskEntry] {
+    REGISTRY
+}
+
+/// Look up a task by name
+pub fn lookup(name: &str) -> Result<&'static TaskEntry> {
+    REGISTRY
+        .iter()
+        .find(|entry| entry.name == name.to_ascii_lowercase())
+        .ok_or_else(|| {
+            let names: Vec<&str> = REGISTRY.iter().map(|entry| entry.name).collect();
+            TRMError::InvalidData(format!(
+                "unknown task {:?} (expected one of: {})",
+                name,
+                names.join(", ")
+            ))
+        })
+}
+
+/// Check a spec's parameters and fill in defaults
+pub fn resolve(spec: &TaskSpec) -> Result<TaskSpec> {
+    let entry = lookup(&spec.name)?;
+    if let Some(key) = spec
+        .params
+        .keys()
+        .find(|key| !entry.params.iter().any(|p| p.name == key.as_str()))
+    {
+        let known: Vec<&str> = entry.params.iter().map(|p| p.name).collect();
+        return Err(TRMError::InvalidData(format!(
+            "task {} has no parameter `{}` (expected one of: {})",
+            entry.name,
+            key,
+            known.join(", ")
+        )));
+    }
+
+    let mut resolved = TaskSpec::new(entry.name);
+    for info in entry.params {
+        match (spec.params.get(info.name), info.default) {
+            (Some(value), _) => {
+                resolved.params.insert(info.name.to_string(), value.clone());
+            }
+            (None, Some("")) => {}
+            (None, Some(default)) => {
+                resolved
+                    .params
+                    .insert(info.name.to_string(), default.to_string());
+            }
+            (None, None) => {
+                return Err(TRMError::InvalidData(format!(
+                    "task {}: missing parameter `{}` ({})",
+                    entry.name, info.name, info.help
+                )))
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+/// Resolve a spec and build `num_examples` examples
+///
+/// Returns the resolved spec alongside the task.
+pub fn build_task(spec: &TaskSpec, num_examples: usize) -> Result<(TaskSpec, BuiltTask)> {
+    build_task_with_rng(spec, num_examples, &mut StdRng::from_entropy())
+}
+
+/// Like [`build_task`], drawing the examples from `rng`
+///
+/// A seeded `rng` builds the same examples every time.
+pub fn build_task_with_rng(
+    spec: &TaskSpec,
+    num_examples: usize,
+    rng: &mut StdRng,
+) -> Result<(TaskSpec, BuiltTask)> {
+    let resolved = resolve(spec)?;
+    let task = lookup(&resolved.name)?.build(&resolved, num_examples, rng)?;
+    Ok((resolved, task))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_parse_and_display() {
+        let spec: TaskSpec = "Maze:size=11, encoding=onehot".parse().unwrap();
+        assert_eq!(spec.name, "maze");
+        assert_eq!(spec.params["size"], "11");
+        assert_eq!(spec.to_string(), "maze:encoding=onehot,size=11");
+        assert_eq!(spec.to_string().parse::<TaskSpec>().unwrap(), spec);
+
+        assert_eq!("copy".parse::<TaskSpec>().unwrap(), TaskSpec::new("copy"));
+        assert!("maze:size".parse::<TaskSpec>().is_err());
+    }
+
+    #[test]
+    fn test_resolve_defaults_and_errors() {
+        let resolved = resolve(&TaskSpec::new("maze").with_param("size", 9)).unwrap();
+        assert_eq!(resolved.params["size"], "9");
+        assert_eq!(resolved.params["algorithm"], "backtracker");
+        assert!(!resolved.has("width"));
+
+        assert!(resolve(&TaskSpec::new("nope")).is_err());
+        assert!(resolve(&TaskSpec::new("copy").with_param("size", 3)).is_err());
+        // The arc task has no default directory
+        assert!(resolve(&TaskSpec::new("arc")).is_err());
+    }
+
+    #[test]
+    fn test_build_infers_dimensions() {
+        let (_, copy) = build_task(&TaskSpec::new("copy").with_param("dim", 7), 4).unwrap();
+        assert_eq!(copy.task.input_dim(), 7);
+        assert_eq!(copy.task.num_instances(), 4);
+
+        let spec = TaskSpec::new("maze")
+            .with_param("size", 9)
+            .with_param("encoding", "onehot");
+        let (_, maze) = build_task(&spec, 2).unwrap();
+        assert_eq!(maze.task.input_dim(), 81 * 4);
+        assert_eq!(maze.task.output_dim(), 81);
+
+        let (_, addition) = build_task(&"addition:length=3".parse().unwrap(), 2).unwrap();
+        assert_eq!(addition.task.input_dim(), 6);
+        assert_eq!(addition.task.output_dim(), 4);
+
+        assert!(build_task(&"maze:algorithm=nope".parse().unwrap(), 1).is_err());
+
+        let filtered = "maze:size=9,filter=solution_length=10..;dead_ends=..40";
+        let (resolved, _) = build_task(&filtered.parse().unwrap(), 2).unwrap();
+        assert_eq!(
+            resolved.params["filter"],
+            "solution_length=10..;dead_ends=..40"
+        );
+        assert!(build_task(&"maze:filter=hard".parse().unwrap(), 1).is_err());
+    }
+
+    #[test]
+    fn test_seeded_builds_are_reproducible() {
+        for entry in tasks().iter().filter(|entry| entry.name != "arc") {
+            let spec = TaskSpec::new(entry.name);
+            let build = |seed| {
+                let (_, built) =
+                    build_task_with_rng(&spec, 3, &mut StdRng::seed_from_u64(seed)).unwrap();
+                built.task.examples().to_vec()
+            };
+            let (a, b, c) = (build(5), build(5), build(6));
+            for (x, y) in a.iter().zip(&b) {
+                assert_eq!(
+                    (&x.input, &x.target),
+                    (&y.input, &y.target),
+                    "{}",
+                    entry.name
+                );
+            }
+            assert!(
+                a.iter().zip(&c).any(|(x, y)| x.input != y.input),
+                "{}",
+                entry.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_default_task_builds() {
+        for entry in tasks().iter().filter(|entry| entry.name != "arc") {
+            let (resolved, built) = build_task(&TaskSpec::new(entry.name), 3).unwrap();
+            assert_eq!(resolved.name, entry.name);
+            let task = built.task;
+            assert_eq!(task.num_instances(), 3, "{}", entry.name);
+            for (index, example) in task.examples().iter().enumerate() {
+                assert_eq!(example.input.len(), task.input_dim(), "{}", entry.name);
+                assert_eq!(example.target.len(), task.output_dim(), "{}", entry.name);
+                let result = task.validate_example(index, &example.target);
+                assert!(result.correct, "{}: {}", entry.name, result);
+            }
+        }
+    }
+}
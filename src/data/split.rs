@@ -0,0 +1,282 @@
+//! Seeded dataset splitting
+//!
+//! Splits work on example indices so they apply to any task or dataset:
+//! shuffled train/validation/test splits, k-fold cross-validation, and
+//! stratified versions of both that spread a difficulty key evenly.
+//! [`Subset`] views the chosen indices of a task as a task of their own, so
+//! task-defined validation keeps working after a split.
+
+use super::{Task, TrainingExample, Validation};
+use crate::utils::{Result, TRMError};
+use ndarray::Array2;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+/// Example indices of a train/validation/test split
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SplitIndices {
+    pub train: Vec<usize>,
+    pub val: Vec<usize>,
+    pub test: Vec<usize>,
+}
+
+/// Example indices of one cross-validation fold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fold {
+    pub train: Vec<usize>,
+    pub val: Vec<usize>,
+}
+
+/// Fractions of examples for training and validation; the rest is the test set
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SplitRatios {
+    pub train: f32,
+    pub val: f32,
+}
+
+impl Default for SplitRatios {
+    fn default() -> Self {
+        Self {
+            train: 0.8,
+            val: 0.1,
+        }
+    }
+}
+
+impl SplitRatios {
+    /// Create ratios, checking that they are non-negative and sum to at most 1
+    pub fn new(train: f32, val: f32) -> Result<Self> {
+        if !(train >= 0.0 && val >= 0.0 && train + val <= 1.0 + 1e-6) {
+            return Err(TRMError::InvalidData(format!(
+                "split ratios {} (train) and {} (val) must be non-negative and sum to at most 1",
+                train, val
+            )));
+        }
+        Ok(Self { train, val })
+    }
+
+    fn targets(self) -> [f32; 3] {
+        [self.train, self.val, (1.0 - self.train - self.val).max(0.0)]
+    }
+}
+
+/// Indices `0..len` in a random order fixed by `seed`
+pub fn shuffled_indices(len: usize, seed: u64) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..len).collect();
+    indices.shuffle(&mut StdRng::seed_from_u64(seed));
+    indices
+}
+
+/// Shuffle and split `len` examples into train, validation and test sets
+pub fn split_indices(len: usize, ratios: SplitRatios, seed: u64) -> SplitIndices {
+    let order = shuffled_indices(len, seed);
+    let train_end = ((len as f32 * ratios.train).round() as usize).min(len);
+    let val_end =
+        ((len as f32 * (ratios.train + ratios.val)).round() as usize).clamp(train_end, len);
+    SplitIndices {
+        train: order[..train_end].to_vec(),
+        val: order[train_end..val_end].to_vec(),
+        test: order[val_end..].to_vec(),
+    }
+}
+
+/// Split so that every set covers the range of `keys` evenly
+///
+/// Examples are ordered by key (ties in seeded random order) and dealt to
+/// whichever set is furthest below its share, so continuous keys such as a
+/// solution length are stratified as well as categorical ones.
+pub fn stratified_split_indices<K: Ord>(
+    keys: &[K],
+    ratios: SplitRatios,
+    seed: u64,
+) -> SplitIndices {
+    let targets = ratios.targets();
+    let mut sets: [Vec<usize>; 3] = Default::default();
+    for (position, index) in order_by_key(keys, seed).into_iter().enumerate() {
+        let seen = (position + 1) as f32;
+        let set = (0..3)
+            .max_by(|&a, &b| {
+                let deficit = |s: usize| targets[s] * seen - sets[s].len() as f32;
+                deficit(a).total_cmp(&deficit(b)).then(b.cmp(&a))
+            })
+            .expect("three sets");
+        sets[set].push(index);
+    }
+    let [train, val, test] = sets;
+    SplitIndices { train, val, test }
+}
+
+/// Shuffle `len` examples into `k` folds of near-equal size
+pub fn k_fold(len: usize, k: usize, seed: u64) -> Result<Vec<Fold>> {
+    check_folds(len, k)?;
+    Ok(folds_from_order(&shuffled_indices(len, seed), k))
+}
+
+/// `k` folds that each cover the range of `keys` evenly
+pub fn stratified_k_fold<K: Ord>(keys: &[K], k: usize, seed: u64) -> Result<Vec<Fold>> {
+    check_folds(keys.len(), k)?;
+    Ok(folds_from_order(&order_by_key(keys, seed), k))
+}
+
+fn check_folds(len: usize, k: usize) -> Result<()> {
+    if k < 2 || k > len {
+        return Err(TRMError::InvalidData(format!(
+            "cannot make {} folds from {} examples",
+            k, len
+        )));
+    }
+    Ok(())
+}
+
+/// Deal `order` round-robin into `k` validation folds
+fn folds_from_order(order: &[usize], k: usize) -> Vec<Fold> {
+    (0..k)
+        .map(|fold| {
+            let mut split = Fold {
+                train: Vec::new(),
+                val: Vec::new(),
+            };
+            for (position, &index) in order.iter().enumerate() {
+                if position % k == fold {
+                    split.val.push(index);
+                } else {
+                    split.train.push(index);
+                }
+            }
+            split
+        })
+        .collect()
+}
+
+fn order_by_key<K: Ord>(keys: &[K], seed: u64) -> Vec<usize> {
+    let mut order = shuffled_indices(keys.len(), seed);
+    order.sort_by(|&a, &b| keys[a].cmp(&keys[b]));
+    order
+}
+
+/// Clone the examples at `indices`
+pub fn select(examples: &[TrainingExample], indices: &[usize]) -> Vec<TrainingExample> {
+    indices.iter().map(|&i| examples[i].clone()).collect()
+}
+
+/// Some examples of a task, validated by the task
+pub struct Subset<'a, T: Task + ?Sized> {
+    task: &'a T,
+    indices: Vec<usize>,
+    examples: Vec<TrainingExample>,
+}
+
+impl<'a, T: Task + ?Sized> Subset<'a, T> {
+    /// View the examples at `indices` of `task`
+    pub fn new(task: &'a T, indices: &[usize]) -> Self {
+        Self {
+            task,
+            examples: select(task.examples(), indices),
+            indices: indices.to_vec(),
+        }
+    }
+
+    /// Indices into the full task, in subset order
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+}
+
+impl<T: Task + ?Sized> Task for Subset<'_, T> {
+    fn examples(&self) -> &[TrainingExample] {
+        &self.examples
+    }
+
+    fn validate_example(&self, index: usize, output: &Array2<f32>) -> Validation {
+        self.task.validate_example(self.indices[index], output)
+    }
+
+    fn input_dim(&self) -> usize {
+        self.task.input_dim()
+    }
+
+    fn output_dim(&self) -> usize {
+        self.task.output_dim()
+    }
+
+    fn difficulty(&self, index: usize) -> Option<usize> {
+        self.task.difficulty(self.indices[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::tasks::CopyTask;
+
+    fn sorted(mut indices: Vec<usize>) -> Vec<usize> {
+        indices.sort_unstable();
+        indices
+    }
+
+    #[test]
+    fn test_split_is_seeded_partition() {
+        let split = split_indices(100, SplitRatios::default(), 7);
+        assert_eq!(
+            (split.train.len(), split.val.len(), split.test.len()),
+            (80, 10, 10)
+        );
+        let all = [split.train.clone(), split.val.clone(), split.test.clone()].concat();
+        assert_eq!(sorted(all), (0..100).collect::<Vec<_>>());
+
+        assert_eq!(split_indices(100, SplitRatios::default(), 7), split);
+        assert_ne!(split_indices(100, SplitRatios::default(), 8), split);
+        // Shuffled, not the first 80
+        assert_ne!(sorted(split.train), (0..80).collect::<Vec<_>>());
+
+        assert!(SplitRatios::new(0.8, 0.3).is_err());
+        assert!(SplitRatios::new(-0.1, 0.3).is_err());
+    }
+
+    #[test]
+    fn test_k_fold_covers_every_example_once() {
+        let folds = k_fold(23, 5, 1).unwrap();
+        assert_eq!(folds.len(), 5);
+        let mut seen: Vec<usize> = folds.iter().flat_map(|f| f.val.clone()).collect();
+        seen.sort_unstable();
+        assert_eq!(seen, (0..23).collect::<Vec<_>>());
+        for fold in &folds {
+            assert!((4..=5).contains(&fold.val.len()));
+            assert_eq!(fold.train.len() + fold.val.len(), 23);
+            assert!(fold.val.iter().all(|i| !fold.train.contains(i)));
+        }
+        assert!(k_fold(3, 5, 0).is_err());
+        assert!(k_fold(10, 1, 0).is_err());
+    }
+
+    #[test]
+    fn test_stratified_splits_balance_keys() {
+        // Half easy (0), half hard (1)
+        let keys: Vec<usize> = (0..40).map(|i| usize::from(i >= 20)).collect();
+        let split = stratified_split_indices(&keys, SplitRatios::new(0.5, 0.25).unwrap(), 3);
+        let hard = |set: &[usize]| set.iter().filter(|&&i| keys[i] == 1).count();
+        assert_eq!(
+            (split.train.len(), split.val.len(), split.test.len()),
+            (20, 10, 10)
+        );
+        assert_eq!(hard(&split.train), 10);
+        assert_eq!(hard(&split.val), 5);
+        assert_eq!(hard(&split.test), 5);
+
+        for fold in stratified_k_fold(&keys, 4, 3).unwrap() {
+            assert_eq!(hard(&fold.val), 5);
+        }
+    }
+
+    #[test]
+    fn test_subset_validates_with_original_indices() {
+        let task = CopyTask::new(6, 3);
+        let subset = Subset::new(&task, &[4, 1]);
+        assert_eq!(subset.num_instances(), 2);
+        assert_eq!(subset.indices(), &[4, 1]);
+        let target = &task.examples()[4].target;
+        assert!(subset.validate_example(0, target).correct);
+        assert!(!subset.validate_example(1, target).correct);
+    }
+}
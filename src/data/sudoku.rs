@@ -0,0 +1,491 @@
+//! Sudoku puzzle generation, solving and encoding
+
+use super::problem::check_len;
+use super::{Task, TrainingExample, Validation};
+use ndarray::Array2;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+
+/// Number of cells in a 9x9 grid
+pub const CELLS: usize = 81;
+/// Input channels per cell: empty plus digits 1-9
+pub const INPUT_CHANNELS: usize = 10;
+/// Target channels per cell: digits 1-9
+pub const OUTPUT_CHANNELS: usize = 9;
+
+/// A 9x9 Sudoku grid; 0 marks an empty cell
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SudokuGrid {
+    pub cells: [[u8; 9]; 9],
+}
+
+impl SudokuGrid {
+    /// Parse an 81-character string of digits; `0` or `.` mark empty cells
+    ///
+    /// Whitespace is ignored so grids can be written one row per line.
+    pub fn parse(text: &str) -> Option<Self> {
+        let digits: Vec<u8> = text
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| match c {
+                '.' => Some(0),
+                _ => c.to_digit(10).map(|d| d as u8),
+            })
+            .collect::<Option<_>>()?;
+
+        if digits.len() != CELLS {
+            return None;
+        }
+
+        let mut grid = Self::default();
+        for (i, digit) in digits.into_iter().enumerate() {
+            grid.cells[i / 9][i % 9] = digit;
+        }
+        Some(grid)
+    }
+
+    /// Number of filled cells
+    pub fn givens(&self) -> usize {
+        self.cells.iter().flatten().filter(|&&d| d != 0).count()
+    }
+
+    /// Bitmask of digits (bits 1-9) that can be placed at a cell
+    fn candidates(&self, row: usize, col: usize) -> u16 {
+        let mut used = 0u16;
+        for i in 0..9 {
+            used |= 1 << self.cells[row][i];
+            used |= 1 << self.cells[i][col];
+        }
+        let (box_row, box_col) = (row / 3 * 3, col / 3 * 3);
+        for r in box_row..box_row + 3 {
+            for c in box_col..box_col + 3 {
+                used |= 1 << self.cells[r][c];
+            }
+        }
+        !used & 0x3FE
+    }
+
+    /// Empty cell with the fewest candidates, or `None` if the grid is full
+    fn most_constrained_cell(&self) -> Option<(usize, usize, u16)> {
+        let mut best: Option<(usize, usize, u16)> = None;
+        for row in 0..9 {
+            for col in 0..9 {
+                if self.cells[row][col] != 0 {
+                    continue;
+                }
+                let candidates = self.candidates(row, col);
+                if best.map_or(true, |(_, _, b)| candidates.count_ones() < b.count_ones()) {
+                    best = Some((row, col, candidates));
+                }
+            }
+        }
+        best
+    }
+
+    /// Check that all filled cells respect the row, column and box constraints
+    pub fn is_consistent(&self) -> bool {
+        (0..9).all(|i| {
+            let row = (0..9).map(|j| self.cells[i][j]);
+            let col = (0..9).map(|j| self.cells[j][i]);
+            let boxed = (0..9).map(|j| self.cells[i / 3 * 3 + j / 3][i % 3 * 3 + j % 3]);
+            no_duplicates(row) && no_duplicates(col) && no_duplicates(boxed)
+        })
+    }
+
+    /// Check that the grid is completely and correctly filled
+    pub fn is_solved(&self) -> bool {
+        self.givens() == CELLS && self.is_consistent()
+    }
+
+    /// Check that every given of `puzzle` appears unchanged in this grid
+    pub fn matches_givens(&self, puzzle: &SudokuGrid) -> bool {
+        self.cells
+            .iter()
+            .flatten()
+            .zip(puzzle.cells.iter().flatten())
+            .all(|(&d, &given)| given == 0 || d == given)
+    }
+
+    /// Solve the puzzle by backtracking
+    pub fn solve(&self) -> Option<SudokuGrid> {
+        if !self.is_consistent() {
+            return None;
+        }
+        let mut grid = *self;
+        let mut solution = None;
+        grid.search(1, &mut 0, &mut solution);
+        solution
+    }
+
+    /// Count solutions, stopping once `limit` have been found
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        if !self.is_consistent() {
+            return 0;
+        }
+        let mut grid = *self;
+        let mut count = 0;
+        grid.search(limit, &mut count, &mut None);
+        count
+    }
+
+    fn search(&mut self, limit: usize, count: &mut usize, first: &mut Option<SudokuGrid>) {
+        let Some((row, col, candidates)) = self.most_constrained_cell() else {
+            *count += 1;
+            first.get_or_insert(*self);
+            return;
+        };
+
+        for digit in 1..=9u8 {
+            if candidates & (1 << digit) == 0 {
+                continue;
+            }
+            self.cells[row][col] = digit;
+            self.search(limit, count, first);
+            self.cells[row][col] = 0;
+            if *count >= limit {
+                return;
+            }
+        }
+    }
+
+    /// Fill the empty cells with a random valid completion
+    fn fill_random(&mut self, rng: &mut impl Rng) -> bool {
+        let Some((row, col, candidates)) = self.most_constrained_cell() else {
+            return true;
+        };
+
+        let mut digits: Vec<u8> = (1..=9).filter(|d| candidates & (1 << d) != 0).collect();
+        digits.shuffle(rng);
+        for digit in digits {
+            self.cells[row][col] = digit;
+            if self.fill_random(rng) {
+                return true;
+            }
+        }
+        self.cells[row][col] = 0;
+        false
+    }
+}
+
+fn no_duplicates(digits: impl Iterator<Item = u8>) -> bool {
+    let mut seen = 0u16;
+    for digit in digits.filter(|&d| d != 0) {
+        if seen & (1 << digit) != 0 {
+            return false;
+        }
+        seen |= 1 << digit;
+    }
+    true
+}
+
+impl fmt::Display for SudokuGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (r, row) in self.cells.iter().enumerate() {
+            if r > 0 && r % 3 == 0 {
+                writeln!(f, "------+-------+------")?;
+            }
+            for (c, &digit) in row.iter().enumerate() {
+                if c > 0 && c % 3 == 0 {
+                    write!(f, "| ")?;
+                }
+                match digit {
+                    0 => write!(f, ". ")?,
+                    d => write!(f, "{} ", d)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Difficulty presets, expressed as the number of givens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SudokuDifficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl SudokuDifficulty {
+    /// Target number of givens for this difficulty
+    pub fn givens(self) -> usize {
+        match self {
+            SudokuDifficulty::Easy => 40,
+            SudokuDifficulty::Medium => 32,
+            SudokuDifficulty::Hard => 26,
+        }
+    }
+}
+
+/// A puzzle together with its unique solution
+#[derive(Debug, Clone, PartialEq)]
+pub struct SudokuPuzzle {
+    pub puzzle: SudokuGrid,
+    pub solution: SudokuGrid,
+}
+
+impl SudokuPuzzle {
+    /// Generate a puzzle with a unique solution and about `givens` filled cells
+    ///
+    /// Cells are removed in random order as long as the solution stays unique,
+    /// so the result may keep more givens than requested when no further cell
+    /// can be removed.
+    pub fn generate(givens: usize, rng: &mut impl Rng) -> Self {
+        let mut solution = SudokuGrid::default();
+        solution.fill_random(rng);
+
+        let mut puzzle = solution;
+        let mut positions: Vec<usize> = (0..CELLS).collect();
+        positions.shuffle(rng);
+
+        let mut remaining = CELLS;
+        for pos in positions {
+            if remaining <= givens {
+                break;
+            }
+            let (row, col) = (pos / 9, pos % 9);
+            let digit = puzzle.cells[row][col];
+            puzzle.cells[row][col] = 0;
+            if puzzle.count_solutions(2) == 1 {
+                remaining -= 1;
+            } else {
+                puzzle.cells[row][col] = digit;
+            }
+        }
+
+        Self { puzzle, solution }
+    }
+
+    /// Encode the puzzle and solution as a training example
+    ///
+    /// Input: one-hot per cell over {empty, 1..9} (81 x 10).
+    /// Target: one-hot per cell over digits 1..9 (81 x 9).
+    pub fn to_training_example(&self) -> TrainingExample {
+        let input = Array2::from_shape_vec((1, CELLS * INPUT_CHANNELS), encode_grid(&self.puzzle))
+            .expect("input size matches encoding");
+
+        let mut target = vec![0.0; CELLS * OUTPUT_CHANNELS];
+        for (i, &digit) in self.solution.cells.iter().flatten().enumerate() {
+            if digit != 0 {
+                target[i * OUTPUT_CHANNELS + digit as usize - 1] = 1.0;
+            }
+        }
+        let target = Array2::from_shape_vec((1, CELLS * OUTPUT_CHANNELS), target)
+            .expect("target size matches encoding");
+
+        TrainingExample::new(input, target)
+    }
+}
+
+/// One-hot encode a grid over {empty, 1..9}
+pub fn encode_grid(grid: &SudokuGrid) -> Vec<f32> {
+    let mut values = vec![0.0; CELLS * INPUT_CHANNELS];
+    for (i, &digit) in grid.cells.iter().flatten().enumerate() {
+        values[i * INPUT_CHANNELS + digit as usize] = 1.0;
+    }
+    values
+}
+
+/// Decode a model output (81 x 9 digit scores) into a grid by per-cell argmax
+pub fn decode_grid(output: &[f32]) -> Option<SudokuGrid> {
+    if output.len() != CELLS * OUTPUT_CHANNELS {
+        return None;
+    }
+
+    let mut grid = SudokuGrid::default();
+    for (i, scores) in output.chunks(OUTPUT_CHANNELS).enumerate() {
+        let digit = scores
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(d, _)| d as u8 + 1)
+            .unwrap_or(0);
+        grid.cells[i / 9][i % 9] = digit;
+    }
+    Some(grid)
+}
+
+/// Sudoku solving task
+pub struct SudokuTask {
+    puzzles: Vec<SudokuPuzzle>,
+    examples: Vec<TrainingExample>,
+}
+
+impl SudokuTask {
+    /// Create a new Sudoku task with about `givens` filled cells per puzzle
+    pub fn new(num_puzzles: usize, givens: usize) -> Self {
+        Self::with_rng(num_puzzles, givens, &mut rand::thread_rng())
+    }
+
+    /// Create a Sudoku task drawing puzzles from `rng`
+    pub fn with_rng<R: Rng>(num_puzzles: usize, givens: usize, rng: &mut R) -> Self {
+        let puzzles: Vec<SudokuPuzzle> = (0..num_puzzles)
+            .map(|_| SudokuPuzzle::generate(givens, rng))
+            .collect();
+        let examples = puzzles.iter().map(|p| p.to_training_example()).collect();
+
+        Self { puzzles, examples }
+    }
+
+    /// Create a new Sudoku task from a difficulty preset
+    pub fn with_difficulty(num_puzzles: usize, difficulty: SudokuDifficulty) -> Self {
+        Self::new(num_puzzles, difficulty.givens())
+    }
+
+    /// Get all puzzles
+    pub fn puzzles(&self) -> &[SudokuPuzzle] {
+        &self.puzzles
+    }
+
+    /// Get all training examples
+    pub fn examples(&self) -> &[TrainingExample] {
+        &self.examples
+    }
+}
+
+impl Task for SudokuTask {
+    fn examples(&self) -> &[TrainingExample] {
+        &self.examples
+    }
+
+    /// Correct for any complete grid that keeps the givens; the score is the
+    /// fraction of cells that match the puzzle's solution
+    fn validate_example(&self, index: usize, output: &Array2<f32>) -> Validation {
+        if let Some(invalid) = check_len(output, CELLS * OUTPUT_CHANNELS) {
+            return invalid;
+        }
+        let values: Vec<f32> = output.iter().cloned().collect();
+        let grid = decode_grid(&values).expect("length was checked");
+        let puzzle = &self.puzzles[index];
+
+        let reason = if !grid.matches_givens(&puzzle.puzzle) {
+            Some("changes a given digit".to_string())
+        } else if !grid.is_solved() {
+            Some("breaks a row, column or box constraint".to_string())
+        } else {
+            None
+        };
+        let matching = grid
+            .cells
+            .iter()
+            .flatten()
+            .zip(puzzle.solution.cells.iter().flatten())
+            .filter(|(a, b)| a == b)
+            .count();
+        Validation::check(matching as f32 / CELLS as f32, reason)
+    }
+
+    fn input_dim(&self) -> usize {
+        CELLS * INPUT_CHANNELS
+    }
+
+    fn output_dim(&self) -> usize {
+        CELLS * OUTPUT_CHANNELS
+    }
+
+    /// Number of empty cells
+    fn difficulty(&self, index: usize) -> Option<usize> {
+        Some(CELLS - self.puzzles[index].puzzle.givens())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const PUZZLE: &str = "
+        530070000
+        600195000
+        098000060
+        800060003
+        400803001
+        700020006
+        060000280
+        000419005
+        000080079";
+
+    #[test]
+    fn test_parse_and_display() {
+        let grid = SudokuGrid::parse(PUZZLE).unwrap();
+        assert_eq!(grid.cells[0][0], 5);
+        assert_eq!(grid.cells[0][2], 0);
+        assert_eq!(grid.givens(), 30);
+        assert!(grid.to_string().contains("5 3 ."));
+        assert!(SudokuGrid::parse("123").is_none());
+    }
+
+    #[test]
+    fn test_solver() {
+        let grid = SudokuGrid::parse(PUZZLE).unwrap();
+        let solution = grid.solve().unwrap();
+        assert!(solution.is_solved());
+        assert!(solution.matches_givens(&grid));
+        assert_eq!(grid.count_solutions(2), 1);
+    }
+
+    #[test]
+    fn test_constraint_checks() {
+        let mut grid = SudokuGrid::parse(PUZZLE).unwrap().solve().unwrap();
+        assert!(grid.is_solved());
+
+        // Swapping two cells in a row keeps the row valid but breaks a column
+        grid.cells[0].swap(0, 1);
+        assert!(!grid.is_solved());
+
+        let mut conflicting = SudokuGrid::default();
+        conflicting.cells[0][0] = 4;
+        conflicting.cells[1][1] = 4;
+        assert!(!conflicting.is_consistent());
+        assert!(conflicting.solve().is_none());
+    }
+
+    #[test]
+    fn test_generator_unique_solution() {
+        let mut rng = StdRng::seed_from_u64(40);
+        let generated = SudokuPuzzle::generate(SudokuDifficulty::Easy.givens(), &mut rng);
+
+        assert!(generated.solution.is_solved());
+        assert!(generated.solution.matches_givens(&generated.puzzle));
+        assert_eq!(generated.puzzle.givens(), 40);
+        assert_eq!(generated.puzzle.count_solutions(2), 1);
+        assert_eq!(generated.puzzle.solve(), Some(generated.solution));
+    }
+
+    #[test]
+    fn test_encoding_round_trip() {
+        let puzzle = SudokuGrid::parse(PUZZLE).unwrap();
+        let generated = SudokuPuzzle {
+            puzzle,
+            solution: puzzle.solve().unwrap(),
+        };
+
+        let example = generated.to_training_example();
+        assert_eq!(example.input.shape(), &[1, CELLS * INPUT_CHANNELS]);
+        assert_eq!(example.target.shape(), &[1, CELLS * OUTPUT_CHANNELS]);
+        assert_eq!(example.input.sum(), CELLS as f32);
+
+        let target: Vec<f32> = example.target.iter().cloned().collect();
+        assert_eq!(decode_grid(&target), Some(generated.solution));
+    }
+
+    #[test]
+    fn test_sudoku_task_problem() {
+        let task = SudokuTask::new(2, 45);
+        assert_eq!(task.examples().len(), 2);
+        assert_eq!(task.input_dim(), 810);
+        assert_eq!(task.output_dim(), 729);
+
+        for (index, example) in task.examples().iter().enumerate() {
+            assert!(task.validate_example(index, &example.target).correct);
+            assert!(
+                !task
+                    .validate_example(index, &Array2::zeros((1, 729)))
+                    .correct
+            );
+        }
+    }
+}
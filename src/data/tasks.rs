@@ -1,6 +1,7 @@
 //! Task implementations for training and evaluation
 
-use super::{Problem, TrainingExample};
+use super::problem::check_len;
+use super::{Task, TrainingExample, Validation};
 use ndarray::Array2;
 use rand::Rng;
 
@@ -16,7 +17,11 @@ pub struct SequenceTask {
 impl SequenceTask {
     /// Create a new sequence task with generated examples
     pub fn new(num_examples: usize, sequence_length: usize) -> Self {
-        let mut rng = rand::thread_rng();
+        Self::with_rng(num_examples, sequence_length, &mut rand::thread_rng())
+    }
+
+    /// Create a sequence task drawing sequences from `rng`
+    pub fn with_rng<R: Rng>(num_examples: usize, sequence_length: usize, rng: &mut R) -> Self {
         let mut examples = Vec::new();
 
         // Input: sequence_length numbers, Output: next number
@@ -66,21 +71,22 @@ impl SequenceTask {
     }
 }
 
-impl Problem for SequenceTask {
-    fn input(&self) -> &Array2<f32> {
-        &self.examples[0].input
-    }
-
-    fn target(&self) -> &Array2<f32> {
-        &self.examples[0].target
+impl Task for SequenceTask {
+    fn examples(&self) -> &[TrainingExample] {
+        &self.examples
     }
 
-    fn validate_solution(&self, output: &Array2<f32>) -> bool {
+    fn validate_example(&self, index: usize, output: &Array2<f32>) -> Validation {
+        if let Some(invalid) = check_len(output, self.output_dim) {
+            return invalid;
+        }
         // Check if output is close to target (within 10% error)
-        let target = self.target();
+        let target = &self.examples[index].target;
         let error = (output[[0, 0]] - target[[0, 0]]).abs();
         let relative_error = error / target[[0, 0]].abs().max(1.0);
-        relative_error < 0.1
+        let reason = (relative_error >= 0.1)
+            .then(|| format!("relative error {:.3} is not below 0.1", relative_error));
+        Validation::check(1.0 - relative_error, reason)
     }
 
     fn input_dim(&self) -> usize {
@@ -102,7 +108,11 @@ pub struct CopyTask {
 impl CopyTask {
     /// Create a new copy task
     pub fn new(num_examples: usize, dim: usize) -> Self {
-        let mut rng = rand::thread_rng();
+        Self::with_rng(num_examples, dim, &mut rand::thread_rng())
+    }
+
+    /// Create a copy task drawing vectors from `rng`
+    pub fn with_rng<R: Rng>(num_examples: usize, dim: usize, rng: &mut R) -> Self {
         let mut examples = Vec::new();
 
         for _ in 0..num_examples {
@@ -134,24 +144,26 @@ impl CopyTask {
     }
 }
 
-impl Problem for CopyTask {
-    fn input(&self) -> &Array2<f32> {
-        &self.examples[0].input
-    }
-
-    fn target(&self) -> &Array2<f32> {
-        &self.examples[0].target
+impl Task for CopyTask {
+    fn examples(&self) -> &[TrainingExample] {
+        &self.examples
     }
 
-    fn validate_solution(&self, output: &Array2<f32>) -> bool {
-        let target = self.target();
-        let mse = output
+    fn validate_example(&self, index: usize, output: &Array2<f32>) -> Validation {
+        if let Some(invalid) = check_len(output, self.dim) {
+            return invalid;
+        }
+        let target = &self.examples[index].target;
+        let errors: Vec<f32> = output
             .iter()
             .zip(target.iter())
-            .map(|(a, b)| (a - b).powi(2))
-            .sum::<f32>()
-            / output.len() as f32;
-        mse < 0.01
+            .map(|(a, b)| (a - b).abs())
+            .collect();
+        let mse = errors.iter().map(|e| e.powi(2)).sum::<f32>() / output.len() as f32;
+        // Partial credit for elements within 0.1 of the target
+        let close = errors.iter().filter(|&&e| e < 0.1).count();
+        let reason = (mse >= 0.01).then(|| format!("mse {:.4} is not below 0.01", mse));
+        Validation::check(close as f32 / self.dim.max(1) as f32, reason)
     }
 
     fn input_dim(&self) -> usize {
@@ -211,6 +223,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_copy_task_validation() {
+        let task = CopyTask::new(3, 4);
+        for (index, example) in task.examples().iter().enumerate() {
+            assert!(task.validate_example(index, &example.target).correct);
+            let wrong = task.validate_example(index, &(&example.target + 0.5));
+            assert!(!wrong.correct);
+            assert_eq!(wrong.score, 0.0);
+        }
+        // Another example's target is not accepted for this one
+        let other = &task.examples()[1].target;
+        assert!(!task.validate_example(0, other).correct);
+    }
+
     #[test]
     fn test_copy_task_split() {
         let task = CopyTask::new(100, 5);
@@ -35,7 +35,7 @@ pub use training::Trainer;
 
 /// Re-export common types
 pub mod prelude {
-    pub use crate::data::Problem;
+    pub use crate::data::{Problem, Task};
     pub use crate::model::{TRMConfig, TRMModel};
     pub use crate::training::Trainer;
     pub use crate::utils::Result;
@@ -1,9 +1,47 @@
 //! CLI entry point for train-trm
 
-use clap::{Parser, Subcommand};
-use train_trm::data::tasks::CopyTask;
-use train_trm::model::{TRMConfig, TRMModel};
-use train_trm::training::{Trainer, TrainingConfig};
+use clap::{Args, Parser, Subcommand};
+use ndarray::{Array2, Axis};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::path::Path;
+use train_trm::data::inference::write_predictions;
+use train_trm::data::maze::{
+    format_mazes, MazeEncoding, MazeMetric, MazeStats, RenderOptions, PATH_THRESHOLD,
+};
+use train_trm::data::registry;
+use train_trm::data::split::select;
+use train_trm::data::{
+    build_task, build_task_with_rng, k_fold, load_inference_data, load_mazes, save_mazes,
+    save_predictions, split_indices, stratified_k_fold, ArcEncoder, BuiltTask, InferenceFormat,
+    Maze, MazeAlgorithm, MazeGenerator, SplitRatios, Subset, Task, TaskSpec,
+};
+use train_trm::model::{ModelSummary, TRMConfig, TRMModel};
+use train_trm::training::metrics::{Metric, TaskAccuracy, TaskScore};
+use train_trm::training::{
+    metric_by_name, summarize_reports, CycleMetrics, EvaluationReport, ModelSettings, RunConfig,
+    Trainer, TrainingConfig,
+};
+use train_trm::utils::image::Image;
+use train_trm::utils::{Result, TRMError};
+
+/// `println!` to stdout, or to stderr when stdout carries data
+macro_rules! note {
+    ($to_stderr:expr) => {
+        if $to_stderr {
+            eprintln!();
+        } else {
+            println!();
+        }
+    };
+    ($to_stderr:expr, $($arg:tt)*) => {
+        if $to_stderr {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
 
 #[derive(Parser)]
 #[command(name = "train-trm")]
@@ -17,7 +55,103 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Train a TRM model
+    ///
+    /// Settings come from `--config` (TOML or JSON) if given; any flag set on
+    /// the command line overrides the file.
     Train {
+        /// Run config file (.toml or .json)
+        #[arg(short, long)]
+        config: Option<String>,
+
+        /// Task to train on, with optional parameters (e.g. `maze:size=11,encoding=onehot`)
+        /// [default: copy]
+        #[arg(long)]
+        task: Option<String>,
+
+        /// Side length of generated mazes (maze task)
+        #[arg(long)]
+        maze_size: Option<usize>,
+
+        /// Directory of ARC JSON task files (arc task)
+        #[arg(long)]
+        arc_dir: Option<String>,
+
+        /// Side length of the padded ARC canvas (arc task)
+        #[arg(long)]
+        arc_size: Option<usize>,
+
+        /// Number of examples to generate [default: 100]
+        #[arg(long)]
+        examples: Option<usize>,
+
+        /// Number of layers [default: 2]
+        #[arg(short, long)]
+        layers: Option<usize>,
+
+        /// Number of outer cycles (H) [default: 3]
+        #[arg(long)]
+        h_cycles: Option<usize>,
+
+        /// Number of inner cycles (L) [default: 4]
+        #[arg(long)]
+        l_cycles: Option<usize>,
+
+        /// Hidden dimension [default: set by the task]
+        #[arg(long)]
+        hidden_dim: Option<usize>,
+
+        /// Latent dimension [default: the hidden dimension]
+        #[arg(long)]
+        latent_dim: Option<usize>,
+
+        /// Learning rate [default: 0.001]
+        #[arg(long)]
+        lr: Option<f32>,
+
+        /// Number of epochs [default: 100]
+        #[arg(short, long)]
+        epochs: Option<usize>,
+
+        /// Batch size [default: 16]
+        #[arg(long)]
+        batch_size: Option<usize>,
+
+        /// Seed for generating examples, initial weights and the train/validation
+        /// split [default: 0]
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Output model path [default: model.trm]
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Where to write the merged run config [default: the model path with a .toml extension]
+        #[arg(long)]
+        save_config: Option<String>,
+    },
+
+    /// Cross-validate: train a fresh model on each fold and report mean and std
+    Cv {
+        /// Task to cross-validate on, with optional parameters
+        #[arg(long, default_value = "copy")]
+        task: String,
+
+        /// Number of examples to generate
+        #[arg(long, default_value_t = 100)]
+        examples: usize,
+
+        /// Number of folds
+        #[arg(short = 'k', long, default_value_t = 5)]
+        folds: usize,
+
+        /// Spread the task's difficulty key evenly across folds
+        #[arg(long)]
+        stratify: bool,
+
+        /// Seed for examples, fold assignment and each fold's initial weights
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+
         /// Number of layers
         #[arg(short, long, default_value_t = 2)]
         layers: usize,
@@ -34,13 +168,13 @@ enum Commands {
         #[arg(long, default_value_t = 0.001)]
         lr: f32,
 
-        /// Number of epochs
+        /// Number of epochs per fold
         #[arg(short, long, default_value_t = 100)]
         epochs: usize,
 
-        /// Output model path
-        #[arg(short, long, default_value = "model.trm")]
-        output: String,
+        /// Comma-separated metrics (mse, mae, accuracy[@tol], exact_match, element_accuracy)
+        #[arg(long, default_value = "mse,accuracy")]
+        metrics: String,
     },
 
     /// Evaluate a trained model
@@ -49,88 +183,331 @@ enum Commands {
         #[arg(short, long)]
         model: String,
 
-        /// Input file path
+        /// Task to evaluate on; defaults to the task recorded in the model
+        /// (missing copy, maze and ARC sizes are inferred from the model)
+        #[arg(long)]
+        task: Option<String>,
+
+        /// Directory of ARC JSON task files (arc task)
+        #[arg(long)]
+        arc_dir: Option<String>,
+
+        /// Run the model on inputs from a file (.json, .jsonl, .csv, .npy or .npz)
+        /// instead of a task; metrics are reported if the file has targets
         #[arg(short, long)]
         input: Option<String>,
+
+        /// Write predictions for --input to this file instead of stdout
+        /// (format from the extension)
+        #[arg(short, long, requires = "input")]
+        output: Option<String>,
+
+        /// Report loss and accuracy of the answer after every outer cycle
+        #[arg(long)]
+        per_cycle: bool,
+
+        /// Comma-separated metrics (mse, mae, accuracy[@tol], exact_match, element_accuracy)
+        #[arg(long, default_value = "mse,accuracy")]
+        metrics: String,
+    },
+
+    /// Show a model's layers, weight statistics, dead ReLU units and FLOPs
+    Inspect {
+        /// Model path
+        model: String,
+
+        /// Examples in the dead-unit probe batch (0 to skip the probe)
+        #[arg(long, default_value_t = 64)]
+        probe: usize,
+
+        /// Task to draw probe inputs from; defaults to the task recorded in
+        /// the model, else uniform random inputs in [0, 1]
+        #[arg(long)]
+        task: Option<String>,
+
+        /// Print the summary as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Generate, solve, render and measure mazes
+    Maze {
+        #[command(subcommand)]
+        command: MazeCommand,
+    },
+
+    /// List the available tasks and their parameters
+    Tasks,
+}
+
+#[derive(Subcommand)]
+enum MazeCommand {
+    /// Generate mazes
+    Generate {
+        #[command(flatten)]
+        mazes: MazeArgs,
+
+        /// Store the BFS solution with each maze
+        #[arg(long)]
+        solve: bool,
+
+        /// Output file (.json, otherwise ASCII); prints ASCII if not given
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Solve mazes with BFS and report their path lengths
+    Solve {
+        #[command(flatten)]
+        mazes: MazeArgs,
+
+        /// Write the solved mazes to this file (.json, otherwise ASCII)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Render mazes with their BFS solution and, optionally, a model's prediction
+    Render {
+        #[command(flatten)]
+        mazes: MazeArgs,
+
+        /// Output file: .svg, .png or .ppm; any other extension gets ASCII.
+        /// Prints ASCII if not given
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Render only the maze at this index (SVG renders the first maze by default)
+        #[arg(long)]
+        index: Option<usize>,
+
+        /// Side of one cell in pixels
+        #[arg(long, default_value_t = 16)]
+        cell_size: usize,
+
+        /// Mazes per row in PNG and PPM montages (default: a square grid)
+        #[arg(long)]
+        columns: Option<usize>,
+
+        /// Trained maze model whose predicted path is drawn next to the solution
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Shade open cells by the model's confidence (PNG and PPM)
+        #[arg(long, requires = "model")]
+        heatmap: bool,
+    },
+
+    /// Difficulty statistics: solution length, dead ends, junctions, turns, ...
+    Stats {
+        #[command(flatten)]
+        mazes: MazeArgs,
+
+        /// Print a row for every maze, not just the summary
+        #[arg(long)]
+        each: bool,
+
+        /// Print the per-maze statistics as JSON
+        #[arg(long)]
+        json: bool,
     },
 }
 
+/// Where `maze` subcommands get their mazes: a file, or freshly generated
+#[derive(Args)]
+struct MazeArgs {
+    /// Maze file (.json or ASCII); generation options are ignored if given
+    #[arg(short, long)]
+    input: Option<String>,
+
+    /// Generation algorithm (backtracker, prim, kruskal, wilson or eller)
+    #[arg(short, long, default_value = "backtracker")]
+    algorithm: MazeAlgorithm,
+
+    /// Side length
+    #[arg(short, long, default_value_t = 7)]
+    size: usize,
+
+    /// Width, if different from size
+    #[arg(long)]
+    width: Option<usize>,
+
+    /// Height, if different from size
+    #[arg(long)]
+    height: Option<usize>,
+
+    /// Fraction of dead ends to remove (0 = perfect maze)
+    #[arg(long, default_value_t = 0.0)]
+    braid: f32,
+
+    /// Number of mazes to generate
+    #[arg(short = 'n', long, default_value_t = 1)]
+    count: usize,
+
+    /// Random seed (random if not given)
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+impl MazeArgs {
+    /// Load the input file, or generate mazes
+    fn mazes(&self) -> Result<Vec<Maze>> {
+        if let Some(path) = &self.input {
+            return load_mazes(path);
+        }
+        let generator = MazeGenerator {
+            braid: self.braid,
+            ..MazeGenerator::new(self.algorithm)
+        };
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let (width, height) = (
+            self.width.unwrap_or(self.size),
+            self.height.unwrap_or(self.size),
+        );
+        (0..self.count)
+            .map(|_| generator.generate(width, height, &mut rng))
+            .collect()
+    }
+}
+
 fn main() {
     let cli = Cli::parse();
 
     match cli.command {
         Commands::Train {
+            config,
+            task,
+            maze_size,
+            arc_dir,
+            arc_size,
+            examples,
             layers,
             h_cycles,
             l_cycles,
+            hidden_dim,
+            latent_dim,
             lr,
             epochs,
+            batch_size,
+            seed,
             output,
+            save_config,
         } => {
             println!("=== Training TRM Model ===\n");
 
+            let mut run = match &config {
+                Some(path) => RunConfig::load(path).unwrap_or_else(|e| {
+                    eprintln!("Error loading config: {}", e);
+                    std::process::exit(1);
+                }),
+                None => RunConfig::default(),
+            };
+
+            // Command-line flags override the config file
+            let model_settings = &mut run.model;
+            override_with(&mut model_settings.l_layers, layers);
+            override_with(&mut model_settings.h_cycles, h_cycles);
+            override_with(&mut model_settings.l_cycles, l_cycles);
+            if hidden_dim.is_some() {
+                model_settings.hidden_dim = hidden_dim;
+            }
+            if latent_dim.is_some() {
+                model_settings.latent_dim = latent_dim;
+            }
+            override_with(&mut run.training.learning_rate, lr);
+            override_with(&mut run.training.epochs, epochs);
+            override_with(&mut run.training.batch_size, batch_size);
+            override_with(&mut run.task, task);
+            override_with(&mut run.examples, examples);
+            override_with(&mut run.seed, seed);
+            override_with(&mut run.output, output);
+
+            // Examples and initial weights both come from the run's seed, so
+            // the written config repeats the run exactly
+            let mut rng = StdRng::seed_from_u64(run.seed);
+
             // Create task
-            println!("Creating copy task with 100 examples (dim=5)...");
-            let task = CopyTask::new(100, 5);
-            let (train_examples, val_examples) = task.split(0.8);
+            let (spec, task_data) = task_spec(&run.task, maze_size, arc_dir, arc_size)
+                .and_then(|spec| create_task(&spec, run.examples, &mut rng))
+                .unwrap_or_else(|e| {
+                    eprintln!("Error creating task: {}", e);
+                    std::process::exit(1);
+                });
+            let examples = task_data.task.examples();
+            let (train_examples, val_examples) = match task_data.training {
+                Some(training) => (training, examples.to_vec()),
+                None => {
+                    let ratios = SplitRatios::new(1.0 - run.val_ratio, run.val_ratio)
+                        .unwrap_or_else(|e| {
+                            eprintln!("Error in config: {}", e);
+                            std::process::exit(1);
+                        });
+                    let split = split_indices(examples.len(), ratios, run.seed);
+                    (select(examples, &split.train), select(examples, &split.val))
+                }
+            };
+            let (train_examples, val_examples) =
+                (train_examples.as_slice(), val_examples.as_slice());
             println!("Training examples: {}", train_examples.len());
             println!("Validation examples: {}\n", val_examples.len());
 
             // Configure model
-            let model_config = TRMConfig {
-                input_dim: 5,
-                output_dim: 5,
-                hidden_dim: 16,
-                latent_dim: 16,
-                l_layers: layers,
-                h_cycles,
-                l_cycles,
-            };
+            let hidden_default = registry::lookup(&spec.name).map_or(64, |entry| entry.hidden_dim);
+            let model_config = run
+                .model
+                .resolve(task_data.task.as_ref(), hidden_default)
+                .unwrap_or_else(|e| {
+                    eprintln!("Error in model config: {}", e);
+                    std::process::exit(1);
+                });
 
-            println!("Model configuration:");
-            println!("  Input/Output dim: 5");
-            println!("  Hidden dim: 16");
-            println!("  Latent dim: 16");
-            println!("  Layers: {}", layers);
-            println!("  H-cycles: {}", h_cycles);
-            println!("  L-cycles: {}\n", l_cycles);
+            // Pin everything that was resolved, so the written config repeats this run
+            run.task = spec.to_string();
+            run.model = ModelSettings::pinned(&model_config);
+            println!("Run configuration:\n{}", run);
+            let config_path = save_config.unwrap_or_else(|| {
+                Path::new(&run.output)
+                    .with_extension("toml")
+                    .to_string_lossy()
+                    .into_owned()
+            });
+            match run.save(&config_path) {
+                Ok(_) => println!("Run config written to: {}\n", config_path),
+                Err(e) => {
+                    eprintln!("Error writing run config: {}", e);
+                    std::process::exit(1);
+                }
+            }
 
-            let model = TRMModel::new(model_config);
+            let mut model = TRMModel::with_rng(model_config, &mut rng);
+            model.task = Some(spec);
             println!("Model created with {} parameters\n", model.num_parameters());
 
-            // Configure training
-            let train_config = TrainingConfig {
-                learning_rate: lr,
-                epochs,
-                batch_size: 16,
-                ..Default::default()
-            };
-
-            println!("Training configuration:");
-            println!("  Learning rate: {}", lr);
-            println!("  Epochs: {}", epochs);
-            println!("  Batch size: 16\n");
-
             // Create trainer and train
-            let mut trainer = Trainer::new(model, train_config);
+            let mut trainer = Trainer::new(model, run.training.clone());
 
-            let initial_train_loss = trainer.evaluate(&train_examples);
-            let initial_val_loss = trainer.evaluate(&val_examples);
+            let initial_train_loss = trainer.evaluate(train_examples);
+            let initial_val_loss = trainer.evaluate(val_examples);
             println!("Initial train loss: {:.6}", initial_train_loss);
             println!("Initial validation loss: {:.6}\n", initial_val_loss);
 
             println!("Training...\n");
-            let metrics = trainer.train(&train_examples);
+            let metrics = trainer.train(train_examples).unwrap_or_else(|e| {
+                eprintln!("Error training model: {}", e);
+                std::process::exit(1);
+            });
 
-            let final_val_loss = trainer.evaluate(&val_examples);
+            let final_val_loss = trainer.evaluate(val_examples);
             println!("\n=== Training Complete ===");
             println!("Initial loss: {:.6}", metrics.initial_loss);
             println!("Final train loss: {:.6}", metrics.final_loss);
             println!("Final validation loss: {:.6}\n", final_val_loss);
 
             // Save the trained model
-            println!("Saving model to: {}", output);
-            match trainer.model().save(&output) {
+            println!("Saving model to: {}", run.output);
+            match trainer.model().save(&run.output) {
                 Ok(_) => println!("Model saved successfully!"),
                 Err(e) => {
                     eprintln!("Error saving model: {}", e);
@@ -138,14 +515,25 @@ fn main() {
                 }
             }
         }
-        Commands::Eval { model, input } => {
-            println!("=== Evaluating Model ===\n");
+        Commands::Eval {
+            model,
+            task,
+            arc_dir,
+            input,
+            output,
+            per_cycle,
+            metrics,
+        } => {
+            // Predictions for --input without --output go to stdout, so keep
+            // everything else off it
+            let to_stderr = input.is_some() && output.is_none();
+            note!(to_stderr, "=== Evaluating Model ===\n");
 
             // Load the model
-            println!("Loading model from: {}", model);
+            note!(to_stderr, "Loading model from: {}", model);
             let mut loaded_model = match TRMModel::load(&model) {
                 Ok(m) => {
-                    println!("Model loaded successfully!\n");
+                    note!(to_stderr, "Model loaded successfully!\n");
                     m
                 }
                 Err(e) => {
@@ -155,58 +543,772 @@ fn main() {
             };
 
             // Display model information
-            println!("Model configuration:");
-            println!("  Input dim: {}", loaded_model.config.input_dim);
-            println!("  Output dim: {}", loaded_model.config.output_dim);
-            println!("  Hidden dim: {}", loaded_model.config.hidden_dim);
-            println!("  Latent dim: {}", loaded_model.config.latent_dim);
-            println!("  Layers: {}", loaded_model.config.l_layers);
-            println!("  H-cycles: {}", loaded_model.config.h_cycles);
-            println!("  L-cycles: {}", loaded_model.config.l_cycles);
-            println!("  Parameters: {}\n", loaded_model.num_parameters());
+            note!(to_stderr, "Model configuration:");
+            note!(to_stderr, "  Input dim: {}", loaded_model.config.input_dim);
+            note!(
+                to_stderr,
+                "  Output dim: {}",
+                loaded_model.config.output_dim
+            );
+            note!(
+                to_stderr,
+                "  Hidden dim: {}",
+                loaded_model.config.hidden_dim
+            );
+            note!(
+                to_stderr,
+                "  Latent dim: {}",
+                loaded_model.config.latent_dim
+            );
+            note!(to_stderr, "  Layers: {}", loaded_model.config.l_layers);
+            note!(to_stderr, "  H-cycles: {}", loaded_model.config.h_cycles);
+            note!(to_stderr, "  L-cycles: {}", loaded_model.config.l_cycles);
+            note!(
+                to_stderr,
+                "  Parameters: {}\n",
+                loaded_model.num_parameters()
+            );
 
             if let Some(input_path) = input {
-                println!("Input file evaluation: {}", input_path);
-                println!("(Custom input evaluation not yet implemented)");
+                if let Err(e) = run_inference(
+                    &mut loaded_model,
+                    &input_path,
+                    output.as_deref(),
+                    &metrics,
+                    per_cycle,
+                ) {
+                    eprintln!("Error evaluating {}: {}", input_path, e);
+                    std::process::exit(1);
+                }
             } else {
-                // Run a simple test with the copy task
-                println!("Running validation test with copy task...");
-                let task = CopyTask::new(20, loaded_model.config.input_dim);
-                let examples = task.examples();
+                let spec = match task {
+                    Some(task) => task_spec(&task, None, arc_dir, None),
+                    None => Ok(loaded_model
+                        .task
+                        .clone()
+                        .unwrap_or_else(|| TaskSpec::new("copy"))),
+                };
+                let (spec, task_data) = spec
+                    .and_then(|spec| infer_size(spec, &loaded_model.config))
+                    .and_then(|spec| {
+                        println!("Running validation test with {} task...", spec.name);
+                        create_task(&spec, 20, &mut StdRng::from_entropy())
+                    })
+                    .unwrap_or_else(|e| {
+                        eprintln!("Error creating task: {}", e);
+                        std::process::exit(1);
+                    });
 
-                let mut total_loss = 0.0;
-                let mut correct = 0;
-
-                for example in examples {
-                    let prediction = loaded_model.forward(&example.input);
-
-                    // Compute MSE loss
-                    let diff = &prediction - &example.target;
-                    let loss = diff.mapv(|x| x * x).sum() / (prediction.len() as f32);
-                    total_loss += loss;
-
-                    // Check if prediction is close to target (within threshold)
-                    let max_diff = diff
-                        .mapv(|x| x.abs())
-                        .iter()
-                        .cloned()
-                        .fold(0.0f32, f32::max);
-                    if max_diff < 0.5 {
-                        correct += 1;
-                    }
+                let task = task_data.task.as_ref();
+                let config = &loaded_model.config;
+                if (task.input_dim(), task.output_dim()) != (config.input_dim, config.output_dim) {
+                    eprintln!(
+                        "Task {} has dims {} -> {}, but the model expects {} -> {}",
+                        spec,
+                        task.input_dim(),
+                        task.output_dim(),
+                        config.input_dim,
+                        config.output_dim
+                    );
+                    std::process::exit(1);
                 }
+                let examples = task.examples();
 
-                let avg_loss = total_loss / examples.len() as f32;
-                let accuracy = (correct as f32 / examples.len() as f32) * 100.0;
+                let mut metrics: Vec<Box<dyn Metric + '_>> = parse_metrics(&metrics);
+                metrics.push(Box::new(TaskAccuracy::new(task)));
+                metrics.push(Box::new(TaskScore::new(task)));
+                let report = EvaluationReport::evaluate(&mut loaded_model, examples, &metrics);
 
-                println!("  Average loss: {:.6}", avg_loss);
+                print!("{}", report);
+                let correct = report.count_correct("task_accuracy");
                 println!(
-                    "  Accuracy: {}/{} ({:.2}%)",
+                    "  Solved: {}/{} ({:.2}%)",
                     correct,
                     examples.len(),
-                    accuracy
+                    (correct as f32 / examples.len() as f32) * 100.0
                 );
+
+                if per_cycle {
+                    let mut trainer = Trainer::new(loaded_model, TrainingConfig::default());
+                    print!("{}", cycle_table(&trainer.evaluate_cycles(examples)));
+                }
             }
         }
+        Commands::Cv {
+            task,
+            examples,
+            folds,
+            stratify,
+            seed,
+            layers,
+            h_cycles,
+            l_cycles,
+            lr,
+            epochs,
+            metrics,
+        } => {
+            println!("=== Cross-Validating TRM Model ===\n");
+
+            let mut rng = StdRng::seed_from_u64(seed);
+            let (spec, task_data) = task_spec(&task, None, None, None)
+                .and_then(|spec| create_task(&spec, examples, &mut rng))
+                .unwrap_or_else(|e| {
+                    eprintln!("Error creating task: {}", e);
+                    std::process::exit(1);
+                });
+            let task = task_data.task.as_ref();
+
+            let folds = if stratify {
+                let keys: Option<Vec<usize>> = (0..task.num_instances())
+                    .map(|i| task.difficulty(i))
+                    .collect();
+                let Some(keys) = keys else {
+                    eprintln!("Task {} has no difficulty key to stratify by", spec.name);
+                    std::process::exit(1);
+                };
+                stratified_k_fold(&keys, folds, seed)
+            } else {
+                k_fold(task.num_instances(), folds, seed)
+            }
+            .unwrap_or_else(|e| {
+                eprintln!("Error splitting examples: {}", e);
+                std::process::exit(1);
+            });
+
+            let train_config = TrainingConfig {
+                learning_rate: lr,
+                epochs,
+                batch_size: 16,
+                ..Default::default()
+            };
+            let mut reports = Vec::with_capacity(folds.len());
+            for (index, fold) in folds.iter().enumerate() {
+                let train_examples = select(task.examples(), &fold.train);
+                let val = Subset::new(task, &fold.val);
+
+                let config = model_config(&spec, task, layers, h_cycles, l_cycles);
+                let model = TRMModel::with_rng(config, &mut rng);
+                let mut trainer = Trainer::new(model, train_config.clone());
+                if let Err(e) = trainer.train(&train_examples) {
+                    eprintln!("Error training model: {}", e);
+                    std::process::exit(1);
+                }
+
+                let mut metrics: Vec<Box<dyn Metric + '_>> = parse_metrics(&metrics);
+                metrics.push(Box::new(TaskAccuracy::new(&val)));
+                metrics.push(Box::new(TaskScore::new(&val)));
+                let report =
+                    EvaluationReport::evaluate(trainer.model_mut(), val.examples(), &metrics);
+                println!(
+                    "Fold {}/{}: {} train, {} validation, task accuracy {:.2}%",
+                    index + 1,
+                    folds.len(),
+                    fold.train.len(),
+                    fold.val.len(),
+                    report.metric("task_accuracy").unwrap_or(0.0) * 100.0
+                );
+                reports.push(report);
+            }
+
+            println!("\nMean and std over {} folds:", reports.len());
+            for summary in summarize_reports(&reports) {
+                println!(
+                    "  {:<18} {:.6} ± {:.6}",
+                    summary.name, summary.mean, summary.std
+                );
+            }
+        }
+        Commands::Inspect {
+            model,
+            probe,
+            task,
+            json,
+        } => {
+            let loaded_model = TRMModel::load(&model).unwrap_or_else(|e| {
+                eprintln!("Error loading model: {}", e);
+                std::process::exit(1);
+            });
+
+            let mut summary = ModelSummary::new(&loaded_model);
+            if probe > 0 {
+                let inputs =
+                    probe_inputs(&loaded_model, task.as_deref(), probe).unwrap_or_else(|e| {
+                        eprintln!("Error creating probe inputs: {}", e);
+                        std::process::exit(1);
+                    });
+                summary = summary.probe(&loaded_model, &inputs);
+            }
+
+            if json {
+                match serde_json::to_string_pretty(&summary) {
+                    Ok(text) => println!("{}", text),
+                    Err(e) => {
+                        eprintln!("Error serializing summary: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            } else {
+                println!("=== Model {} ===\n", model);
+                if let Some(spec) = &loaded_model.task {
+                    println!("Task: {}", spec);
+                }
+                print!("{}", summary);
+            }
+        }
+        Commands::Maze { command } => {
+            if let Err(e) = run_maze(command) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Tasks => {
+            for entry in registry::tasks() {
+                println!("{:<12} {}", entry.name, entry.description);
+                for param in entry.params {
+                    let default = match param.default {
+                        Some("") => String::new(),
+                        Some(default) => format!(" (default: {})", default),
+                        None => " (required)".to_string(),
+                    };
+                    println!("    {:<10} {}{}", param.name, param.help, default);
+                }
+            }
+        }
+    }
+}
+
+/// Probe inputs for `inspect`: examples of the given or recorded task, else
+/// seeded uniform noise
+fn probe_inputs(model: &TRMModel, task: Option<&str>, count: usize) -> Result<Array2<f32>> {
+    let spec = match task {
+        Some(task) => Some(task.parse()?),
+        None => model.task.clone(),
+    };
+    let Some(spec) = spec else {
+        let mut rng = StdRng::seed_from_u64(0);
+        return Ok(Array2::from_shape_fn(
+            (count, model.config.input_dim),
+            |_| rng.gen(),
+        ));
+    };
+
+    let (_, built) = build_task(&infer_size(spec, &model.config)?, count)?;
+    let examples = built.task.examples();
+    let rows: Vec<_> = examples.iter().map(|e| e.input.view()).collect();
+    let inputs =
+        ndarray::concatenate(Axis(0), &rows).map_err(|e| TRMError::InvalidData(e.to_string()))?;
+    if inputs.ncols() != model.config.input_dim {
+        return Err(TRMError::DimensionMismatch {
+            expected: model.config.input_dim,
+            actual: inputs.ncols(),
+            line: None,
+        });
+    }
+    Ok(inputs)
+}
+
+/// Replace `value` if the flag was given
+fn override_with<T>(value: &mut T, flag: Option<T>) {
+    if let Some(flag) = flag {
+        *value = flag;
+    }
+}
+
+/// Run a model on inputs read from a file and write or score its predictions
+fn run_inference(
+    model: &mut TRMModel,
+    input_path: &str,
+    output: Option<&str>,
+    metrics: &str,
+    per_cycle: bool,
+) -> Result<()> {
+    let to_stderr = output.is_none();
+    let data = load_inference_data(input_path)?;
+    data.check_dims(&model.config)?;
+    note!(
+        to_stderr,
+        "Running inference on {} inputs from {}\n",
+        data.len(),
+        input_path
+    );
+
+    let mut predictions = Array2::zeros((data.len(), model.config.output_dim));
+    for (mut row, input) in predictions.rows_mut().into_iter().zip(data.input_rows()) {
+        row.assign(&model.forward(&input).row(0));
+    }
+
+    match output {
+        Some(path) => {
+            save_predictions(path, &predictions)?;
+            println!("Predictions written to: {}", path);
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            write_predictions(&mut stdout, &predictions, InferenceFormat::JsonLines)?;
+        }
+    }
+
+    if let Some(examples) = data.examples() {
+        let metrics = parse_metrics(metrics);
+        note!(to_stderr);
+        let report = EvaluationReport::evaluate(model, examples.as_slice(), &metrics);
+        note!(to_stderr, "{}", report.to_string().trim_end());
+        if per_cycle {
+            let mut trainer = Trainer::new(model.clone(), TrainingConfig::default());
+            note!(
+                to_stderr,
+                "{}",
+                cycle_table(&trainer.evaluate_cycles(&examples)).trim_end()
+            );
+        }
+    } else {
+        note!(
+            to_stderr,
+            "\n(No targets in the input file; skipping metrics)"
+        );
+    }
+    Ok(())
+}
+
+/// Run a `maze` subcommand
+fn run_maze(command: MazeCommand) -> Result<()> {
+    match command {
+        MazeCommand::Generate {
+            mazes,
+            solve,
+            output,
+        } => {
+            let mut mazes = mazes.mazes()?;
+            if solve {
+                for maze in &mut mazes {
+                    maze.solve();
+                }
+            }
+            write_mazes(output.as_deref(), &mazes)
+        }
+        MazeCommand::Solve { mazes, output } => {
+            let mut mazes = mazes.mazes()?;
+            for (index, maze) in mazes.iter_mut().enumerate() {
+                match maze.solve() {
+                    true => println!(
+                        "Maze {} ({}x{}): solution of {} steps",
+                        index,
+                        maze.width,
+                        maze.height,
+                        maze.solution.as_ref().map_or(0, |path| path.len() - 1)
+                    ),
+                    false => println!(
+                        "Maze {} ({}x{}): no solution",
+                        index, maze.width, maze.height
+                    ),
+                }
+            }
+            match output {
+                Some(path) => {
+                    save_mazes(&path, &mazes)?;
+                    println!("Solved mazes written to: {}", path);
+                    Ok(())
+                }
+                None => {
+                    println!();
+                    write_mazes(None, &mazes)
+                }
+            }
+        }
+        MazeCommand::Render {
+            mazes,
+            output,
+            index,
+            cell_size,
+            columns,
+            model,
+            heatmap,
+        } => {
+            let mut mazes = mazes.mazes()?;
+            let svg = output
+                .as_deref()
+                .is_some_and(|path| extension(path) == "svg");
+            if let Some(index) = index.or(svg.then_some(0)) {
+                if index >= mazes.len() {
+                    return Err(TRMError::InvalidData(format!(
+                        "maze index {} is out of range ({} mazes)",
+                        index,
+                        mazes.len()
+                    )));
+                }
+                mazes = vec![mazes.swap_remove(index)];
+            }
+            for maze in &mut mazes {
+                if maze.solution.is_none() {
+                    maze.solve();
+                }
+            }
+
+            let predictions = match &model {
+                Some(path) => {
+                    let mut model = TRMModel::load(path)
+                        .map_err(|e| TRMError::InvalidData(format!("loading {}: {}", path, e)))?;
+                    let predictions = predict_paths(&mut model, &mazes)?;
+                    report_predictions(&mazes, &predictions);
+                    Some(predictions)
+                }
+                None => None,
+            };
+            render_mazes(
+                output.as_deref(),
+                &mazes,
+                predictions.as_deref(),
+                cell_size,
+                columns,
+                heatmap,
+            )
+        }
+        MazeCommand::Stats { mazes, each, json } => {
+            let mazes = mazes.mazes()?;
+            let stats: Vec<Option<MazeStats>> = mazes.iter().map(Maze::stats).collect();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            } else {
+                print_maze_stats(&stats, each);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Save mazes to a file, or print them as ASCII
+fn write_mazes(output: Option<&str>, mazes: &[Maze]) -> Result<()> {
+    match output {
+        Some(path) => {
+            save_mazes(path, mazes)?;
+            println!("{} mazes written to: {}", mazes.len(), path);
+        }
+        None => print!("{}", format_mazes(mazes)),
+    }
+    Ok(())
+}
+
+/// A model's output for one maze
+struct PathPrediction {
+    /// Per-cell on-path scores
+    scores: Vec<f32>,
+}
+
+impl PathPrediction {
+    /// Every cell scoring at least `PATH_THRESHOLD`
+    fn cells(&self, width: usize) -> Vec<(usize, usize)> {
+        self.scores
+            .iter()
+            .enumerate()
+            .filter(|(_, &score)| score >= PATH_THRESHOLD)
+            .map(|(cell, _)| (cell / width, cell % width))
+            .collect()
+    }
+}
+
+/// Run a maze model on every maze
+///
+/// Mazes are encoded as recorded in the model's task spec, else with the
+/// encoding that matches the model's input dim.
+fn predict_paths(model: &mut TRMModel, mazes: &[Maze]) -> Result<Vec<PathPrediction>> {
+    let recorded = match &model.task {
+        Some(spec) if spec.name == "maze" && spec.has("encoding") => {
+            Some(spec.get::<MazeEncoding>("encoding")?)
+        }
+        _ => None,
+    };
+
+    mazes
+        .iter()
+        .map(|maze| {
+            let cells = maze.width * maze.height;
+            let encoding = recorded
+                .or_else(|| MazeEncoding::for_input_dim(model.config.input_dim, cells))
+                .filter(|encoding| encoding.channels() * cells == model.config.input_dim)
+                .ok_or_else(|| {
+                    TRMError::InvalidData(format!(
+                        "model input_dim {} does not fit a {}x{} maze",
+                        model.config.input_dim, maze.width, maze.height
+                    ))
+                })?;
+            if model.config.output_dim != cells {
+                return Err(TRMError::InvalidData(format!(
+                    "model predicts {} cells, but the maze has {}",
+                    model.config.output_dim, cells
+                )));
+            }
+
+            let input = Array2::from_shape_vec((1, model.config.input_dim), encoding.encode(maze))
+                .map_err(|e| TRMError::InvalidData(e.to_string()))?;
+            let scores = model.forward(&input).row(0).to_vec();
+            Ok(PathPrediction { scores })
+        })
+        .collect()
+}
+
+/// Print whether each prediction marks exactly one shortest path
+fn report_predictions(mazes: &[Maze], predictions: &[PathPrediction]) {
+    let mut solved = 0;
+    for (index, (maze, prediction)) in mazes.iter().zip(predictions).enumerate() {
+        let cells = prediction.cells(maze.width);
+        let shortest = maze.shortest_path_length().map_or(0, |steps| steps + 1);
+        let verdict = if maze.is_shortest_path_set(&cells) {
+            solved += 1;
+            format!("shortest path of {} cells", cells.len())
+        } else if maze.decode_path(&prediction.scores).is_some() {
+            format!(
+                "connects start and goal with {} cells marked (shortest path has {})",
+                cells.len(),
+                shortest
+            )
+        } else {
+            "prediction does not connect start and goal".to_string()
+        };
+        eprintln!("Maze {}: {}", index, verdict);
+    }
+    eprintln!("Model solved {}/{} mazes\n", solved, mazes.len());
+}
+
+/// Render mazes to SVG, PNG, PPM or ASCII, by the output extension
+fn render_mazes(
+    output: Option<&str>,
+    mazes: &[Maze],
+    predictions: Option<&[PathPrediction]>,
+    cell_size: usize,
+    columns: Option<usize>,
+    heatmap: bool,
+) -> Result<()> {
+    let predicted: Vec<Option<Vec<(usize, usize)>>> = mazes
+        .iter()
+        .enumerate()
+        .map(|(index, maze)| predictions.map(|p| p[index].cells(maze.width)))
+        .collect();
+
+    let Some(path) = output else {
+        print!("{}", ascii_mazes(mazes, &predicted));
+        return Ok(());
+    };
+    match extension(path).as_str() {
+        "svg" => {
+            let maze = &mazes[0];
+            let svg = match &predicted[0] {
+                Some(cells) => maze.to_svg_with_prediction(cell_size, cells),
+                None => maze.to_svg(cell_size),
+            };
+            std::fs::write(path, svg)?;
+        }
+        "png" | "ppm" => {
+            let images: Vec<Image> = mazes
+                .iter()
+                .enumerate()
+                .map(|(index, maze)| {
+                    maze.to_image(&RenderOptions {
+                        cell_size,
+                        predicted: predicted[index].as_deref(),
+                        heatmap: predictions
+                            .filter(|_| heatmap)
+                            .map(|p| p[index].scores.as_slice()),
+                        ..Default::default()
+                    })
+                })
+                .collect();
+            let columns =
+                columns.unwrap_or_else(|| (images.len() as f64).sqrt().ceil().max(1.0) as usize);
+            Image::montage(&images, columns, cell_size, [255, 255, 255]).save(path)?;
+        }
+        _ => std::fs::write(path, ascii_mazes(mazes, &predicted))?,
+    }
+    println!("Rendered {} mazes to: {}", mazes.len(), path);
+    Ok(())
+}
+
+/// ASCII mazes, each with its predicted path alongside if there is one
+fn ascii_mazes(mazes: &[Maze], predicted: &[Option<Vec<(usize, usize)>>]) -> String {
+    let blocks: Vec<String> = mazes
+        .iter()
+        .zip(predicted)
+        .map(|(maze, predicted)| {
+            let Some(cells) = predicted else {
+                return maze.to_ascii();
+            };
+            let mut model_view = maze.clone();
+            model_view.solution = Some(cells.clone());
+            let mut text = format!("{:<w$}   Model\n", "BFS", w = maze.width);
+            for (left, right) in maze.to_ascii().lines().zip(model_view.to_ascii().lines()) {
+                text.push_str(&format!("{}   {}\n", left, right));
+            }
+            text
+        })
+        .collect();
+    blocks.join("\n")
+}
+
+/// Print a summary of maze statistics, and a row per maze if asked
+fn print_maze_stats(stats: &[Option<MazeStats>], each: bool) {
+    let header = format!(
+        "{:>8} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "Length", "Dead ends", "Junctions", "Branching", "Tortuosity", "Turns"
+    );
+    let row = |s: &MazeStats| {
+        format!(
+            "{:>8} {:>10} {:>10} {:>10.3} {:>10.3} {:>10}",
+            s.solution_length, s.dead_ends, s.junctions, s.branching_factor, s.tortuosity, s.turns
+        )
+    };
+
+    if each {
+        println!("{:<6} {}", "Maze", header);
+        for (index, stats) in stats.iter().enumerate() {
+            match stats {
+                Some(s) => println!("{:<6} {}", index, row(s)),
+                None => println!("{:<6} {:>8}", index, "unsolvable"),
+            }
+        }
+        println!();
+    }
+
+    let solved: Vec<&MazeStats> = stats.iter().flatten().collect();
+    println!("{} mazes, {} solvable", stats.len(), solved.len());
+    if solved.is_empty() {
+        return;
+    }
+    println!(
+        "\n{:<16} {:>10} {:>10} {:>10}",
+        "Metric", "Mean", "Min", "Max"
+    );
+    for metric in MazeMetric::ALL {
+        let values: Vec<f32> = solved.iter().map(|s| metric.value(s)).collect();
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        println!(
+            "{:<16} {:>10.3} {:>10.3} {:>10.3}",
+            metric.name(),
+            mean,
+            min,
+            max
+        );
+    }
+}
+
+/// Lowercase file extension
+fn extension(path: &str) -> String {
+    Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// Parse `--task` and apply the task-specific shortcut flags
+fn task_spec(
+    task: &str,
+    maze_size: Option<usize>,
+    arc_dir: Option<String>,
+    arc_size: Option<usize>,
+) -> Result<TaskSpec> {
+    let mut spec: TaskSpec = task.parse()?;
+    match spec.name.as_str() {
+        "maze" => {
+            if let Some(size) = maze_size {
+                spec = spec.with_param("size", size);
+            }
+        }
+        "arc" => {
+            if let Some(dir) = arc_dir {
+                spec = spec.with_param("dir", dir);
+            }
+            if let Some(size) = arc_size {
+                spec = spec.with_param("size", size);
+            }
+        }
+        _ => {}
+    }
+    Ok(spec)
+}
+
+/// Fill in a missing copy dim, maze side or ARC canvas size from the model's input dim
+fn infer_size(spec: TaskSpec, config: &TRMConfig) -> Result<TaskSpec> {
+    let input_dim = config.input_dim;
+    let (key, size) = match spec.name.as_str() {
+        "copy" if !spec.has("dim") => ("dim", Some(input_dim)),
+        "maze" if !spec.has("size") && !spec.has("encoding") => ("size", square_side(input_dim)),
+        "arc" if !spec.has("size") => ("size", square_side(input_dim / ArcEncoder::CHANNELS)),
+        _ => return Ok(spec),
+    };
+    let size = size.ok_or_else(|| {
+        TRMError::InvalidData(format!(
+            "model input dim {} does not match a square {} grid",
+            input_dim, spec.name
+        ))
+    })?;
+    Ok(spec.with_param(key, size))
+}
+
+/// Model configuration with dims taken from the task
+fn model_config(
+    spec: &TaskSpec,
+    task: &dyn Task,
+    layers: usize,
+    h_cycles: usize,
+    l_cycles: usize,
+) -> TRMConfig {
+    let hidden_dim = registry::lookup(&spec.name).map_or(64, |entry| entry.hidden_dim);
+    TRMConfig {
+        input_dim: task.input_dim(),
+        output_dim: task.output_dim(),
+        hidden_dim,
+        latent_dim: hidden_dim,
+        l_layers: layers,
+        h_cycles,
+        l_cycles,
+    }
+}
+
+/// Resolve a task spec and build `num_examples` examples drawn from `rng`
+fn create_task(
+    spec: &TaskSpec,
+    num_examples: usize,
+    rng: &mut StdRng,
+) -> Result<(TaskSpec, BuiltTask)> {
+    println!("Creating {} task with {} examples...", spec, num_examples);
+    build_task_with_rng(spec, num_examples, rng)
+}
+
+/// Side length of a square grid with `cells` cells
+fn square_side(cells: usize) -> Option<usize> {
+    let side = (cells as f64).sqrt().round() as usize;
+    (side * side == cells).then_some(side)
+}
+
+/// Parse a comma-separated list of metric names, exiting on unknown names
+fn parse_metrics(names: &str) -> Vec<Box<dyn Metric>> {
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            metric_by_name(name).unwrap_or_else(|| {
+                eprintln!("Unknown metric: {}", name);
+                std::process::exit(1);
+            })
+        })
+        .collect()
+}
+
+/// Per-cycle loss and accuracy as a table
+fn cycle_table(cycles: &[CycleMetrics]) -> String {
+    let mut table = format!(
+        "\nPer-cycle diagnostics:\n  {:>5}  {:>12}  {:>9}\n",
+        "Cycle", "Loss", "Accuracy"
+    );
+    for cycle in cycles {
+        table.push_str(&format!(
+            "  {:>5}  {:>12.6}  {:>8.2}%\n",
+            cycle.cycle,
+            cycle.loss,
+            cycle.accuracy * 100.0
+        ));
     }
+    table
 }
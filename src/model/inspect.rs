@@ -0,0 +1,323 @@
+//! Model introspection: architecture, weight statistics, dead units and cost
+//!
+//! [`ModelSummary`] describes a trained model layer by layer. It prints as a
+//! table and serializes to JSON.
+
+use super::{ActivationType, Layer, TRMConfig, TRMModel};
+use ndarray::{Array2, Axis};
+use serde::Serialize;
+use std::fmt;
+
+/// Magnitude below which a weight counts as near zero
+pub const NEAR_ZERO: f32 = 1e-3;
+
+/// Summary statistics of a tensor's values
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TensorStats {
+    pub mean: f32,
+    /// Population standard deviation
+    pub std: f32,
+    pub min: f32,
+    pub max: f32,
+    /// L2 norm
+    pub norm: f32,
+    /// Fraction of values with magnitude below [`NEAR_ZERO`]
+    pub near_zero: f32,
+}
+
+impl TensorStats {
+    /// Statistics of `values`; all zero if there are none
+    pub fn of<'a>(values: impl IntoIterator<Item = &'a f32>) -> Self {
+        let values: Vec<f32> = values.into_iter().copied().collect();
+        if values.is_empty() {
+            return Self {
+                mean: 0.0,
+                std: 0.0,
+                min: 0.0,
+                max: 0.0,
+                norm: 0.0,
+                near_zero: 0.0,
+            };
+        }
+        let count = values.len() as f32;
+        let mean = values.iter().sum::<f32>() / count;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / count;
+        Self {
+            mean,
+            std: variance.sqrt(),
+            min: values.iter().copied().fold(f32::INFINITY, f32::min),
+            max: values.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+            norm: values.iter().map(|v| v * v).sum::<f32>().sqrt(),
+            near_zero: values.iter().filter(|v| v.abs() < NEAR_ZERO).count() as f32 / count,
+        }
+    }
+}
+
+/// One dense layer of the network
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LayerSummary {
+    pub input_dim: usize,
+    pub output_dim: usize,
+    pub activation: ActivationType,
+    pub parameters: usize,
+    pub weights: TensorStats,
+    pub bias: TensorStats,
+    /// ReLU units that never fired on the probe batch; `None` if not probed
+    /// or not a ReLU layer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dead_units: Option<Vec<usize>>,
+}
+
+impl LayerSummary {
+    fn new(layer: &Layer) -> Self {
+        Self {
+            input_dim: layer.weights.ncols(),
+            output_dim: layer.weights.nrows(),
+            activation: layer.activation,
+            parameters: layer.weights.len() + layer.bias.len(),
+            weights: TensorStats::of(&layer.weights),
+            bias: TensorStats::of(&layer.bias),
+            dead_units: None,
+        }
+    }
+
+    /// Floating-point operations for one example: a multiply and an add per
+    /// weight, then the bias and the activation per unit
+    pub fn flops(&self) -> usize {
+        2 * self.input_dim * self.output_dim + 2 * self.output_dim
+    }
+}
+
+/// Floating-point operations of one forward pass, per example
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FlopCount {
+    /// One pass through the shared network
+    pub per_network_pass: usize,
+    /// Network passes per forward pass (`h_cycles * (l_cycles + 1)`)
+    pub network_passes: usize,
+    pub total: usize,
+}
+
+/// Architecture and weight statistics of a model
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelSummary {
+    pub config: TRMConfig,
+    pub parameters: usize,
+    pub layers: Vec<LayerSummary>,
+    pub flops: FlopCount,
+    /// Examples in the dead-unit probe, if one was run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub probe_size: Option<usize>,
+}
+
+impl ModelSummary {
+    /// Summarize a model's architecture and weights
+    pub fn new(model: &TRMModel) -> Self {
+        let layers: Vec<LayerSummary> =
+            model.network.layers.iter().map(LayerSummary::new).collect();
+        let per_network_pass = layers.iter().map(LayerSummary::flops).sum();
+        let network_passes = model.network_passes();
+        Self {
+            config: model.config.clone(),
+            parameters: model.num_parameters(),
+            layers,
+            flops: FlopCount {
+                per_network_pass,
+                network_passes,
+                total: per_network_pass * network_passes,
+            },
+            probe_size: None,
+        }
+    }
+
+    /// Run `inputs` (one example per row) through the model and record the
+    /// ReLU units that stay at zero in every network pass
+    pub fn probe(mut self, model: &TRMModel, inputs: &Array2<f32>) -> Self {
+        let mut fired: Vec<Vec<bool>> = self
+            .layers
+            .iter()
+            .map(|layer| vec![false; layer.output_dim])
+            .collect();
+
+        let mut model = model.clone();
+        for network_input in model.network_inputs(inputs) {
+            let activations = model.network.activations(&network_input);
+            for (fired, output) in fired.iter_mut().zip(&activations) {
+                for (unit, column) in output.axis_iter(Axis(1)).enumerate() {
+                    fired[unit] |= column.iter().any(|&v| v > 0.0);
+                }
+            }
+        }
+
+        for (layer, fired) in self.layers.iter_mut().zip(fired) {
+            if layer.activation == ActivationType::ReLU {
+                layer.dead_units = Some(
+                    fired
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, &fired)| !fired)
+                        .map(|(unit, _)| unit)
+                        .collect(),
+                );
+            }
+        }
+        self.probe_size = Some(inputs.nrows());
+        self
+    }
+}
+
+impl fmt::Display for ModelSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = &self.config;
+        writeln!(
+            f,
+            "Config: input {}, output {}, hidden {}, latent {}, {} layers, H={} L={}",
+            c.input_dim,
+            c.output_dim,
+            c.hidden_dim,
+            c.latent_dim,
+            c.l_layers,
+            c.h_cycles,
+            c.l_cycles
+        )?;
+        writeln!(f, "Parameters: {}\n", self.parameters)?;
+
+        writeln!(
+            f,
+            "{:<6} {:<12} {:<10} {:>8} {:>6}  {:>9} {:>9} {:>9} {:>9} {:>9} {:>7}",
+            "Layer",
+            "Weights",
+            "Activation",
+            "Params",
+            "Tensor",
+            "Mean",
+            "Std",
+            "Min",
+            "Max",
+            "Norm",
+            "~0"
+        )?;
+        for (index, layer) in self.layers.iter().enumerate() {
+            let shape = format!("{}x{}", layer.output_dim, layer.input_dim);
+            let activation = format!("{:?}", layer.activation);
+            for (name, stats) in [("W", &layer.weights), ("b", &layer.bias)] {
+                let (index, shape, activation, params) = if name == "W" {
+                    (
+                        index.to_string(),
+                        shape.as_str(),
+                        activation.as_str(),
+                        layer.parameters.to_string(),
+                    )
+                } else {
+                    (String::new(), "", "", String::new())
+                };
+                writeln!(
+                    f,
+                    "{:<6} {:<12} {:<10} {:>8} {:>6}  {:>9.4} {:>9.4} {:>9.4} {:>9.4} {:>9.4} {:>6.1}%",
+                    index,
+                    shape,
+                    activation,
+                    params,
+                    name,
+                    stats.mean,
+                    stats.std,
+                    stats.min,
+                    stats.max,
+                    stats.norm,
+                    stats.near_zero * 100.0
+                )?;
+            }
+        }
+
+        if let Some(probe_size) = self.probe_size {
+            writeln!(f, "\nDead ReLU units (probe of {} examples):", probe_size)?;
+            for (index, layer) in self.layers.iter().enumerate() {
+                if let Some(dead) = &layer.dead_units {
+                    write!(f, "  Layer {}: {}/{}", index, dead.len(), layer.output_dim)?;
+                    if !dead.is_empty() {
+                        let units: Vec<String> = dead.iter().map(|u| u.to_string()).collect();
+                        write!(f, " [{}]", units.join(", "))?;
+                    }
+                    writeln!(f)?;
+                }
+            }
+        }
+
+        writeln!(
+            f,
+            "\nFLOPs per example: {} per network pass x {} passes = {}",
+            self.flops.per_network_pass, self.flops.network_passes, self.flops.total
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{array, Array1};
+
+    fn small_model() -> TRMModel {
+        TRMModel::new(TRMConfig {
+            l_layers: 2,
+            h_cycles: 2,
+            l_cycles: 3,
+            hidden_dim: 8,
+            latent_dim: 8,
+            input_dim: 4,
+            output_dim: 4,
+        })
+    }
+
+    #[test]
+    fn test_tensor_stats() {
+        let stats = TensorStats::of(&array![3.0, -4.0, 0.0, 0.0005]);
+        assert!((stats.mean - (-0.249875)).abs() < 1e-6);
+        assert_eq!((stats.min, stats.max), (-4.0, 3.0));
+        assert!((stats.norm - 5.0).abs() < 1e-4);
+        assert_eq!(stats.near_zero, 0.5);
+        assert_eq!(TensorStats::of(&Array1::<f32>::zeros(0)).norm, 0.0);
+    }
+
+    #[test]
+    fn test_summary_shapes_and_flops() {
+        let model = small_model();
+        let summary = ModelSummary::new(&model);
+        assert_eq!(summary.layers.len(), 3);
+        // First layer takes the padded think input [x, y, z]
+        assert_eq!(summary.layers[0].input_dim, 16);
+        assert_eq!(summary.layers[2].activation, ActivationType::Tanh);
+        let parameters: usize = summary.layers.iter().map(|l| l.parameters).sum();
+        assert_eq!(parameters, model.num_parameters());
+
+        let per_pass = (2 * 16 * 8 + 16) + (2 * 8 * 8 + 16) + (2 * 8 * 8 + 16);
+        assert_eq!(summary.flops.per_network_pass, per_pass);
+        assert_eq!(summary.flops.network_passes, 8);
+        assert_eq!(summary.flops.total, per_pass * 8);
+    }
+
+    #[test]
+    fn test_probe_finds_dead_units() {
+        let mut model = small_model();
+        // Unit 3 of the first layer can never fire
+        model.network.layers[0].weights.row_mut(3).fill(0.0);
+        model.network.layers[0].bias[3] = -1.0;
+
+        let probe = Array2::from_elem((5, 4), 1.0);
+        let summary = ModelSummary::new(&model).probe(&model, &probe);
+        assert_eq!(summary.probe_size, Some(5));
+        assert!(summary.layers[0].dead_units.as_ref().unwrap().contains(&3));
+        assert!(summary.layers[2].dead_units.is_none());
+        assert!(summary
+            .to_string()
+            .contains("Dead ReLU units (probe of 5 examples)"));
+    }
+
+    #[test]
+    fn test_json_output() {
+        let model = small_model();
+        let json = serde_json::to_value(ModelSummary::new(&model)).unwrap();
+        assert_eq!(json["layers"][0]["activation"], "ReLU");
+        assert_eq!(json["flops"]["network_passes"], 8);
+        assert!(json.get("probe_size").is_none());
+    }
+}
@@ -1,7 +1,9 @@
 //! TRM model and neural network components
 
+mod inspect;
 mod network;
 mod trm;
 
+pub use inspect::{FlopCount, LayerSummary, ModelSummary, TensorStats, NEAR_ZERO};
 pub use network::{ActivationType, Layer, Network};
 pub use trm::{TRMConfig, TRMModel};
@@ -3,6 +3,7 @@
 use ndarray::{Array1, Array2};
 use ndarray_rand::rand_distr::Uniform;
 use ndarray_rand::RandomExt;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 /// Activation function type
@@ -59,9 +60,20 @@ pub struct Layer {
 impl Layer {
     /// Create a new layer with random initialization
     pub fn new(input_dim: usize, output_dim: usize, activation: ActivationType) -> Self {
+        Self::with_rng(input_dim, output_dim, activation, &mut rand::thread_rng())
+    }
+
+    /// Create a new layer with weights drawn from `rng`
+    pub fn with_rng<R: Rng>(
+        input_dim: usize,
+        output_dim: usize,
+        activation: ActivationType,
+        rng: &mut R,
+    ) -> Self {
         // Xavier/Glorot initialization
         let scale = (2.0 / (input_dim + output_dim) as f32).sqrt();
-        let weights = Array2::random((output_dim, input_dim), Uniform::new(-scale, scale));
+        let weights =
+            Array2::random_using((output_dim, input_dim), Uniform::new(-scale, scale), rng);
         let bias = Array1::zeros(output_dim);
 
         Self {
@@ -152,6 +164,17 @@ impl Network {
         x
     }
 
+    /// Output of every layer for `input`, without caching anything for backprop
+    pub fn activations(&self, input: &Array2<f32>) -> Vec<Array2<f32>> {
+        let mut outputs: Vec<Array2<f32>> = Vec::with_capacity(self.layers.len());
+        for layer in &self.layers {
+            let x = outputs.last().unwrap_or(input);
+            let linear = x.dot(&layer.weights.t()) + &layer.bias;
+            outputs.push(layer.activation.apply(&linear));
+        }
+        outputs
+    }
+
     /// Backward pass and update weights
     pub fn backward_and_update(&mut self, grad_output: &Array2<f32>, learning_rate: f32) {
         let mut grad = grad_output.clone();
@@ -267,6 +290,11 @@ mod tests {
         let output = network.forward(&input);
 
         assert_eq!(output.shape(), &[1, 2]);
+
+        let activations = network.activations(&input);
+        assert_eq!(activations.len(), 2);
+        assert_eq!(activations[0].shape(), &[1, 4]);
+        assert_abs_diff_eq!(activations[1], output, epsilon = 1e-6);
     }
 
     #[test]
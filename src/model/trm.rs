@@ -1,7 +1,11 @@
 //! Tiny Recursive Model implementation
 
 use super::network::{ActivationType, Layer, Network};
-use ndarray::{Array2, Axis};
+use crate::data::TaskSpec;
+use crate::utils::npy;
+use crate::utils::TRMError;
+use ndarray::{Array1, Array2, Axis, Ix1};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Read, Write};
@@ -47,11 +51,21 @@ pub struct TRMModel {
     pub config: TRMConfig,
     /// Network for think and act operations
     pub network: Network,
+    /// Task the model was trained on, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<TaskSpec>,
 }
 
 impl TRMModel {
     /// Create a new TRM model
     pub fn new(config: TRMConfig) -> Self {
+        Self::with_rng(config, &mut rand::thread_rng())
+    }
+
+    /// Create a new TRM model with weights drawn from `rng`
+    ///
+    /// A seeded `rng` gives the same initial weights every time.
+    pub fn with_rng<R: Rng>(config: TRMConfig, rng: &mut R) -> Self {
         // Build network architecture
         // For think: concat(x, y, z) -> latent_dim
         // For act: concat(y, z) -> output_dim
@@ -65,64 +79,45 @@ impl TRMModel {
         let mut layers = Vec::new();
 
         // First layer
-        layers.push(Layer::new(
+        layers.push(Layer::with_rng(
             max_input_dim,
             config.hidden_dim,
             ActivationType::ReLU,
+            rng,
         ));
 
         // Hidden layers
         for _ in 1..config.l_layers {
-            layers.push(Layer::new(
+            layers.push(Layer::with_rng(
                 config.hidden_dim,
                 config.hidden_dim,
                 ActivationType::ReLU,
+                rng,
             ));
         }
 
         // Output layer for think (latent_dim)
         // We'll use this for both think and act by adapting the final layer
-        layers.push(Layer::new(
+        layers.push(Layer::with_rng(
             config.hidden_dim,
             config.latent_dim.max(config.output_dim),
             ActivationType::Tanh,
+            rng,
         ));
 
         let network = Network::new(layers);
 
-        Self { config, network }
+        Self {
+            config,
+            network,
+            task: None,
+        }
     }
 
     /// Think step: update latent state z given input x, current answer y, and previous z
     fn think(&mut self, x: &Array2<f32>, y: &Array2<f32>, z: &Array2<f32>) -> Array2<f32> {
-        // Concatenate [x, y, z] and pad to network input size
-        let batch_size = x.shape()[0];
-        let think_input_dim =
-            self.config.input_dim + self.config.output_dim + self.config.latent_dim;
-        let act_input_dim = self.config.output_dim + self.config.latent_dim;
-        let max_input_dim = think_input_dim.max(act_input_dim);
-
-        let mut input = Array2::zeros((batch_size, max_input_dim));
-
-        // Fill in the values
-        for i in 0..batch_size {
-            let mut offset = 0;
-            for j in 0..x.shape()[1] {
-                input[[i, offset]] = x[[i, j]];
-                offset += 1;
-            }
-            for j in 0..y.shape()[1] {
-                input[[i, offset]] = y[[i, j]];
-                offset += 1;
-            }
-            for j in 0..z.shape()[1] {
-                input[[i, offset]] = z[[i, j]];
-                offset += 1;
-            }
-            // Remaining elements are already zero (padding)
-        }
-
-        // Pass through network and extract latent_dim output
+        // Pass [x, y, z] through the network and extract latent_dim output
+        let input = self.network_input(&[x, y, z]);
         let output = self.network.forward(&input);
         output
             .slice_axis(Axis(1), ndarray::Slice::from(0..self.config.latent_dim))
@@ -131,42 +126,80 @@ impl TRMModel {
 
     /// Act step: update answer y given current y and latent state z
     fn act(&mut self, y: &Array2<f32>, z: &Array2<f32>) -> Array2<f32> {
-        // Concatenate [y, z] and pad to network input size
-        let batch_size = y.shape()[0];
+        // Pass [y, z] through the network and extract output_dim
+        let input = self.network_input(&[y, z]);
+        let output = self.network.forward(&input);
+        output
+            .slice_axis(Axis(1), ndarray::Slice::from(0..self.config.output_dim))
+            .to_owned()
+    }
+
+    /// Concatenate `parts` column-wise and zero-pad to the network input size
+    fn network_input(&self, parts: &[&Array2<f32>]) -> Array2<f32> {
+        let batch_size = parts.first().map_or(0, |part| part.nrows());
+        let mut input = Array2::zeros((batch_size, self.network_input_dim()));
+        let mut offset = 0;
+        for part in parts {
+            let width = part.ncols();
+            input
+                .slice_mut(ndarray::s![.., offset..offset + width])
+                .assign(part);
+            offset += width;
+        }
+        // Remaining columns stay zero (padding)
+        input
+    }
+
+    /// Width of the shared network's input: the larger of the think and act inputs
+    pub fn network_input_dim(&self) -> usize {
         let think_input_dim =
             self.config.input_dim + self.config.output_dim + self.config.latent_dim;
         let act_input_dim = self.config.output_dim + self.config.latent_dim;
-        let max_input_dim = think_input_dim.max(act_input_dim);
+        think_input_dim.max(act_input_dim)
+    }
 
-        let mut input = Array2::zeros((batch_size, max_input_dim));
+    /// Number of network passes in one forward pass: `l_cycles` thinks and
+    /// one act per outer cycle
+    pub fn network_passes(&self) -> usize {
+        self.config.h_cycles * (self.config.l_cycles + 1)
+    }
 
-        for i in 0..batch_size {
-            let mut offset = 0;
-            for j in 0..y.shape()[1] {
-                input[[i, offset]] = y[[i, j]];
-                offset += 1;
-            }
-            for j in 0..z.shape()[1] {
-                input[[i, offset]] = z[[i, j]];
-                offset += 1;
+    /// The padded network input of every pass in a forward pass, in order
+    pub fn network_inputs(&mut self, x: &Array2<f32>) -> Vec<Array2<f32>> {
+        let batch_size = x.shape()[0];
+        let mut z = Array2::zeros((batch_size, self.config.latent_dim));
+        let mut y = Array2::zeros((batch_size, self.config.output_dim));
+        let mut inputs = Vec::with_capacity(self.network_passes());
+
+        for _ in 0..self.config.h_cycles {
+            for _ in 0..self.config.l_cycles {
+                inputs.push(self.network_input(&[x, &y, &z]));
+                z = self.think(x, &y, &z);
             }
-            // Remaining elements are already zero (padding)
+            inputs.push(self.network_input(&[&y, &z]));
+            y = self.act(&y, &z);
         }
-
-        // Pass through network and extract output_dim
-        let output = self.network.forward(&input);
-        output
-            .slice_axis(Axis(1), ndarray::Slice::from(0..self.config.output_dim))
-            .to_owned()
+        inputs
     }
 
     /// Forward pass: recursive reasoning
     pub fn forward(&mut self, x: &Array2<f32>) -> Array2<f32> {
         let batch_size = x.shape()[0];
+        self.forward_cycles(x)
+            .pop()
+            .unwrap_or_else(|| Array2::zeros((batch_size, self.config.output_dim)))
+    }
+
+    /// Forward pass that keeps the intermediate answer after every outer cycle
+    ///
+    /// Returns one `y` per H-cycle; the last entry equals the output of `forward`.
+    pub fn forward_cycles(&mut self, x: &Array2<f32>) -> Vec<Array2<f32>> {
+        let batch_size = x.shape()[0];
 
         // Initialize latent state z and answer y
         let mut z = Array2::zeros((batch_size, self.config.latent_dim));
         let mut y = Array2::zeros((batch_size, self.config.output_dim));
+        let mut answers = Vec::with_capacity(self.config.h_cycles);
 
         // Recursive improvement loop
         for _ in 0..self.config.h_cycles {
@@ -177,9 +210,10 @@ impl TRMModel {
 
             // Act phase: update answer
             y = self.act(&y, &z);
+            answers.push(y.clone());
         }
 
-        y
+        answers
     }
 
     /// Backward pass and weight update
@@ -228,6 +262,64 @@ impl TRMModel {
         let model: TRMModel = serde_json::from_str(&json)?;
         Ok(model)
     }
+
+    /// Save layer weights and biases to a NumPy `.npz` archive
+    ///
+    /// Arrays are named `layer_<i>_weights` (output_dim x input_dim) and
+    /// `layer_<i>_bias` (output_dim). The configuration is not stored.
+    pub fn save_weights_npz<P: AsRef<Path>>(&self, path: P) -> crate::utils::Result<()> {
+        let names: Vec<(String, String)> = (0..self.network.layers.len())
+            .map(|i| (format!("layer_{}_weights", i), format!("layer_{}_bias", i)))
+            .collect();
+
+        let mut arrays = Vec::new();
+        for (layer, (weights_name, bias_name)) in self.network.layers.iter().zip(&names) {
+            arrays.push((weights_name.as_str(), layer.weights.view().into_dyn()));
+            arrays.push((bias_name.as_str(), layer.bias.view().into_dyn()));
+        }
+        npy::save_npz(path, &arrays)
+    }
+
+    /// Load layer weights and biases from a NumPy `.npz` archive
+    ///
+    /// The archive must match this model's architecture (see `save_weights_npz`).
+    pub fn load_weights_npz<P: AsRef<Path>>(&mut self, path: P) -> crate::utils::Result<()> {
+        let mut arrays = npy::load_npz(path)?;
+        let mut take = |name: String| {
+            arrays
+                .remove(&name)
+                .ok_or_else(|| TRMError::InvalidData(format!("npz is missing `{}`", name)))
+        };
+
+        let mut loaded = Vec::with_capacity(self.network.layers.len());
+        for (i, layer) in self.network.layers.iter().enumerate() {
+            let weights = npy::to_array2(take(format!("layer_{}_weights", i))?)?;
+            let bias: Array1<f32> = take(format!("layer_{}_bias", i))?
+                .into_dimensionality::<Ix1>()
+                .map_err(|e| TRMError::InvalidData(e.to_string()))?;
+
+            for (actual, expected) in [
+                (weights.nrows(), layer.weights.nrows()),
+                (weights.ncols(), layer.weights.ncols()),
+                (bias.len(), layer.bias.len()),
+            ] {
+                if actual != expected {
+                    return Err(TRMError::DimensionMismatch {
+                        expected,
+                        actual,
+                        line: None,
+                    });
+                }
+            }
+            loaded.push((weights, bias));
+        }
+
+        for (layer, (weights, bias)) in self.network.layers.iter_mut().zip(loaded) {
+            layer.weights = weights;
+            layer.bias = bias;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -318,6 +410,92 @@ mod tests {
         assert_eq!(output.shape(), &[batch_size, 2]);
     }
 
+    #[test]
+    fn test_forward_cycles_matches_forward() {
+        let config = TRMConfig {
+            h_cycles: 3,
+            ..TRMConfig::default()
+        };
+        let mut model = TRMModel::new(config);
+
+        let input = Array2::ones((2, 10));
+        let answers = model.forward_cycles(&input);
+        let output = model.forward(&input);
+
+        assert_eq!(answers.len(), 3);
+        for answer in &answers {
+            assert_eq!(answer.shape(), &[2, 10]);
+        }
+        assert_abs_diff_eq!(answers[2], output, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_network_inputs_follow_the_recursion() {
+        let config = TRMConfig {
+            h_cycles: 2,
+            l_cycles: 3,
+            ..TRMConfig::default()
+        };
+        let mut model = TRMModel::new(config);
+        let input = Array2::from_elem((2, 10), 0.5);
+        let inputs = model.network_inputs(&input);
+
+        assert_eq!(inputs.len(), model.network_passes());
+        assert_eq!(inputs.len(), 8);
+        assert!(inputs.iter().all(|i| i.shape() == [2, 84]));
+        // The first think sees x followed by zero y and z
+        assert_abs_diff_eq!(inputs[0].row(0)[0], 0.5);
+        assert_abs_diff_eq!(inputs[0].row(0)[10], 0.0);
+        // Act passes carry only [y, z] and leave x's columns as padding
+        assert_abs_diff_eq!(inputs[3].row(0)[74], 0.0);
+    }
+
+    #[test]
+    fn test_weights_npz_round_trip() {
+        let config = TRMConfig {
+            input_dim: 3,
+            output_dim: 2,
+            hidden_dim: 4,
+            latent_dim: 4,
+            l_layers: 2,
+            h_cycles: 1,
+            l_cycles: 1,
+        };
+        let model = TRMModel::new(config.clone());
+        let path = std::env::temp_dir().join("train-trm-weights-test.npz");
+        model.save_weights_npz(&path).unwrap();
+
+        let mut restored = TRMModel::new(config);
+        restored.load_weights_npz(&path).unwrap();
+        for (a, b) in model.network.layers.iter().zip(&restored.network.layers) {
+            assert_eq!(a.weights, b.weights);
+            assert_eq!(a.bias, b.bias);
+        }
+
+        // A different architecture is rejected
+        let mut other = TRMModel::new(TRMConfig::default());
+        assert!(other.load_weights_npz(&path).is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_task_recorded_in_model_file() {
+        let mut model = TRMModel::new(TRMConfig::default());
+        let json = serde_json::to_string(&model).unwrap();
+        assert!(!json.contains("\"task\""));
+
+        model.task = Some("maze:size=9".parse().unwrap());
+        let json = serde_json::to_string(&model).unwrap();
+        let loaded: TRMModel = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.task, model.task);
+
+        // Files written before tasks were recorded still load
+        let legacy: TRMModel =
+            serde_json::from_str(&json.replace(",\"task\":", ",\"_\":")).unwrap();
+        assert!(legacy.task.is_none());
+    }
+
     #[test]
     fn test_num_parameters() {
         let config = TRMConfig::default();
@@ -328,4 +506,18 @@ mod tests {
         // Should have parameters from all layers
         assert!(num_params > 0);
     }
+
+    #[test]
+    fn test_seeded_initialization() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let init =
+            |seed| TRMModel::with_rng(TRMConfig::default(), &mut StdRng::seed_from_u64(seed));
+        let (a, b, c) = (init(1), init(1), init(2));
+        for (x, y) in a.network.layers.iter().zip(&b.network.layers) {
+            assert_eq!(x.weights, y.weights);
+        }
+        assert_ne!(a.network.layers[0].weights, c.network.layers[0].weights);
+    }
 }
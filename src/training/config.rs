@@ -0,0 +1,318 @@
+//! Config files for training runs
+//!
+//! A [`RunConfig`] covers everything `train` needs: the task, the model and
+//! the training settings. Files may be TOML or JSON and may leave out any
+//! value; missing values keep their defaults, at every level of nesting.
+//!
+//! ```toml
+//! task = "maze:size=9,encoding=onehot"
+//! examples = 200
+//!
+//! [model]
+//! hidden_dim = 128
+//! h_cycles = 4
+//!
+//! [training]
+//! learning_rate = 0.005
+//! batch_size = 32
+//! ```
+
+use super::TrainingConfig;
+use crate::data::Task;
+use crate::model::TRMConfig;
+use crate::utils::{Result, TRMError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// File format of a run config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// Infer the format from a file extension
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let extension = path
+            .as_ref()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        match extension.as_str() {
+            "toml" => Ok(ConfigFormat::Toml),
+            "json" => Ok(ConfigFormat::Json),
+            _ => Err(TRMError::InvalidData(format!(
+                "unknown config extension: {:?} (expected .toml or .json)",
+                extension
+            ))),
+        }
+    }
+}
+
+/// Model settings; unset dims are taken from the task
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelSettings {
+    pub l_layers: usize,
+    pub h_cycles: usize,
+    pub l_cycles: usize,
+    /// Defaults to the task's registered hidden size
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hidden_dim: Option<usize>,
+    /// Defaults to `hidden_dim`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latent_dim: Option<usize>,
+    /// Must match the task if set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_dim: Option<usize>,
+    /// Must match the task if set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_dim: Option<usize>,
+}
+
+impl Default for ModelSettings {
+    fn default() -> Self {
+        let config = TRMConfig::default();
+        Self {
+            l_layers: config.l_layers,
+            h_cycles: config.h_cycles,
+            l_cycles: config.l_cycles,
+            hidden_dim: None,
+            latent_dim: None,
+            input_dim: None,
+            output_dim: None,
+        }
+    }
+}
+
+impl ModelSettings {
+    /// The model configuration for `task`
+    pub fn resolve(&self, task: &dyn Task, default_hidden_dim: usize) -> Result<TRMConfig> {
+        for (name, configured, actual) in [
+            ("input_dim", self.input_dim, task.input_dim()),
+            ("output_dim", self.output_dim, task.output_dim()),
+        ] {
+            if configured.is_some_and(|dim| dim != actual) {
+                return Err(TRMError::InvalidData(format!(
+                    "config sets model {} to {}, but the task has {}",
+                    name,
+                    configured.unwrap_or_default(),
+                    actual
+                )));
+            }
+        }
+        let hidden_dim = self.hidden_dim.unwrap_or(default_hidden_dim);
+        Ok(TRMConfig {
+            l_layers: self.l_layers,
+            h_cycles: self.h_cycles,
+            l_cycles: self.l_cycles,
+            hidden_dim,
+            latent_dim: self.latent_dim.unwrap_or(hidden_dim),
+            input_dim: task.input_dim(),
+            output_dim: task.output_dim(),
+        })
+    }
+
+    /// Settings that pin every value of `config`
+    pub fn pinned(config: &TRMConfig) -> Self {
+        Self {
+            l_layers: config.l_layers,
+            h_cycles: config.h_cycles,
+            l_cycles: config.l_cycles,
+            hidden_dim: Some(config.hidden_dim),
+            latent_dim: Some(config.latent_dim),
+            input_dim: Some(config.input_dim),
+            output_dim: Some(config.output_dim),
+        }
+    }
+}
+
+/// Everything needed to repeat a training run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RunConfig {
+    /// Task spec, e.g. `maze:size=9,encoding=onehot`
+    pub task: String,
+    /// Number of examples to generate
+    pub examples: usize,
+    /// Seed for generating examples, initializing weights and splitting
+    /// off the validation set
+    pub seed: u64,
+    /// Fraction of generated examples held out for validation
+    pub val_ratio: f32,
+    /// Path of the trained model
+    pub output: String,
+    pub model: ModelSettings,
+    pub training: TrainingConfig,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        Self {
+            task: "copy".to_string(),
+            examples: 100,
+            seed: 0,
+            val_ratio: 0.2,
+            output: "model.trm".to_string(),
+            model: ModelSettings::default(),
+            training: TrainingConfig {
+                batch_size: 16,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl RunConfig {
+    /// Load a config file, inferring the format from the extension
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let format = ConfigFormat::from_path(&path)?;
+        let text = fs::read_to_string(&path)?;
+        Self::parse(&text, format)
+            .map_err(|e| TRMError::InvalidData(format!("{}: {}", path.as_ref().display(), e)))
+    }
+
+    /// Parse a config, filling in defaults for missing values
+    pub fn parse(text: &str, format: ConfigFormat) -> Result<Self> {
+        let overrides: Value = match format {
+            ConfigFormat::Toml => toml::from_str(text).map_err(invalid)?,
+            ConfigFormat::Json => serde_json::from_str(text)?,
+        };
+        let mut merged = serde_json::to_value(Self::default())?;
+        merge(&mut merged, overrides);
+        Ok(serde_json::from_value(merged)?)
+    }
+
+    /// Write the config, inferring the format from the extension
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let text = self.to_string_as(ConfigFormat::from_path(&path)?)?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// The config as TOML or pretty-printed JSON
+    pub fn to_string_as(&self, format: ConfigFormat) -> Result<String> {
+        match format {
+            ConfigFormat::Toml => {
+                // Go through JSON text so f32 values print as written (0.001,
+                // not 0.0010000000474974513)
+                let value: toml::Value = serde_json::from_str(&serde_json::to_string(self)?)?;
+                toml::to_string(&value).map_err(invalid)
+            }
+            ConfigFormat::Json => Ok(serde_json::to_string_pretty(self)? + "\n"),
+        }
+    }
+}
+
+/// Prints as TOML
+impl fmt::Display for RunConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = self
+            .to_string_as(ConfigFormat::Toml)
+            .map_err(|_| fmt::Error)?;
+        f.write_str(&text)
+    }
+}
+
+/// Overwrite `base` with `overrides`, merging tables key by key
+fn merge(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, value) => *base = value,
+    }
+}
+
+fn invalid(error: impl fmt::Display) -> TRMError {
+    TRMError::InvalidData(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::tasks::CopyTask;
+    use crate::data::{AugmentationConfig, AugmentationMode, Symmetry};
+    use crate::training::LossType;
+
+    #[test]
+    fn test_partial_toml_keeps_defaults() {
+        let config = RunConfig::parse(
+            "task = \"maze:size=9\"\n[model]\nhidden_dim = 128\n[training]\nepochs = 5\n",
+            ConfigFormat::Toml,
+        )
+        .unwrap();
+        assert_eq!(config.task, "maze:size=9");
+        assert_eq!(config.model.hidden_dim, Some(128));
+        assert_eq!(config.model.h_cycles, 3);
+        assert_eq!(config.training.epochs, 5);
+        // Untouched nested values keep the run defaults, not the library ones
+        assert_eq!(config.training.batch_size, 16);
+        assert_eq!(config.examples, 100);
+    }
+
+    #[test]
+    fn test_round_trip_both_formats() {
+        let mut config = RunConfig::default();
+        config.model.latent_dim = Some(32);
+        config.training.loss_type = LossType::MAE;
+        config.training.augmentation = Some(AugmentationConfig {
+            symmetry: Symmetry::maze(7, 7),
+            mode: AugmentationMode::Offline { copies: 2 },
+            seed: 3,
+        });
+        for format in [ConfigFormat::Toml, ConfigFormat::Json] {
+            let text = config.to_string_as(format).unwrap();
+            assert_eq!(RunConfig::parse(&text, format).unwrap(), config);
+        }
+        let toml = config.to_string();
+        assert!(toml.contains("loss_type = \"mae\""));
+        assert!(toml.contains("learning_rate = 0.001\n"));
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        assert!(RunConfig::parse("epochs = 5", ConfigFormat::Toml).is_err());
+        assert!(RunConfig::parse("[model]\nlayers = 2", ConfigFormat::Toml).is_err());
+        assert!(RunConfig::parse("{\"training\": {\"lr\": 1}}", ConfigFormat::Json).is_err());
+        assert!(ConfigFormat::from_path("run.yaml").is_err());
+    }
+
+    #[test]
+    fn test_model_settings_resolve() {
+        let task = CopyTask::new(2, 5);
+        let settings = ModelSettings {
+            hidden_dim: Some(16),
+            ..Default::default()
+        };
+        let config = settings.resolve(&task, 64).unwrap();
+        assert_eq!((config.input_dim, config.output_dim), (5, 5));
+        assert_eq!((config.hidden_dim, config.latent_dim), (16, 16));
+        assert_eq!(
+            ModelSettings::pinned(&config)
+                .resolve(&task, 64)
+                .unwrap()
+                .hidden_dim,
+            16
+        );
+
+        let wrong = ModelSettings {
+            input_dim: Some(4),
+            ..Default::default()
+        };
+        assert!(wrong.resolve(&task, 64).is_err());
+    }
+}
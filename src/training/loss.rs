@@ -1,9 +1,11 @@
 //! Loss functions for training
 
 use ndarray::Array2;
+use serde::{Deserialize, Serialize};
 
 /// Loss function types
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum LossType {
     /// Mean Squared Error
     MSE,
@@ -0,0 +1,465 @@
+//! Evaluation metrics and reports
+
+use crate::data::{Dataset, Task};
+use crate::model::TRMModel;
+use ndarray::Array2;
+use std::fmt;
+
+/// Maximum absolute per-element error for a prediction to count as correct
+pub const ACCURACY_TOLERANCE: f32 = 0.5;
+
+/// A per-example evaluation metric
+///
+/// Reports average the per-example scores over the whole evaluation set.
+pub trait Metric {
+    /// Name used to identify the metric in reports
+    fn name(&self) -> String;
+
+    /// Score the prediction for the example at `index`
+    fn score(&self, index: usize, prediction: &Array2<f32>, target: &Array2<f32>) -> f32;
+}
+
+/// Mean squared error
+pub struct MeanSquaredError;
+
+impl Metric for MeanSquaredError {
+    fn name(&self) -> String {
+        "mse".to_string()
+    }
+
+    fn score(&self, _index: usize, prediction: &Array2<f32>, target: &Array2<f32>) -> f32 {
+        let diff = prediction - target;
+        diff.mapv(|x| x * x).sum() / prediction.len().max(1) as f32
+    }
+}
+
+/// Mean absolute error
+pub struct MeanAbsoluteError;
+
+impl Metric for MeanAbsoluteError {
+    fn name(&self) -> String {
+        "mae".to_string()
+    }
+
+    fn score(&self, _index: usize, prediction: &Array2<f32>, target: &Array2<f32>) -> f32 {
+        let diff = prediction - target;
+        diff.mapv(|x| x.abs()).sum() / prediction.len().max(1) as f32
+    }
+}
+
+/// An example is correct when every element is within `tolerance` of the target
+pub struct ToleranceAccuracy {
+    pub tolerance: f32,
+}
+
+impl Default for ToleranceAccuracy {
+    fn default() -> Self {
+        Self {
+            tolerance: ACCURACY_TOLERANCE,
+        }
+    }
+}
+
+impl Metric for ToleranceAccuracy {
+    fn name(&self) -> String {
+        format!("accuracy@{}", self.tolerance)
+    }
+
+    fn score(&self, _index: usize, prediction: &Array2<f32>, target: &Array2<f32>) -> f32 {
+        bool_score(within_tolerance(prediction, target, self.tolerance))
+    }
+}
+
+/// An example is correct when the rounded prediction equals the rounded target
+pub struct ExactMatch;
+
+impl Metric for ExactMatch {
+    fn name(&self) -> String {
+        "exact_match".to_string()
+    }
+
+    fn score(&self, _index: usize, prediction: &Array2<f32>, target: &Array2<f32>) -> f32 {
+        bool_score(
+            prediction.shape() == target.shape()
+                && prediction
+                    .iter()
+                    .zip(target.iter())
+                    .all(|(p, t)| p.round() == t.round()),
+        )
+    }
+}
+
+/// Fraction of elements whose rounded prediction equals the rounded target
+pub struct ElementAccuracy;
+
+impl Metric for ElementAccuracy {
+    fn name(&self) -> String {
+        "element_accuracy".to_string()
+    }
+
+    fn score(&self, _index: usize, prediction: &Array2<f32>, target: &Array2<f32>) -> f32 {
+        let matching = prediction
+            .iter()
+            .zip(target.iter())
+            .filter(|(p, t)| p.round() == t.round())
+            .count();
+        matching as f32 / target.len().max(1) as f32
+    }
+}
+
+/// Task-defined correctness through `Task::validate_example`
+///
+/// The evaluated examples must be in the same order as the task's examples.
+pub struct TaskAccuracy<'a, T: Task + ?Sized> {
+    task: &'a T,
+}
+
+impl<'a, T: Task + ?Sized> TaskAccuracy<'a, T> {
+    /// Create a metric that delegates to the given task
+    pub fn new(task: &'a T) -> Self {
+        Self { task }
+    }
+}
+
+impl<T: Task + ?Sized> Metric for TaskAccuracy<'_, T> {
+    fn name(&self) -> String {
+        "task_accuracy".to_string()
+    }
+
+    fn score(&self, index: usize, prediction: &Array2<f32>, _target: &Array2<f32>) -> f32 {
+        bool_score(self.task.validate_example(index, prediction).correct)
+    }
+}
+
+/// Task-defined partial credit through `Validation::score`
+pub struct TaskScore<'a, T: Task + ?Sized> {
+    task: &'a T,
+}
+
+impl<'a, T: Task + ?Sized> TaskScore<'a, T> {
+    /// Create a metric that delegates to the given task
+    pub fn new(task: &'a T) -> Self {
+        Self { task }
+    }
+}
+
+impl<T: Task + ?Sized> Metric for TaskScore<'_, T> {
+    fn name(&self) -> String {
+        "task_score".to_string()
+    }
+
+    fn score(&self, index: usize, prediction: &Array2<f32>, _target: &Array2<f32>) -> f32 {
+        self.task.validate_example(index, prediction).score
+    }
+}
+
+/// Look up a built-in metric by name
+///
+/// Accepts `mse`, `mae`, `accuracy` (optionally `accuracy@<tolerance>`),
+/// `exact_match` and `element_accuracy`.
+pub fn metric_by_name(name: &str) -> Option<Box<dyn Metric>> {
+    match name {
+        "mse" => Some(Box::new(MeanSquaredError)),
+        "mae" => Some(Box::new(MeanAbsoluteError)),
+        "accuracy" => Some(Box::new(ToleranceAccuracy::default())),
+        "exact_match" => Some(Box::new(ExactMatch)),
+        "element_accuracy" => Some(Box::new(ElementAccuracy)),
+        _ => {
+            let tolerance = name.strip_prefix("accuracy@")?.parse().ok()?;
+            Some(Box::new(ToleranceAccuracy { tolerance }))
+        }
+    }
+}
+
+/// Metrics used when the caller does not ask for specific ones
+pub fn default_metrics() -> Vec<Box<dyn Metric>> {
+    vec![
+        Box::new(MeanSquaredError),
+        Box::new(ToleranceAccuracy::default()),
+    ]
+}
+
+/// Check whether every element of the prediction is within `tolerance` of the target
+///
+/// Predictions shaped differently from the target never match.
+pub fn within_tolerance(prediction: &Array2<f32>, target: &Array2<f32>, tolerance: f32) -> bool {
+    prediction.shape() == target.shape()
+        && prediction
+            .iter()
+            .zip(target.iter())
+            .all(|(p, t)| (p - t).abs() < tolerance)
+}
+
+fn bool_score(correct: bool) -> f32 {
+    if correct {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// Averaged value of one metric
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricResult {
+    pub name: String,
+    pub value: f32,
+}
+
+/// Prediction and per-metric scores for one example
+#[derive(Debug, Clone)]
+pub struct ExampleEvaluation {
+    pub prediction: Array2<f32>,
+    /// Scores in the same order as `EvaluationReport::metrics`
+    pub scores: Vec<f32>,
+}
+
+/// Result of evaluating a model on a set of examples
+#[derive(Debug, Clone, Default)]
+pub struct EvaluationReport {
+    /// Average of each metric over all examples
+    pub metrics: Vec<MetricResult>,
+    /// Per-example predictions and scores
+    pub examples: Vec<ExampleEvaluation>,
+}
+
+impl EvaluationReport {
+    /// Evaluate a model on examples with the given metrics
+    pub fn evaluate<D: Dataset + ?Sized>(
+        model: &mut TRMModel,
+        examples: &D,
+        metrics: &[Box<dyn Metric + '_>],
+    ) -> Self {
+        let mut totals = vec![0.0; metrics.len()];
+        let mut evaluations = Vec::with_capacity(examples.len());
+
+        for (index, example) in examples.iter().enumerate() {
+            let prediction = model.forward(&example.input);
+            let scores: Vec<f32> = metrics
+                .iter()
+                .map(|metric| metric.score(index, &prediction, &example.target))
+                .collect();
+
+            for (total, score) in totals.iter_mut().zip(&scores) {
+                *total += score;
+            }
+            evaluations.push(ExampleEvaluation { prediction, scores });
+        }
+
+        let count = examples.len().max(1) as f32;
+        let metrics = metrics
+            .iter()
+            .zip(totals)
+            .map(|(metric, total)| MetricResult {
+                name: metric.name(),
+                value: total / count,
+            })
+            .collect();
+
+        Self {
+            metrics,
+            examples: evaluations,
+        }
+    }
+
+    /// Number of evaluated examples
+    pub fn num_examples(&self) -> usize {
+        self.examples.len()
+    }
+
+    /// Average value of the named metric
+    pub fn metric(&self, name: &str) -> Option<f32> {
+        self.metrics
+            .iter()
+            .find(|m| m.name == name)
+            .map(|m| m.value)
+    }
+
+    /// Score of the named metric for a single example
+    pub fn example_score(&self, index: usize, name: &str) -> Option<f32> {
+        let position = self.metrics.iter().position(|m| m.name == name)?;
+        self.examples.get(index).map(|e| e.scores[position])
+    }
+
+    /// Number of examples scoring 1.0 on the named metric
+    pub fn count_correct(&self, name: &str) -> usize {
+        (0..self.examples.len())
+            .filter(|&i| self.example_score(i, name) == Some(1.0))
+            .count()
+    }
+}
+
+/// Mean and standard deviation of one metric over several reports
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSummary {
+    pub name: String,
+    pub mean: f32,
+    /// Population standard deviation
+    pub std: f32,
+}
+
+/// Summarize metrics over reports, such as one per cross-validation fold
+///
+/// Metrics are taken from the first report; reports missing one are skipped
+/// for that metric.
+pub fn summarize_reports(reports: &[EvaluationReport]) -> Vec<MetricSummary> {
+    let Some(first) = reports.first() else {
+        return Vec::new();
+    };
+    first
+        .metrics
+        .iter()
+        .map(|metric| {
+            let values: Vec<f32> = reports
+                .iter()
+                .filter_map(|report| report.metric(&metric.name))
+                .collect();
+            let count = values.len() as f32;
+            let mean = values.iter().sum::<f32>() / count;
+            let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / count;
+            MetricSummary {
+                name: metric.name.clone(),
+                mean,
+                std: variance.sqrt(),
+            }
+        })
+        .collect()
+}
+
+impl fmt::Display for EvaluationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for metric in &self.metrics {
+            writeln!(f, "  {:<18} {:.6}", metric.name, metric.value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::tasks::CopyTask;
+    use crate::model::TRMConfig;
+    use approx::assert_abs_diff_eq;
+    use ndarray::array;
+
+    #[test]
+    fn test_mse_and_mae() {
+        let prediction = array![[1.0, 3.0]];
+        let target = array![[2.0, 1.0]];
+        assert_abs_diff_eq!(
+            MeanSquaredError.score(0, &prediction, &target),
+            2.5,
+            epsilon = 1e-6
+        );
+        assert_abs_diff_eq!(
+            MeanAbsoluteError.score(0, &prediction, &target),
+            1.5,
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn test_tolerance_accuracy() {
+        let metric = ToleranceAccuracy { tolerance: 0.5 };
+        let target = array![[0.0, 1.0]];
+        assert_eq!(metric.score(0, &array![[0.4, 0.6]], &target), 1.0);
+        assert_eq!(metric.score(0, &array![[0.6, 1.0]], &target), 0.0);
+        // A prediction shaped differently from the target is never correct
+        assert_eq!(metric.score(0, &array![[0.0]], &target), 0.0);
+        assert_eq!(metric.score(0, &array![[0.0, 1.0, 0.0]], &target), 0.0);
+    }
+
+    #[test]
+    fn test_exact_and_element_accuracy() {
+        let target = array![[0.0, 1.0, 2.0, 3.0]];
+        let prediction = array![[0.2, 0.9, 2.4, 1.0]];
+        assert_eq!(ExactMatch.score(0, &prediction, &target), 0.0);
+        assert_eq!(
+            ExactMatch.score(0, &array![[0.1, 1.2, 1.8, 3.0]], &target),
+            1.0
+        );
+        assert_abs_diff_eq!(
+            ElementAccuracy.score(0, &prediction, &target),
+            0.75,
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn test_task_accuracy_and_score() {
+        let task = CopyTask::new(3, 4);
+        let accuracy = TaskAccuracy::new(&task);
+        let score = TaskScore::new(&task);
+        let example = &task.examples()[1];
+        assert_eq!(accuracy.score(1, &example.target, &example.target), 1.0);
+        assert_eq!(
+            accuracy.score(1, &(&example.target + 1.0), &example.target),
+            0.0
+        );
+
+        // Two of four elements within tolerance earn half credit
+        let mut half = example.target.clone();
+        half[[0, 0]] += 1.0;
+        half[[0, 1]] += 1.0;
+        assert_eq!(accuracy.score(1, &half, &example.target), 0.0);
+        assert_eq!(score.score(1, &half, &example.target), 0.5);
+    }
+
+    #[test]
+    fn test_summarize_reports() {
+        let report = |mse: f32, accuracy: f32| EvaluationReport {
+            metrics: vec![
+                MetricResult {
+                    name: "mse".to_string(),
+                    value: mse,
+                },
+                MetricResult {
+                    name: "accuracy".to_string(),
+                    value: accuracy,
+                },
+            ],
+            examples: Vec::new(),
+        };
+        let summary = summarize_reports(&[report(1.0, 0.5), report(3.0, 0.5)]);
+        assert_eq!(summary.len(), 2);
+        assert_eq!((summary[0].mean, summary[0].std), (2.0, 1.0));
+        assert_eq!((summary[1].mean, summary[1].std), (0.5, 0.0));
+        assert!(summarize_reports(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_metric_by_name() {
+        for name in ["mse", "mae", "accuracy", "exact_match", "element_accuracy"] {
+            assert!(metric_by_name(name).is_some(), "missing metric {}", name);
+        }
+        assert_eq!(
+            metric_by_name("accuracy@0.1").map(|m| m.name()),
+            Some("accuracy@0.1".to_string())
+        );
+        assert!(metric_by_name("unknown").is_none());
+    }
+
+    #[test]
+    fn test_evaluation_report() {
+        let config = TRMConfig {
+            input_dim: 3,
+            output_dim: 3,
+            hidden_dim: 8,
+            latent_dim: 8,
+            l_layers: 2,
+            h_cycles: 1,
+            l_cycles: 1,
+        };
+        let mut model = TRMModel::new(config);
+        let task = CopyTask::new(4, 3);
+
+        let report = EvaluationReport::evaluate(&mut model, task.examples(), &default_metrics());
+
+        assert_eq!(report.num_examples(), 4);
+        assert_eq!(report.metrics.len(), 2);
+        assert!(report.metric("mse").unwrap() >= 0.0);
+        assert!(report.count_correct("accuracy@0.5") <= 4);
+        assert!(report.metric("missing").is_none());
+        assert!(report.to_string().contains("mse"));
+    }
+}
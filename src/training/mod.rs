@@ -1,13 +1,28 @@
 //! Training infrastructure
 
+pub mod config;
 pub mod loss;
+pub mod metrics;
 
-use crate::data::TrainingExample;
+use crate::data::{
+    augment_dataset, AugmentationConfig, AugmentationMode, AugmentedDataset, Dataset, Symmetry,
+};
 use crate::model::TRMModel;
+use crate::utils::{Result, TRMError};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+pub use config::{ConfigFormat, ModelSettings, RunConfig};
 pub use loss::{compute_loss, mse_gradient, LossType};
+pub use metrics::{
+    default_metrics, metric_by_name, summarize_reports, within_tolerance, EvaluationReport, Metric,
+    MetricSummary, ACCURACY_TOLERANCE,
+};
 
 /// Training configuration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct TrainingConfig {
     /// Learning rate
     pub learning_rate: f32,
@@ -17,6 +32,9 @@ pub struct TrainingConfig {
     pub batch_size: usize,
     /// Loss function type
     pub loss_type: LossType,
+    /// Symmetry augmentation of the training examples, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub augmentation: Option<AugmentationConfig>,
 }
 
 impl Default for TrainingConfig {
@@ -26,6 +44,7 @@ impl Default for TrainingConfig {
             epochs: 100,
             batch_size: 32,
             loss_type: LossType::MSE,
+            augmentation: None,
         }
     }
 }
@@ -39,6 +58,20 @@ pub struct TrainingMetrics {
     pub initial_loss: f32,
     /// Final loss
     pub final_loss: f32,
+    /// Loss and accuracy of the intermediate answer after each outer cycle,
+    /// measured on the training examples once training has finished
+    pub cycle_metrics: Vec<CycleMetrics>,
+}
+
+/// Quality of the intermediate answer `y` after one outer (H) cycle
+#[derive(Debug, Clone, PartialEq)]
+pub struct CycleMetrics {
+    /// Outer cycle index (1-based)
+    pub cycle: usize,
+    /// Average loss of the answer after this cycle
+    pub loss: f32,
+    /// Fraction of examples whose answer is within `ACCURACY_TOLERANCE`
+    pub accuracy: f32,
 }
 
 /// Trainer for TRM models
@@ -55,39 +88,72 @@ impl Trainer {
 
     /// Run training loop
     ///
+    /// Fails before training if an example does not fit the configured
+    /// augmentation symmetry.
+    ///
     /// Note: This is a simplified training demonstration for MVP.
     /// Full gradient-based training would require automatic differentiation.
-    pub fn train(&mut self, examples: &[TrainingExample]) -> TrainingMetrics {
+    pub fn train<D: Dataset + ?Sized>(&mut self, examples: &D) -> Result<TrainingMetrics> {
+        if let Some(augmentation) = &self.config.augmentation {
+            check_symmetry(examples, &augmentation.symmetry)?;
+        }
         let mut losses = Vec::new();
 
         // Compute initial loss
         let initial_loss = self.evaluate(examples);
         losses.push(initial_loss);
 
-        // Training loop (simplified for MVP)
-        for epoch in 0..self.config.epochs {
-            let epoch_loss = self.train_epoch(examples);
-            losses.push(epoch_loss);
-
-            if epoch % 10 == 0 {
-                println!("Epoch {}: loss = {:.6}", epoch, epoch_loss);
+        // Training loop (simplified for MVP), on augmented data if configured
+        match self.config.augmentation {
+            None => self.train_epochs(examples, &mut losses),
+            Some(AugmentationConfig {
+                symmetry,
+                mode: AugmentationMode::Offline { copies },
+                seed,
+            }) => {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let expanded = augment_dataset(examples, &symmetry, copies, &mut rng);
+                self.train_epochs(&expanded, &mut losses);
+            }
+            Some(AugmentationConfig {
+                symmetry,
+                mode: AugmentationMode::OnTheFly,
+                seed,
+            }) => {
+                let augmented = AugmentedDataset::new(examples, symmetry, seed);
+                self.train_epochs(&augmented, &mut losses);
             }
         }
 
         let final_loss = *losses.last().unwrap_or(&initial_loss);
+        let cycle_metrics = self.evaluate_cycles(examples);
 
-        TrainingMetrics {
+        Ok(TrainingMetrics {
             losses,
             initial_loss,
             final_loss,
+            cycle_metrics,
+        })
+    }
+
+    /// Run all epochs, appending each epoch's loss
+    fn train_epochs<D: Dataset + ?Sized>(&mut self, examples: &D, losses: &mut Vec<f32>) {
+        for epoch in 0..self.config.epochs {
+            examples.start_epoch(epoch);
+            let epoch_loss = self.train_epoch(examples);
+            losses.push(epoch_loss);
+
+            if epoch % 10 == 0 {
+                println!("Epoch {}: loss = {:.6}", epoch, epoch_loss);
+            }
         }
     }
 
     /// Train for one epoch
-    fn train_epoch(&mut self, examples: &[TrainingExample]) -> f32 {
+    fn train_epoch<D: Dataset + ?Sized>(&mut self, examples: &D) -> f32 {
         let mut total_loss = 0.0;
 
-        for example in examples {
+        for example in examples.iter() {
             // Forward pass
             let prediction = self.model.forward(&example.input);
 
@@ -103,20 +169,57 @@ impl Trainer {
                 .backward_and_update(&grad_output, self.config.learning_rate);
         }
 
-        total_loss / examples.len() as f32
+        total_loss / examples.len().max(1) as f32
     }
 
     /// Evaluate model on examples
-    pub fn evaluate(&mut self, examples: &[TrainingExample]) -> f32 {
+    pub fn evaluate<D: Dataset + ?Sized>(&mut self, examples: &D) -> f32 {
         let mut total_loss = 0.0;
 
-        for example in examples {
+        for example in examples.iter() {
             let prediction = self.model.forward(&example.input);
             let loss = compute_loss(&prediction, &example.target, self.config.loss_type);
             total_loss += loss;
         }
 
-        total_loss / examples.len() as f32
+        total_loss / examples.len().max(1) as f32
+    }
+
+    /// Evaluate model on examples with the given metrics
+    pub fn evaluate_report<D: Dataset + ?Sized>(
+        &mut self,
+        examples: &D,
+        metrics: &[Box<dyn Metric + '_>],
+    ) -> EvaluationReport {
+        EvaluationReport::evaluate(&mut self.model, examples, metrics)
+    }
+
+    /// Evaluate the intermediate answer after every outer cycle
+    ///
+    /// Shows whether additional recursion actually improves the answer.
+    pub fn evaluate_cycles<D: Dataset + ?Sized>(&mut self, examples: &D) -> Vec<CycleMetrics> {
+        let h_cycles = self.model.config.h_cycles;
+        let mut total_loss = vec![0.0; h_cycles];
+        let mut correct = vec![0usize; h_cycles];
+
+        for example in examples.iter() {
+            let answers = self.model.forward_cycles(&example.input);
+            for (cycle, answer) in answers.iter().enumerate() {
+                total_loss[cycle] += compute_loss(answer, &example.target, self.config.loss_type);
+                if within_tolerance(answer, &example.target, ACCURACY_TOLERANCE) {
+                    correct[cycle] += 1;
+                }
+            }
+        }
+
+        let count = examples.len().max(1) as f32;
+        (0..h_cycles)
+            .map(|cycle| CycleMetrics {
+                cycle: cycle + 1,
+                loss: total_loss[cycle] / count,
+                accuracy: correct[cycle] as f32 / count,
+            })
+            .collect()
     }
 
     /// Get reference to the model
@@ -130,6 +233,24 @@ impl Trainer {
     }
 }
 
+/// Check that every example has the size `symmetry` transforms
+fn check_symmetry<D: Dataset + ?Sized>(examples: &D, symmetry: &Symmetry) -> Result<()> {
+    let mismatch = examples
+        .iter()
+        .enumerate()
+        .find(|(_, example)| !symmetry.matches(example));
+    match mismatch {
+        Some((index, example)) => Err(TRMError::InvalidData(format!(
+            "augmentation symmetry {:?} does not fit example {} of size {}/{}",
+            symmetry,
+            index,
+            example.input.len(),
+            example.target.len()
+        ))),
+        None => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,4 +302,128 @@ mod tests {
         let loss = trainer.evaluate(examples);
         assert!(loss >= 0.0);
     }
+
+    #[test]
+    fn test_train_on_generated_dataset() {
+        use crate::data::{generated_mazes, MazeGenerator};
+
+        let model_config = TRMConfig {
+            input_dim: 25,
+            output_dim: 25,
+            hidden_dim: 8,
+            latent_dim: 8,
+            l_layers: 1,
+            h_cycles: 1,
+            l_cycles: 1,
+        };
+        let train_config = TrainingConfig {
+            epochs: 3,
+            ..TrainingConfig::default()
+        };
+        let mut trainer = Trainer::new(TRMModel::new(model_config), train_config);
+
+        let mazes = generated_mazes(4, 5, 5, MazeGenerator::default(), 7).unwrap();
+        let metrics = trainer.train(&mazes).unwrap();
+
+        assert_eq!(metrics.losses.len(), 4);
+        // The trainer advanced the dataset to the last epoch
+        assert_eq!(mazes.epoch(), 2);
+    }
+
+    #[test]
+    fn test_train_with_augmentation() {
+        use crate::data::{MazeTask, Symmetry};
+
+        let model_config = TRMConfig {
+            input_dim: 25,
+            output_dim: 25,
+            hidden_dim: 8,
+            latent_dim: 8,
+            l_layers: 1,
+            h_cycles: 1,
+            l_cycles: 1,
+        };
+        let task = MazeTask::new(3, 5, 5).unwrap();
+
+        for mode in [
+            AugmentationMode::Offline { copies: 2 },
+            AugmentationMode::OnTheFly,
+        ] {
+            let train_config = TrainingConfig {
+                epochs: 2,
+                augmentation: Some(AugmentationConfig {
+                    symmetry: Symmetry::maze(5, 5),
+                    mode,
+                    seed: 3,
+                }),
+                ..TrainingConfig::default()
+            };
+            let mut trainer = Trainer::new(TRMModel::new(model_config.clone()), train_config);
+            let metrics = trainer.train(&task).unwrap();
+
+            assert_eq!(metrics.losses.len(), 3);
+            assert!(metrics.losses.iter().all(|loss| loss.is_finite()));
+        }
+    }
+
+    #[test]
+    fn test_train_rejects_mismatched_symmetry() {
+        use crate::data::Symmetry;
+
+        let model_config = TRMConfig {
+            input_dim: 5,
+            output_dim: 5,
+            hidden_dim: 8,
+            latent_dim: 8,
+            l_layers: 1,
+            h_cycles: 1,
+            l_cycles: 1,
+        };
+        let train_config = TrainingConfig {
+            epochs: 1,
+            augmentation: Some(AugmentationConfig {
+                symmetry: Symmetry::maze(7, 7),
+                mode: AugmentationMode::OnTheFly,
+                seed: 0,
+            }),
+            ..TrainingConfig::default()
+        };
+        let mut trainer = Trainer::new(TRMModel::new(model_config), train_config);
+
+        // 5-element copy examples do not fit a 7x7 grid
+        let task = CopyTask::new(3, 5);
+        let err = trainer.train(&task).err().unwrap();
+        assert!(err.to_string().contains("example 0 of size 5/5"));
+    }
+
+    #[test]
+    fn test_evaluate_cycles() {
+        let model_config = TRMConfig {
+            input_dim: 5,
+            output_dim: 5,
+            hidden_dim: 8,
+            latent_dim: 8,
+            l_layers: 2,
+            h_cycles: 3,
+            l_cycles: 1,
+        };
+
+        let model = TRMModel::new(model_config);
+        let mut trainer = Trainer::new(model, TrainingConfig::default());
+
+        let task = CopyTask::new(5, 5);
+        let cycles = trainer.evaluate_cycles(task.examples());
+
+        assert_eq!(cycles.len(), 3);
+        assert_eq!(cycles[0].cycle, 1);
+        assert_eq!(cycles[2].cycle, 3);
+        for cycle in &cycles {
+            assert!(cycle.loss >= 0.0);
+            assert!((0.0..=1.0).contains(&cycle.accuracy));
+        }
+
+        // The last cycle is the model's actual output
+        let final_loss = trainer.evaluate(task.examples());
+        assert!((cycles[2].loss - final_loss).abs() < 1e-6);
+    }
 }
@@ -0,0 +1,388 @@
+//! RGB raster images with PPM and PNG output
+//!
+//! A small, dependency-free canvas for reports and quick visual diffs. PNGs
+//! are written uncompressed (stored deflate blocks), which keeps the encoder
+//! tiny at the cost of file size.
+
+use super::{Result, TRMError};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// An RGB colour
+pub type Rgb = [u8; 3];
+
+const PNG_SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+/// Largest payload of one stored deflate block
+const MAX_STORED_BLOCK: usize = 65_535;
+
+/// An 8-bit RGB image, stored row by row
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Create an image filled with one colour
+    pub fn new(width: usize, height: usize, fill: Rgb) -> Self {
+        Self {
+            width,
+            height,
+            pixels: fill.repeat(width * height),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Raw RGB bytes, row by row
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Colour at `(x, y)`, or `None` outside the image
+    pub fn get(&self, x: usize, y: usize) -> Option<Rgb> {
+        (x < self.width && y < self.height).then(|| {
+            let i = (y * self.width + x) * 3;
+            [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2]]
+        })
+    }
+
+    /// Set the colour at `(x, y)`; pixels outside the image are ignored
+    pub fn set(&mut self, x: usize, y: usize, color: Rgb) {
+        self.blend(x, y, color, 1.0);
+    }
+
+    /// Mix `color` into `(x, y)` with opacity `alpha` in `[0, 1]`
+    pub fn blend(&mut self, x: usize, y: usize, color: Rgb, alpha: f32) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let alpha = alpha.clamp(0.0, 1.0);
+        let i = (y * self.width + x) * 3;
+        for (channel, &value) in self.pixels[i..i + 3].iter_mut().zip(&color) {
+            *channel = (*channel as f32 * (1.0 - alpha) + value as f32 * alpha).round() as u8;
+        }
+    }
+
+    /// Fill a rectangle, clipped to the image
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: Rgb) {
+        self.blend_rect(x, y, width, height, color, 1.0);
+    }
+
+    /// Mix `color` into a rectangle with opacity `alpha`, clipped to the image
+    pub fn blend_rect(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        color: Rgb,
+        alpha: f32,
+    ) {
+        for row in y..(y + height).min(self.height) {
+            for col in x..(x + width).min(self.width) {
+                self.blend(col, row, color, alpha);
+            }
+        }
+    }
+
+    /// Copy `other` with its top-left corner at `(x, y)`, clipped to the image
+    pub fn draw_image(&mut self, other: &Image, x: usize, y: usize) {
+        for row in 0..other.height.min(self.height.saturating_sub(y)) {
+            let cols = other.width.min(self.width.saturating_sub(x));
+            let src = row * other.width * 3;
+            let dst = ((y + row) * self.width + x) * 3;
+            self.pixels[dst..dst + cols * 3].copy_from_slice(&other.pixels[src..src + cols * 3]);
+        }
+    }
+
+    /// Lay out images in a grid of `columns`, `gap` pixels apart
+    ///
+    /// Every grid cell is as large as the largest image; smaller images sit
+    /// in the top-left corner of their cell.
+    pub fn montage(images: &[Image], columns: usize, gap: usize, background: Rgb) -> Image {
+        let columns = columns.clamp(1, images.len().max(1));
+        let rows = (images.len() + columns - 1) / columns;
+        let cell_w = images.iter().map(|i| i.width).max().unwrap_or(0);
+        let cell_h = images.iter().map(|i| i.height).max().unwrap_or(0);
+        let mut canvas = Image::new(
+            columns * cell_w + (columns + 1) * gap,
+            rows * cell_h + (rows + 1) * gap,
+            background,
+        );
+        for (index, image) in images.iter().enumerate() {
+            let x = gap + (index % columns) * (cell_w + gap);
+            let y = gap + (index / columns) * (cell_h + gap);
+            canvas.draw_image(image, x, y);
+        }
+        canvas
+    }
+
+    /// Write as binary PPM (`P6`)
+    pub fn write_ppm<W: Write>(&self, mut writer: W) -> Result<()> {
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+        writer.write_all(&self.pixels)?;
+        Ok(())
+    }
+
+    /// Write as an uncompressed 8-bit RGB PNG
+    pub fn write_png<W: Write>(&self, mut writer: W) -> Result<()> {
+        let (width, height) = match (u32::try_from(self.width), u32::try_from(self.height)) {
+            (Ok(w), Ok(h)) if w > 0 && h > 0 => (w, h),
+            _ => {
+                return Err(TRMError::InvalidData(format!(
+                    "cannot write a {}x{} image as PNG",
+                    self.width, self.height
+                )))
+            }
+        };
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&width.to_be_bytes());
+        header.extend_from_slice(&height.to_be_bytes());
+        // 8-bit truecolour, deflate, adaptive filtering, no interlace
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        // Each scanline starts with filter type 0 (none)
+        let mut scanlines = Vec::with_capacity(self.height * (self.width * 3 + 1));
+        for row in self.pixels.chunks_exact(self.width * 3) {
+            scanlines.push(0);
+            scanlines.extend_from_slice(row);
+        }
+
+        writer.write_all(PNG_SIGNATURE)?;
+        write_chunk(&mut writer, b"IHDR", &header)?;
+        write_chunk(&mut writer, b"IDAT", &zlib_stored(&scanlines))?;
+        write_chunk(&mut writer, b"IEND", &[])?;
+        Ok(())
+    }
+
+    /// Save as PPM or PNG, chosen by the file extension
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        let mut writer = BufWriter::new(File::create(path)?);
+        match extension.as_deref() {
+            Some("ppm") => self.write_ppm(&mut writer)?,
+            Some("png") => self.write_png(&mut writer)?,
+            _ => {
+                return Err(TRMError::InvalidData(format!(
+                    "unknown image format for {} (expected .png or .ppm)",
+                    path.display()
+                )))
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Colour for a value in `[0, 1]` on a blue–yellow–red scale
+pub fn heat_color(value: f32) -> Rgb {
+    const STOPS: [Rgb; 3] = [[49, 54, 149], [254, 224, 144], [165, 0, 38]];
+    let t = if value.is_nan() {
+        0.0
+    } else {
+        value.clamp(0.0, 1.0) * 2.0
+    };
+    let (low, high, t) = if t < 1.0 {
+        (STOPS[0], STOPS[1], t)
+    } else {
+        (STOPS[1], STOPS[2], t - 1.0)
+    };
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    [
+        mix(low[0], high[0]),
+        mix(low[1], high[1]),
+        mix(low[2], high[2]),
+    ]
+}
+
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    let mut crc = Crc32::new();
+    crc.update(kind);
+    crc.update(data);
+    writer.write_all(&crc.finish().to_be_bytes())?;
+    Ok(())
+}
+
+/// Wrap `data` in a zlib stream of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let blocks = ((data.len() + MAX_STORED_BLOCK - 1) / MAX_STORED_BLOCK).max(1);
+    let mut out = Vec::with_capacity(data.len() + blocks * 5 + 6);
+    // Deflate with a 32K window, no preset dictionary, fastest level
+    out.extend_from_slice(&[0x78, 0x01]);
+    let mut chunks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if chunks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let len = chunk.len() as u16;
+        out.push(u8::from(last));
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// Adler-32 checksum, as used by zlib
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65_521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 bytes is the most that can be summed before `b` may overflow
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+/// CRC-32 (IEEE), as used by PNG chunks
+struct Crc32 {
+    table: [u32; 256],
+    value: u32,
+}
+
+impl Crc32 {
+    fn new() -> Self {
+        let mut table = [0u32; 256];
+        for (n, entry) in table.iter_mut().enumerate() {
+            let mut c = n as u32;
+            for _ in 0..8 {
+                c = if c & 1 == 1 {
+                    0xedb8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+            }
+            *entry = c;
+        }
+        Self {
+            table,
+            value: 0xffff_ffff,
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.value =
+                self.table[((self.value ^ byte as u32) & 0xff) as usize] ^ (self.value >> 8);
+        }
+    }
+
+    fn finish(&self) -> u32 {
+        self.value ^ 0xffff_ffff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Rgb = [255, 0, 0];
+    const WHITE: Rgb = [255, 255, 255];
+
+    #[test]
+    fn test_checksums() {
+        let mut crc = Crc32::new();
+        crc.update(b"123456789");
+        assert_eq!(crc.finish(), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        assert_eq!(adler32(&[]), 1);
+    }
+
+    #[test]
+    fn test_drawing_and_blending() {
+        let mut image = Image::new(4, 3, WHITE);
+        image.fill_rect(1, 1, 10, 10, RED);
+        assert_eq!(image.get(0, 0), Some(WHITE));
+        assert_eq!(image.get(3, 2), Some(RED));
+        assert_eq!(image.get(4, 0), None);
+
+        image.blend(0, 0, [0, 0, 0], 0.5);
+        assert_eq!(image.get(0, 0), Some([128, 128, 128]));
+        // Out of bounds is ignored
+        image.set(9, 9, RED);
+
+        assert_eq!(heat_color(0.0), [49, 54, 149]);
+        assert_eq!(heat_color(1.0), [165, 0, 38]);
+        assert_eq!(heat_color(f32::NAN), heat_color(0.0));
+    }
+
+    #[test]
+    fn test_ppm_output() {
+        let image = Image::new(2, 1, RED);
+        let mut bytes = Vec::new();
+        image.write_ppm(&mut bytes).unwrap();
+        assert_eq!(bytes, b"P6\n2 1\n255\n\xff\x00\x00\xff\x00\x00");
+    }
+
+    #[test]
+    fn test_png_structure() {
+        let image = Image::new(3, 2, RED);
+        let mut bytes = Vec::new();
+        image.write_png(&mut bytes).unwrap();
+        assert_eq!(&bytes[..8], PNG_SIGNATURE);
+        assert_eq!(&bytes[12..16], b"IHDR");
+        assert_eq!(&bytes[16..24], &[0, 0, 0, 3, 0, 0, 0, 2]);
+        // IEND chunk with its fixed CRC
+        assert_eq!(
+            &bytes[bytes.len() - 12..],
+            b"\x00\x00\x00\x00IEND\xae\x42\x60\x82"
+        );
+        assert!(Image::new(0, 2, RED).write_png(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_stored_blocks_split_large_data() {
+        let data = vec![7u8; MAX_STORED_BLOCK + 10];
+        let stream = zlib_stored(&data);
+        assert_eq!(stream.len(), 2 + 2 * 5 + data.len() + 4);
+        // First block is not final and full; second is final with 10 bytes
+        assert_eq!(&stream[2..5], &[0, 0xff, 0xff]);
+        let second = 2 + 5 + MAX_STORED_BLOCK;
+        assert_eq!(&stream[second..second + 5], &[1, 10, 0, 0xf5, 0xff]);
+        assert_eq!(
+            zlib_stored(&[]),
+            vec![0x78, 1, 1, 0, 0, 0xff, 0xff, 0, 0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn test_montage_layout() {
+        let images = vec![
+            Image::new(2, 2, RED),
+            Image::new(3, 1, RED),
+            Image::new(1, 1, RED),
+        ];
+        let montage = Image::montage(&images, 2, 1, WHITE);
+        assert_eq!((montage.width(), montage.height()), (2 * 3 + 3, 2 * 2 + 3));
+        assert_eq!(montage.get(1, 1), Some(RED));
+        assert_eq!(montage.get(0, 0), Some(WHITE));
+        // Third image starts the second row
+        assert_eq!(montage.get(1, 4), Some(RED));
+        assert_eq!(montage.get(2, 4), Some(WHITE));
+    }
+}
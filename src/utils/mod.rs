@@ -1,12 +1,24 @@
 //! Utility functions and common types
 
+pub mod image;
+pub mod npy;
+
+use std::io::Read;
 use thiserror::Error;
 
 /// Custom error type for TRM operations
 #[derive(Debug, Error)]
 pub enum TRMError {
-    #[error("Invalid input dimension: expected {expected}, got {actual}")]
-    DimensionMismatch { expected: usize, actual: usize },
+    #[error(
+        "Invalid dimension: expected {expected}, got {actual}{}",
+        .line.map(|l| format!(" (line {})", l)).unwrap_or_default()
+    )]
+    DimensionMismatch {
+        expected: usize,
+        actual: usize,
+        /// Line (or record) number in the source file, if any
+        line: Option<usize>,
+    },
 
     #[error("Model not initialized")]
     NotInitialized,
@@ -14,6 +26,9 @@ pub enum TRMError {
     #[error("Training error: {0}")]
     TrainingError(String),
 
+    #[error("Invalid data: {0}")]
+    InvalidData(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -23,3 +38,20 @@ pub enum TRMError {
 
 /// Result type alias using TRMError
 pub type Result<T> = std::result::Result<T, TRMError>;
+
+/// Read exactly `len` bytes, growing the buffer only as data arrives
+///
+/// Lengths taken from file headers can't force a huge allocation this way;
+/// running out of data first is an `InvalidData` error.
+pub(crate) fn read_bytes<R: Read>(reader: &mut R, len: usize) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.by_ref().take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(TRMError::InvalidData(format!(
+            "expected {} more bytes, found {}",
+            len,
+            bytes.len()
+        )));
+    }
+    Ok(bytes)
+}
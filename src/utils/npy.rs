@@ -0,0 +1,334 @@
+//! NumPy `.npy` and `.npz` reading and writing
+//!
+//! Reads little-endian `float32`/`float64` arrays in C or Fortran order and
+//! writes `float32` arrays in C order (format version 1.0). `.npz` archives are
+//! zip files holding one `<name>.npy` entry per array, as written by `np.savez`.
+
+use super::{read_bytes, Result, TRMError};
+use ndarray::{Array2, ArrayD, ArrayViewD, IxDyn, ShapeBuilder};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Cursor, Read, Seek, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 6] = b"\x93NUMPY";
+
+/// Read a `.npy` array of any rank
+pub fn read_npy<R: Read>(mut reader: R) -> Result<ArrayD<f32>> {
+    let mut magic = [0u8; 6];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid("not a .npy file"));
+    }
+
+    let mut version = [0u8; 2];
+    reader.read_exact(&mut version)?;
+    let header_len = match version[0] {
+        1 => {
+            let mut len = [0u8; 2];
+            reader.read_exact(&mut len)?;
+            u16::from_le_bytes(len) as usize
+        }
+        2 | 3 => {
+            let mut len = [0u8; 4];
+            reader.read_exact(&mut len)?;
+            u32::from_le_bytes(len) as usize
+        }
+        major => return Err(invalid(&format!("unsupported .npy version {}", major))),
+    };
+
+    let header = read_bytes(&mut reader, header_len)?;
+    let header = String::from_utf8_lossy(&header);
+    let (descr, fortran_order, shape) = parse_header(&header)?;
+
+    let width = match descr.as_str() {
+        "<f4" => 4,
+        "<f8" => 8,
+        other => return Err(invalid(&format!("unsupported dtype {:?}", other))),
+    };
+    let len = shape
+        .iter()
+        .try_fold(width, |len: usize, &dim| len.checked_mul(dim))
+        .ok_or_else(|| invalid(&format!("shape {:?} is too large", shape)))?;
+
+    let bytes = read_bytes(&mut reader, len)?;
+    let data: Vec<f32> = if width == 4 {
+        bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().expect("chunk of 4")))
+            .collect()
+    } else {
+        bytes
+            .chunks_exact(8)
+            .map(|b| f64::from_le_bytes(b.try_into().expect("chunk of 8")) as f32)
+            .collect()
+    };
+
+    let array = if fortran_order {
+        ArrayD::from_shape_vec(IxDyn(&shape).f(), data)
+    } else {
+        ArrayD::from_shape_vec(IxDyn(&shape), data)
+    }
+    .map_err(|e| invalid(&e.to_string()))?;
+
+    Ok(array.as_standard_layout().into_owned())
+}
+
+/// Write an array of any rank as little-endian `float32` in C order
+pub fn write_npy<W: Write>(mut writer: W, array: ArrayViewD<f32>) -> Result<()> {
+    let shape = match array.shape() {
+        [] => "()".to_string(),
+        [n] => format!("({},)", n),
+        dims => format!(
+            "({})",
+            dims.iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let mut header = format!(
+        "{{'descr': '<f4', 'fortran_order': False, 'shape': {}, }}",
+        shape
+    );
+
+    // Pad so the data starts on a 64-byte boundary, ending with a newline
+    let prefix = MAGIC.len() + 2 + 2;
+    let padding = (64 - (prefix + header.len() + 1) % 64) % 64;
+    header.push_str(&" ".repeat(padding));
+    header.push('\n');
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[1, 0])?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    for value in array.as_standard_layout().iter() {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Convert an array to 2-D: vectors become a single row, scalars a 1x1 matrix
+pub fn to_array2(array: ArrayD<f32>) -> Result<Array2<f32>> {
+    let shape = array.shape().to_vec();
+    let (rows, cols) = match shape.as_slice() {
+        [] => (1, 1),
+        [n] => (1, *n),
+        [r, c] => (*r, *c),
+        _ => {
+            return Err(invalid(&format!(
+                "expected a 1-D or 2-D array, got shape {:?}",
+                shape
+            )))
+        }
+    };
+    array
+        .into_shape((rows, cols))
+        .map_err(|e| invalid(&e.to_string()))
+}
+
+/// Load a 2-D array from a `.npy` file
+pub fn load_npy<P: AsRef<Path>>(path: P) -> Result<Array2<f32>> {
+    to_array2(read_npy(BufReader::new(File::open(path)?))?)
+}
+
+/// Save a 2-D array to a `.npy` file
+pub fn save_npy<P: AsRef<Path>>(path: P, array: &Array2<f32>) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_npy(&mut writer, array.view().into_dyn())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read all arrays from a `.npz` archive, keyed by name without `.npy`
+pub fn read_npz<R: Read + Seek>(reader: R) -> Result<BTreeMap<String, ArrayD<f32>>> {
+    let mut archive = zip::ZipArchive::new(reader).map_err(zip_error)?;
+    let mut arrays = BTreeMap::new();
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i).map_err(zip_error)?;
+        let name = entry.name().trim_end_matches(".npy").to_string();
+        arrays.insert(name, read_npy(entry)?);
+    }
+    Ok(arrays)
+}
+
+/// Write named arrays to an uncompressed `.npz` archive
+pub fn write_npz<W: Write + Seek>(writer: W, arrays: &[(&str, ArrayViewD<f32>)]) -> Result<()> {
+    let mut archive = zip::ZipWriter::new(writer);
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    for (name, array) in arrays {
+        archive
+            .start_file(format!("{}.npy", name), options)
+            .map_err(zip_error)?;
+        write_npy(&mut archive, array.view())?;
+    }
+    archive.finish().map_err(zip_error)?;
+    Ok(())
+}
+
+/// Load all arrays from a `.npz` file
+pub fn load_npz<P: AsRef<Path>>(path: P) -> Result<BTreeMap<String, ArrayD<f32>>> {
+    read_npz(BufReader::new(File::open(path)?))
+}
+
+/// Save named arrays to a `.npz` file
+pub fn save_npz<P: AsRef<Path>>(path: P, arrays: &[(&str, ArrayViewD<f32>)]) -> Result<()> {
+    // Build in memory: the zip writer needs to seek, and BufWriter<File> cannot
+    let mut buffer = Cursor::new(Vec::new());
+    write_npz(&mut buffer, arrays)?;
+    File::create(path)?.write_all(buffer.get_ref())?;
+    Ok(())
+}
+
+/// Extract `descr`, `fortran_order` and `shape` from a `.npy` header dict
+fn parse_header(header: &str) -> Result<(String, bool, Vec<usize>)> {
+    let value_after = |key: &str| -> Result<&str> {
+        let start = header
+            .find(&format!("'{}'", key))
+            .ok_or_else(|| invalid(&format!("missing '{}' in .npy header", key)))?;
+        let rest = &header[start + key.len() + 2..];
+        let colon = rest
+            .find(':')
+            .ok_or_else(|| invalid("malformed .npy header"))?;
+        Ok(rest[colon + 1..].trim_start())
+    };
+
+    let descr = value_after("descr")?;
+    let descr = descr
+        .strip_prefix('\'')
+        .and_then(|d| d.split('\'').next())
+        .ok_or_else(|| invalid("malformed dtype in .npy header"))?
+        .to_string();
+
+    let fortran_order = value_after("fortran_order")?.starts_with("True");
+
+    let shape = value_after("shape")?;
+    let shape = shape
+        .strip_prefix('(')
+        .and_then(|s| s.split(')').next())
+        .ok_or_else(|| invalid("malformed shape in .npy header"))?;
+    let shape = shape
+        .split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(|d| d.parse::<usize>().map_err(|e| invalid(&e.to_string())))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok((descr, fortran_order, shape))
+}
+
+fn invalid(message: &str) -> TRMError {
+    TRMError::InvalidData(message.to_string())
+}
+
+fn zip_error(error: zip::result::ZipError) -> TRMError {
+    TRMError::InvalidData(format!("npz archive: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{array, Array1};
+
+    fn npy_bytes(header: &str, data: &[u8]) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&[1, 0]);
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn test_npy_round_trip() {
+        let array = array![[1.0f32, 2.0, 3.0], [4.0, 5.0, 6.0]];
+        let mut bytes = Vec::new();
+        write_npy(&mut bytes, array.view().into_dyn()).unwrap();
+
+        // Data starts on a 64-byte boundary
+        assert_eq!((bytes.len() - 6 * 4) % 64, 0);
+
+        let loaded = to_array2(read_npy(bytes.as_slice()).unwrap()).unwrap();
+        assert_eq!(loaded, array);
+    }
+
+    #[test]
+    fn test_read_float64_fortran_order() {
+        // [[1, 2, 3], [4, 5, 6]] stored column by column
+        let data: Vec<u8> = [1.0f64, 4.0, 2.0, 5.0, 3.0, 6.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let bytes = npy_bytes(
+            "{'descr': '<f8', 'fortran_order': True, 'shape': (2, 3), }\n",
+            &data,
+        );
+
+        let loaded = to_array2(read_npy(bytes.as_slice()).unwrap()).unwrap();
+        assert_eq!(loaded, array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+    }
+
+    #[test]
+    fn test_one_dimensional_and_rejects_big_endian() {
+        let vector = Array1::from(vec![1.0f32, 2.0]);
+        let mut bytes = Vec::new();
+        write_npy(&mut bytes, vector.view().into_dyn()).unwrap();
+        let loaded = read_npy(bytes.as_slice()).unwrap();
+        assert_eq!(loaded.shape(), &[2]);
+        assert_eq!(to_array2(loaded).unwrap(), array![[1.0, 2.0]]);
+
+        let bytes = npy_bytes(
+            "{'descr': '>f4', 'fortran_order': False, 'shape': (1,), }\n",
+            &[0; 4],
+        );
+        assert!(read_npy(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_rejects_huge_and_truncated_shapes() {
+        for shape in [
+            "(4294967295, 4294967295, 4294967295)",
+            "(100000, 100000)",
+            "(3,)",
+        ] {
+            let header = format!(
+                "{{'descr': '<f4', 'fortran_order': False, 'shape': {}, }}\n",
+                shape
+            );
+            let bytes = npy_bytes(&header, &[0; 8]);
+            match read_npy(bytes.as_slice()) {
+                Err(TRMError::InvalidData(_)) => {}
+                other => panic!("{}: expected invalid data, got {:?}", shape, other.is_ok()),
+            }
+        }
+
+        // A header length beyond the end of the file
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend([2, 0]);
+        bytes.extend(u32::MAX.to_le_bytes());
+        assert!(read_npy(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_npz_round_trip() {
+        let inputs = array![[1.0f32, 2.0], [3.0, 4.0]];
+        let bias = Array1::from(vec![0.5f32, -0.5, 0.0]);
+
+        let mut buffer = Cursor::new(Vec::new());
+        write_npz(
+            &mut buffer,
+            &[
+                ("inputs", inputs.view().into_dyn()),
+                ("bias", bias.view().into_dyn()),
+            ],
+        )
+        .unwrap();
+
+        buffer.set_position(0);
+        let arrays = read_npz(buffer).unwrap();
+        assert_eq!(arrays.len(), 2);
+        assert_eq!(arrays["inputs"], inputs.into_dyn());
+        assert_eq!(arrays["bias"], bias.into_dyn());
+    }
+}
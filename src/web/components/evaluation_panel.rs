@@ -2,6 +2,8 @@
 
 use crate::data::tasks::CopyTask;
 use crate::model::{TRMConfig, TRMModel};
+use crate::training::metrics::{MeanSquaredError, Metric, ToleranceAccuracy};
+use crate::training::EvaluationReport;
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq)]
@@ -18,9 +20,7 @@ pub struct EvaluationPanel {
 
 #[derive(Clone)]
 struct EvalResults {
-    total: usize,
-    correct: usize,
-    average_loss: f32,
+    report: EvaluationReport,
     examples: Vec<ExampleResult>,
 }
 
@@ -82,42 +82,29 @@ impl Component for EvaluationPanel {
                     let task = CopyTask::new(num_examples, 5);
                     let examples = task.examples();
 
-                    let mut total_loss = 0.0;
-                    let mut correct = 0;
-                    let mut eval_examples = Vec::new();
-
-                    for example in examples {
-                        let prediction = model.forward(&example.input);
-
-                        // Compute loss
-                        let diff = &prediction - &example.target;
-                        let loss = diff.mapv(|x| x * x).sum() / prediction.len() as f32;
-                        total_loss += loss;
-
-                        // Check correctness (within 0.5 threshold)
-                        let max_diff = diff
-                            .mapv(|x| x.abs())
-                            .iter()
-                            .cloned()
-                            .fold(0.0f32, f32::max);
-                        let is_correct = max_diff < 0.5;
-                        if is_correct {
-                            correct += 1;
-                        }
-
-                        // Store all results for scrollable display
-                        eval_examples.push(ExampleResult {
+                    let accuracy = ToleranceAccuracy::default();
+                    let accuracy_name = accuracy.name();
+                    let report = EvaluationReport::evaluate(
+                        model,
+                        examples,
+                        &[Box::new(MeanSquaredError), Box::new(accuracy)],
+                    );
+
+                    // Store all results for scrollable display
+                    let eval_examples = examples
+                        .iter()
+                        .zip(&report.examples)
+                        .enumerate()
+                        .map(|(i, (example, evaluation))| ExampleResult {
                             input: example.input.iter().cloned().collect(),
                             target: example.target.iter().cloned().collect(),
-                            prediction: prediction.iter().cloned().collect(),
-                            correct: is_correct,
-                        });
-                    }
+                            prediction: evaluation.prediction.iter().cloned().collect(),
+                            correct: report.example_score(i, &accuracy_name) == Some(1.0),
+                        })
+                        .collect();
 
                     self.results = Some(EvalResults {
-                        total: examples.len(),
-                        correct,
-                        average_loss: total_loss / examples.len() as f32,
+                        report,
                         examples: eval_examples,
                     });
 
@@ -187,13 +174,13 @@ impl Component for EvaluationPanel {
                                 <div class="metric">
                                     <span class="metric-label">{ "Accuracy:" }</span>
                                     <span class="metric-value">
-                                        { format!("{}/{} ({:.1}%)", results.correct, results.total,
-                                            (results.correct as f32 / results.total as f32) * 100.0) }
+                                        { format!("{}/{} ({:.1}%)", results.correct(), results.report.num_examples(),
+                                            (results.correct() as f32 / results.report.num_examples() as f32) * 100.0) }
                                     </span>
                                 </div>
                                 <div class="metric">
                                     <span class="metric-label">{ "Average Loss:" }</span>
-                                    <span class="metric-value">{ format!("{:.6}", results.average_loss) }</span>
+                                    <span class="metric-value">{ format!("{:.6}", results.report.metric("mse").unwrap_or_default()) }</span>
                                 </div>
                             </div>
 
@@ -244,6 +231,12 @@ impl Component for EvaluationPanel {
     }
 }
 
+impl EvalResults {
+    fn correct(&self) -> usize {
+        self.examples.iter().filter(|ex| ex.correct).count()
+    }
+}
+
 impl EvaluationPanel {
     fn format_vector(vec: &[f32]) -> String {
         format!(